@@ -3,12 +3,16 @@
 use jclassfile::{
     class_file::{ClassFile, ClassFlags},
     constant_pool::ConstantPool,
-    fields::FieldFlags,
-    methods::MethodFlags,
+    fields::{FieldFlags, FieldInfo},
+    methods::{MethodFlags, MethodInfo},
     attributes::Attribute,
 };
-use mermaid_parser::types::{Class, Member, Method, Attribute as MermaidAttribute, Visibility, Parameter, TypeNotation};
-use crate::descriptor::{parse_field_descriptor, parse_method_descriptor};
+use crate::diagram::{Class, Member, Method, Attribute as MermaidAttribute};
+use mermaid_parser::types::{Visibility, Parameter, TypeNotation};
+use crate::descriptor::{
+    parse_field_descriptor, parse_field_signature, parse_method_descriptor, parse_method_signature,
+    referenced_class_paths,
+};
 
 /// Get a UTF-8 string from the constant pool by index
 pub fn get_utf8(constant_pool: &[ConstantPool], index: u16) -> Option<&str> {
@@ -58,14 +62,29 @@ pub fn get_superclass_name(class_file: &ClassFile) -> Option<String> {
         return None; // No superclass (only for Object)
     }
 
-    let constant_pool = class_file.constant_pool();
-    let super_name = get_class_name_from_index(constant_pool, super_class_index)?;
+    // Skip java.lang.Object, java.lang.Enum and java.lang.Record as they're
+    // implicit, but not classes of the same simple name in other packages
+    let full_name = get_superclass_full_name(class_file)?;
+    if matches!(full_name.as_str(), "java/lang/Object" | "java/lang/Enum" | "java/lang/Record") {
+        return None;
+    }
 
-    // Skip java.lang.Object and java.lang.Enum as they're implicit
-    if super_name == "Object" || super_name == "Enum" {
-        None
+    get_class_name_from_index(class_file.constant_pool(), super_class_index)
+}
+
+/// Get the fully qualified superclass name (e.g., "com/example/Base")
+/// Returns None if the class has no superclass
+pub fn get_superclass_full_name(class_file: &ClassFile) -> Option<String> {
+    let super_class_index = class_file.super_class();
+    if super_class_index == 0 {
+        return None;
+    }
+
+    let constant_pool = class_file.constant_pool();
+    if let Some(ConstantPool::Class { name_index }) = constant_pool.get(super_class_index as usize) {
+        get_utf8(constant_pool, *name_index).map(|s| s.to_string())
     } else {
-        Some(super_name)
+        None
     }
 }
 
@@ -80,6 +99,99 @@ pub fn get_interface_names(class_file: &ClassFile) -> Vec<String> {
         .collect()
 }
 
+/// Get the fully qualified names (in Java format, e.g. "com/example/MyClass")
+/// of the interfaces a class implements or an interface extends
+pub fn get_interface_full_names(class_file: &ClassFile) -> Vec<String> {
+    let constant_pool = class_file.constant_pool();
+    class_file
+        .interfaces()
+        .iter()
+        .filter_map(|&interface_index| match constant_pool.get(interface_index as usize) {
+            Some(ConstantPool::Class { name_index }) => get_utf8(constant_pool, *name_index).map(str::to_string),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Get the names of the classes nested directly in this one (simple names
+/// with `.` between outer and inner, e.g. "Tree.Node") from the
+/// `InnerClasses` attribute. Anonymous and local classes are left out.
+pub fn get_nested_class_names(class_file: &ClassFile) -> Vec<String> {
+    let constant_pool = class_file.constant_pool();
+    let this_class = class_file.this_class();
+
+    class_file
+        .attributes()
+        .iter()
+        .filter_map(|attr| match attr {
+            Attribute::InnerClasses { classes } => Some(classes),
+            _ => None,
+        })
+        .flatten()
+        // Anonymous and local classes have no outer class entry
+        .filter(|record| record.outer_class_info_index() == this_class)
+        .filter_map(|record| get_class_name_from_index(constant_pool, record.inner_class_info_index()))
+        .filter(|name| {
+            let last = name.rsplit('.').next().unwrap_or(name);
+            !last.chars().all(|ch| ch.is_ascii_digit())
+        })
+        .collect()
+}
+
+/// Get the names of the classes a sealed class or interface permits to extend
+/// it (simple names with `.` between outer and inner, e.g. "Shape.Circle")
+/// from the `PermittedSubclasses` attribute. Empty unless the class is sealed.
+pub fn get_permitted_subclasses(class_file: &ClassFile) -> Vec<String> {
+    let constant_pool = class_file.constant_pool();
+    class_file
+        .attributes()
+        .iter()
+        .filter_map(|attr| match attr {
+            Attribute::PermittedSubclasses { classes } => Some(classes),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|&class_index| get_class_name_from_index(constant_pool, class_index))
+        .collect()
+}
+
+/// Check if a class or interface is sealed, i.e. it lists the classes
+/// permitted to extend it
+pub fn is_sealed(class_file: &ClassFile) -> bool {
+    class_file
+        .attributes()
+        .iter()
+        .any(|attr| matches!(attr, Attribute::PermittedSubclasses { .. }))
+}
+
+/// Get the name of the class enclosing this one (a simple name with `.`
+/// between outer and inner, e.g. "Tree" for "Tree.Node") from the
+/// `InnerClasses` attribute, or the `EnclosingMethod` attribute of local and
+/// anonymous classes. Returns None for top level classes.
+pub fn get_enclosing_class_name(class_file: &ClassFile) -> Option<String> {
+    let constant_pool = class_file.constant_pool();
+    let this_class = class_file.this_class();
+
+    let outer_class_index = class_file
+        .attributes()
+        .iter()
+        .find_map(|attr| match attr {
+            Attribute::InnerClasses { classes } => classes
+                .iter()
+                .find(|record| record.inner_class_info_index() == this_class)
+                .map(|record| record.outer_class_info_index())
+                .filter(|&index| index != 0),
+            _ => None,
+        })
+        .or_else(|| {
+            class_file.attributes().iter().find_map(|attr| match attr {
+                Attribute::EnclosingMethod { class_index, .. } => Some(*class_index),
+                _ => None,
+            })
+        })?;
+    get_class_name_from_index(constant_pool, outer_class_index)
+}
+
 /// Extract package name from a fully qualified class name
 /// e.g., "com/example/MyClass" -> "com/example"
 pub fn get_package_name(full_class_name: &str) -> &str {
@@ -90,6 +202,97 @@ pub fn get_package_name(full_class_name: &str) -> &str {
     }
 }
 
+/// The least visible members shown in the diagram. Variants are ordered from
+/// least to most visible, so a member is shown when its visibility is at
+/// least the threshold.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MinVisibility {
+    /// Show every member
+    #[default]
+    Private,
+    /// Hide private members
+    Package,
+    /// Only show public and protected members
+    Protected,
+    /// Only show public members
+    Public,
+}
+
+impl MinVisibility {
+    /// Check if a member with the given visibility is shown. Members without
+    /// one (enum constants and record components) always are.
+    pub fn allows(self, visibility: Visibility) -> bool {
+        let level = match visibility {
+            Visibility::Private => MinVisibility::Private,
+            Visibility::Package => MinVisibility::Package,
+            Visibility::Protected => MinVisibility::Protected,
+            Visibility::Public => MinVisibility::Public,
+            Visibility::Unspecified => return true,
+        };
+        level >= self
+    }
+}
+
+/// Order of the attributes and of the methods of a class. Attributes are
+/// listed before methods whatever the order, and enum constants before both.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MemberOrder {
+    /// As declared in the classfile
+    #[default]
+    Declaration,
+    /// By name
+    Alpha,
+    /// Public members first, then protected, package and private ones, each
+    /// by name
+    Visibility,
+}
+
+impl MemberOrder {
+    /// Sort members in place. The sort is stable, so overloads keep their
+    /// declaration order.
+    pub fn sort(self, members: &mut [Member]) {
+        fn name<'a>(member: &'a Member) -> &'a str {
+            match member {
+                Member::Attribute(attribute) => attribute.name.as_ref(),
+                Member::Method(method) => method.name.as_ref(),
+            }
+        }
+        fn rank(member: &Member) -> u8 {
+            let visibility = match member {
+                Member::Attribute(attribute) => &attribute.visibility,
+                Member::Method(method) => &method.visibility,
+            };
+            match visibility {
+                // Record components are public through their accessors
+                Visibility::Public | Visibility::Unspecified => 0,
+                Visibility::Protected => 1,
+                Visibility::Package => 2,
+                Visibility::Private => 3,
+            }
+        }
+        let by_name = |a: &Member, b: &Member| {
+            name(a)
+                .to_lowercase()
+                .cmp(&name(b).to_lowercase())
+                .then_with(|| name(a).cmp(name(b)))
+        };
+        let group = |member: &Member| matches!(member, Member::Method(_));
+
+        match self {
+            MemberOrder::Declaration => {}
+            MemberOrder::Alpha => {
+                members.sort_by(|a, b| group(a).cmp(&group(b)).then_with(|| by_name(a, b)))
+            }
+            MemberOrder::Visibility => members.sort_by(|a, b| {
+                group(a)
+                    .cmp(&group(b))
+                    .then_with(|| rank(a).cmp(&rank(b)))
+                    .then_with(|| by_name(a, b))
+            }),
+        }
+    }
+}
+
 /// Convert field flags to Mermaid visibility
 pub fn field_visibility(flags: &FieldFlags) -> Visibility {
     if flags.contains(FieldFlags::ACC_PUBLIC) {
@@ -162,36 +365,209 @@ pub fn has_annotation(
     false
 }
 
+/// Check if a classfile refers to a class (given in Java format, e.g.
+/// "com/example/MyClass") anywhere in its constant pool, including in
+/// descriptors and generic signatures
+pub fn references_class(class_file: &ClassFile, full_class_name: &str) -> bool {
+    let as_type = format!("L{};", full_class_name);
+    let as_generic_type = format!("L{}<", full_class_name);
+    class_file.constant_pool().iter().any(|entry| match entry {
+        ConstantPool::Utf8 { value } => {
+            value.as_str() == full_class_name
+                || value.contains(&as_type)
+                || value.contains(&as_generic_type)
+        }
+        _ => false,
+    })
+}
+
+/// Get the names of a class's fields and methods hidden by one of the skip
+/// annotations, each with the first annotation hiding it
+pub fn get_skipped_members<'a, 'b>(
+    class_file: &'a ClassFile,
+    skip_annotations: &[Option<&'b str>],
+) -> Vec<(&'a str, &'b str)> {
+    let constant_pool = class_file.constant_pool();
+    let members = class_file
+        .fields()
+        .iter()
+        .map(|field| (field.name_index(), field.attributes()))
+        .chain(
+            class_file
+                .methods()
+                .iter()
+                .map(|method| (method.name_index(), method.attributes())),
+        );
+
+    let mut skipped = Vec::new();
+    for (name_index, attributes) in members {
+        let annotation = skip_annotations
+            .iter()
+            .flatten()
+            .find(|skip_ann| has_annotation(constant_pool, attributes, Some(skip_ann)));
+        if let Some(annotation) = annotation {
+            let name = get_utf8(constant_pool, name_index).unwrap_or("unknown");
+            skipped.push((name, *annotation));
+        }
+    }
+    skipped
+}
+
+/// Get the fully qualified names (in Java format, e.g. "com/example/MyClass")
+/// of the classes used by the parameter and return types of a class's methods
+/// and constructors, in order of appearance and with repeats. Compiler
+/// generated methods and those with any of the skip annotations are left out.
+pub fn get_method_signature_classes(
+    class_file: &ClassFile,
+    skip_annotations: &[Option<&str>],
+) -> Vec<String> {
+    let constant_pool = class_file.constant_pool();
+    let mut classes = Vec::new();
+    for method in class_file.methods() {
+        let has_skip_annotation = skip_annotations.iter().any(|skip_ann| {
+            has_annotation(constant_pool, method.attributes(), *skip_ann)
+        });
+        if has_skip_annotation {
+            continue;
+        }
+
+        let name = get_utf8(constant_pool, method.name_index()).unwrap_or("");
+        let synthetic = is_synthetic_method(method);
+        let noise = if name == "<init>" {
+            synthetic || has_synthetic_attribute(method.attributes())
+        } else {
+            is_noise_member(name, synthetic, method.attributes())
+        };
+        if noise {
+            continue;
+        }
+
+        // The generic signature also names the classes used as type
+        // arguments, followed by any thrown exceptions after a `^`
+        let signature = get_signature(constant_pool, method.attributes())
+            .or_else(|| get_utf8(constant_pool, method.descriptor_index()))
+            .unwrap_or("");
+        let signature = signature.split('^').next().unwrap_or_default();
+        classes.extend(referenced_class_paths(signature));
+    }
+    classes
+}
+
+/// Get the fully qualified names of every annotation on a field/method/class
+/// in Java format (e.g. "com/example/MyAnnotation")
+pub fn get_annotation_names(constant_pool: &[ConstantPool], attributes: &[Attribute]) -> Vec<String> {
+    let mut names = Vec::new();
+    for attr in attributes {
+        let annotations = match attr {
+            Attribute::RuntimeVisibleAnnotations { annotations, .. } => annotations,
+            Attribute::RuntimeInvisibleAnnotations { annotations } => annotations,
+            _ => continue,
+        };
+
+        for annotation in annotations {
+            if let Some(type_name) = get_annotation_type(constant_pool, annotation.type_index()) {
+                names.push(
+                    type_name
+                        .trim_start_matches('L')
+                        .trim_end_matches(';')
+                        .to_string(),
+                );
+            }
+        }
+    }
+    names
+}
+
 /// Get annotation type name from constant pool
 fn get_annotation_type(constant_pool: &[ConstantPool], type_index: u16) -> Option<String> {
     get_utf8(constant_pool, type_index).map(|s| s.to_string())
 }
 
-/// Get annotation parameter value as string from ElementValue
+/// Get a loadable constant (a string, integer, etc.) from the constant pool
+/// as a string. Strings are returned unquoted.
+fn get_constant_as_string(constant_pool: &[ConstantPool], index: u16) -> Option<String> {
+    match constant_pool.get(index as usize)? {
+        ConstantPool::Utf8 { value } => Some(value.to_string()),
+        ConstantPool::Integer { value } => Some(value.to_string()),
+        ConstantPool::Float { value } => Some(value.to_string()),
+        ConstantPool::Long { value } => Some(value.to_string()),
+        ConstantPool::Double { value } => Some(value.to_string()),
+        ConstantPool::String { string_index } => {
+            get_utf8(constant_pool, *string_index).map(|s| s.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// Render an annotation element's value as it would be written in Java,
+/// except that strings are left unquoted: enum constants keep their type
+/// (`Stage.DRAFT`), classes read `Foo.class` and arrays are enclosed in
+/// braces. Nested annotations give `None`.
 fn get_element_value_as_string(constant_pool: &[ConstantPool], element_value: &jclassfile::attributes::ElementValue) -> Option<String> {
     use jclassfile::attributes::ElementValue;
     match element_value {
         ElementValue::ConstValueIndex { const_value_index, .. } => {
-            // The const_value_index points to a constant pool entry
-            // For strings, integers, etc.
-            if let Some(cp_entry) = constant_pool.get(*const_value_index as usize) {
-                match cp_entry {
-                    ConstantPool::Utf8 { value } => Some(value.to_string()),
-                    ConstantPool::Integer { value } => Some(value.to_string()),
-                    ConstantPool::Float { value } => Some(value.to_string()),
-                    ConstantPool::Long { value } => Some(value.to_string()),
-                    ConstantPool::Double { value } => Some(value.to_string()),
-                    ConstantPool::String { string_index } => {
-                        get_utf8(constant_pool, *string_index).map(|s| s.to_string())
-                    }
-                    _ => None,
-                }
+            get_constant_as_string(constant_pool, *const_value_index)
+        }
+        ElementValue::EnumConstValue { type_name_index, const_name_index } => {
+            let type_name = parse_field_descriptor(get_utf8(constant_pool, *type_name_index)?, false);
+            let const_name = get_utf8(constant_pool, *const_name_index)?;
+            Some(format!("{}.{}", type_name, const_name))
+        }
+        ElementValue::ClassInfoIndex { class_info_index } => {
+            let class_name = parse_field_descriptor(get_utf8(constant_pool, *class_info_index)?, false);
+            Some(format!("{}.class", class_name))
+        }
+        ElementValue::ArrayValue { values } => {
+            let values: Vec<String> = values
+                .iter()
+                .filter_map(|value| get_element_value_as_string(constant_pool, value))
+                .collect();
+            Some(format!("{{{}}}", values.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+/// Render an annotation element's value as plain text for the diagram, such
+/// as a relation's label. Arrays are joined with `, ` and enum constants are
+/// given by name alone, so `{"reads", "writes"}` reads `reads, writes` and
+/// `Stage.DRAFT` reads `DRAFT`. Nested annotations give `None`.
+fn get_element_value_as_text(constant_pool: &[ConstantPool], element_value: &jclassfile::attributes::ElementValue) -> Option<String> {
+    use jclassfile::attributes::ElementValue;
+    match element_value {
+        ElementValue::EnumConstValue { const_name_index, .. } => {
+            get_utf8(constant_pool, *const_name_index).map(str::to_string)
+        }
+        ElementValue::ArrayValue { values } => {
+            let values: Vec<String> = values
+                .iter()
+                .filter_map(|value| get_element_value_as_text(constant_pool, value))
+                .collect();
+            Some(values.join(", "))
+        }
+        _ => get_element_value_as_string(constant_pool, element_value),
+    }
+}
+
+/// Get the default value of an annotation element from its `AnnotationDefault`
+/// attribute, formatted as it would be written in Java (strings are quoted)
+pub fn get_annotation_default(
+    constant_pool: &[ConstantPool],
+    attributes: &[Attribute],
+    element_type: &str,
+) -> Option<String> {
+    attributes.iter().find_map(|attr| match attr {
+        Attribute::AnnotationDefault { default_value } => {
+            let value = get_element_value_as_string(constant_pool, default_value)?;
+            if element_type == "String" {
+                Some(java_literal(&value, '"'))
             } else {
-                None
+                Some(value)
             }
         }
         _ => None,
-    }
+    })
 }
 
 /// Extract annotation parameters from a field
@@ -220,14 +596,15 @@ pub fn get_annotation_params(
                     .replace('/', ".");
 
                 if type_name_clean == target_name {
-                    // Found the target annotation, extract parameters
-                    let mut self_card = "1".to_string();
+                    // Found the target annotation, extract parameters.
+                    // Cardinalities which aren't given are left empty.
+                    let mut self_card = String::new();
                     let mut label = String::new();
-                    let mut other_card = "1".to_string();
+                    let mut other_card = String::new();
 
                     for pair in annotation.element_value_pairs() {
                         if let Some(param_name) = get_utf8(constant_pool, pair.element_name_index()) {
-                            if let Some(value) = get_element_value_as_string(constant_pool, pair.value()) {
+                            if let Some(value) = get_element_value_as_text(constant_pool, pair.value()) {
                                 match param_name {
                                     "selfCard" => self_card = value,
                                     "label" => label = value,
@@ -247,6 +624,147 @@ pub fn get_annotation_params(
     None
 }
 
+/// Extract the `value` parameter of an annotation, e.g. the text of
+/// `@DiagramNote("Handles auth")`. Returns `None` if the annotation isn't
+/// present or has no `value`.
+pub fn get_annotation_value(
+    constant_pool: &[ConstantPool],
+    attributes: &[Attribute],
+    target_annotation: &str,
+) -> Option<String> {
+    for attr in attributes {
+        let annotations = match attr {
+            Attribute::RuntimeVisibleAnnotations { annotations, .. } => annotations,
+            Attribute::RuntimeInvisibleAnnotations { annotations } => annotations,
+            _ => continue,
+        };
+
+        for annotation in annotations {
+            let Some(type_name) = get_annotation_type(constant_pool, annotation.type_index()) else {
+                continue;
+            };
+            let type_name_clean = type_name
+                .trim_start_matches('L')
+                .trim_end_matches(';')
+                .replace('/', ".");
+            if type_name_clean != target_annotation {
+                continue;
+            }
+
+            return annotation.element_value_pairs().iter().find_map(|pair| {
+                if get_utf8(constant_pool, pair.element_name_index())? != "value" {
+                    return None;
+                }
+                get_element_value_as_text(constant_pool, pair.value())
+            });
+        }
+    }
+
+    None
+}
+
+/// Get the value of a constant field from its `ConstantValue` attribute,
+/// formatted as it would be written in Java. `field_type` is needed as
+/// `boolean` and `char` constants are stored as integers.
+pub fn get_constant_value(
+    constant_pool: &[ConstantPool],
+    attributes: &[Attribute],
+    field_type: &str,
+) -> Option<String> {
+    attributes.iter().find_map(|attr| match attr {
+        Attribute::ConstantValue { constantvalue_index } => {
+            let value = get_constant_as_string(constant_pool, *constantvalue_index)?;
+            match field_type {
+                "String" => Some(java_literal(&value, '"')),
+                "boolean" => Some((value != "0").to_string()),
+                "char" => {
+                    let code = value.parse::<u32>().ok().and_then(char::from_u32)?;
+                    Some(java_literal(&code.to_string(), '\''))
+                }
+                _ => Some(value),
+            }
+        }
+        _ => None,
+    })
+}
+
+/// Write text as a Java string or char literal enclosed in `quote`, escaping
+/// the quote, backslashes and control characters as Java source would
+fn java_literal(text: &str, quote: char) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push(quote);
+    for c in text.chars() {
+        match c {
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            '\u{8}' => literal.push_str("\\b"),
+            '\u{c}' => literal.push_str("\\f"),
+            c if c == quote => {
+                literal.push('\\');
+                literal.push(c);
+            }
+            c if c.is_control() => literal.push_str(&format!("\\u{:04x}", c as u32)),
+            c => literal.push(c),
+        }
+    }
+    literal.push(quote);
+    literal
+}
+
+/// Get the generic signature of a class, field, or method from its `Signature`
+/// attribute (e.g. "Ljava/util/List<Lcom/example/Item;>;"), if present
+pub fn get_signature<'a>(constant_pool: &'a [ConstantPool], attributes: &[Attribute]) -> Option<&'a str> {
+    attributes.iter().find_map(|attr| match attr {
+        Attribute::Signature { signature_index } => get_utf8(constant_pool, *signature_index),
+        _ => None,
+    })
+}
+
+/// Get the simple names of the checked exceptions a method declares in its
+/// `Exceptions` attribute (e.g. "IOException"), empty if it has none
+pub fn get_thrown_exceptions(constant_pool: &[ConstantPool], attributes: &[Attribute]) -> Vec<String> {
+    attributes
+        .iter()
+        .filter_map(|attr| match attr {
+            Attribute::Exceptions { exception_index_table } => Some(exception_index_table),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|&class_index| get_class_name_from_index(constant_pool, class_index))
+        .collect()
+}
+
+/// Get the name of the source file a class was compiled from (e.g.
+/// "Circle.java") from its `SourceFile` attribute, if present
+pub fn get_source_file(class_file: &ClassFile) -> Option<&str> {
+    let constant_pool = class_file.constant_pool();
+    class_file.attributes().iter().find_map(|attr| match attr {
+        Attribute::SourceFile { sourcefile_index } => get_utf8(constant_pool, *sourcefile_index),
+        _ => None,
+    })
+}
+
+/// Get the first source line of a method's body from the `LineNumberTable` of
+/// its `Code` attribute. Only present for methods with a body in classes
+/// compiled with line number debug info (javac's default).
+pub fn get_first_line(attributes: &[Attribute]) -> Option<u16> {
+    attributes.iter().find_map(|attr| match attr {
+        Attribute::Code { attributes, .. } => attributes
+            .iter()
+            .filter_map(|attr| match attr {
+                Attribute::LineNumberTable { line_number_table } => line_number_table
+                    .iter()
+                    .map(|record| record.line_number())
+                    .min(),
+                _ => None,
+            })
+            .min(),
+        _ => None,
+    })
+}
+
 /// Extract parameter names from method attributes (if available)
 /// Falls back to "arg0", "arg1", etc. if names are not present
 pub fn extract_parameter_names(
@@ -304,105 +822,451 @@ pub fn is_abstract(class_file: &ClassFile) -> bool {
         && !is_interface(class_file)
 }
 
+/// Check if classfile represents a final class, which can't be extended
+pub fn is_final(class_file: &ClassFile) -> bool {
+    class_file.access_flags().contains(ClassFlags::ACC_FINAL)
+}
+
 /// Check if classfile represents a record (Java 16+)
 pub fn is_record(class_file: &ClassFile) -> bool {
     // Records have a Record attribute
     class_file.attributes().iter().any(|attr| matches!(attr, Attribute::Record { .. }))
 }
 
-/// Convert a ClassFile to a Mermaid Class with all members
+/// Check if the attributes include a `Synthetic` attribute, which older
+/// compilers use in place of the `ACC_SYNTHETIC` flag
+pub fn has_synthetic_attribute(attributes: &[Attribute]) -> bool {
+    attributes.iter().any(|attr| matches!(attr, Attribute::Synthetic))
+}
+
+/// Check if a class or member is deprecated, by either the `Deprecated`
+/// attribute or the `@java.lang.Deprecated` annotation
+pub fn is_deprecated(constant_pool: &[ConstantPool], attributes: &[Attribute]) -> bool {
+    attributes.iter().any(|attr| matches!(attr, Attribute::Deprecated))
+        || has_annotation(constant_pool, attributes, Some("java.lang.Deprecated"))
+}
+
+/// Check if classfile was generated by the compiler, by either the
+/// `ACC_SYNTHETIC` flag or the `Synthetic` attribute
+pub fn is_synthetic_class(class_file: &ClassFile) -> bool {
+    class_file.access_flags().contains(ClassFlags::ACC_SYNTHETIC)
+        || has_synthetic_attribute(class_file.attributes())
+}
+
+/// Check if a field or method is compiler noise that shouldn't be drawn:
+/// constructors, static initializers, lambda methods, and anything marked
+/// synthetic by either the access flag or the `Synthetic` attribute
+pub fn is_noise_member(name: &str, synthetic_flag: bool, attributes: &[Attribute]) -> bool {
+    name == "<init>"
+        || name == "<clinit>"
+        || name.starts_with("lambda$")
+        || synthetic_flag
+        || has_synthetic_attribute(attributes)
+}
+
+/// Check if a method was generated by the compiler: marked `ACC_SYNTHETIC`,
+/// or `ACC_BRIDGE` for the bridge methods erasure adds when a subclass
+/// narrows a generic or covariant signature
+pub fn is_synthetic_method(method: &MethodInfo) -> bool {
+    method.access_flags().contains(MethodFlags::ACC_SYNTHETIC)
+        || method.access_flags().contains(MethodFlags::ACC_BRIDGE)
+}
+
+/// Check if a field is one of an enum's constants, which the compiler marks
+/// with `ACC_ENUM`
+pub fn is_enum_constant_field(field: &FieldInfo) -> bool {
+    field.access_flags().contains(FieldFlags::ACC_ENUM)
+}
+
+/// Check if a method is one the compiler adds to every enum: `values()` or
+/// `valueOf(String)`. `full_class_name` is the enum's name as in the
+/// classfile (e.g. "com/example/KeyCode").
+fn is_enum_implicit_method(name: &str, descriptor: &str, full_class_name: &str) -> bool {
+    match name {
+        "values" => descriptor == format!("()[L{};", full_class_name),
+        "valueOf" => descriptor == format!("(Ljava/lang/String;)L{};", full_class_name),
+        _ => false,
+    }
+}
+
+/// A record component, as listed by the record's `Record` attribute
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordComponent<'a> {
+    pub name: &'a str,
+    /// Field descriptor of the component's type (e.g. `Ljava/util/List;`)
+    pub descriptor: &'a str,
+    /// Generic signature of the component's type, if it has one
+    pub signature: Option<&'a str>,
+}
+
+impl RecordComponent<'_> {
+    /// The component's type as written in Java, preferring the generic
+    /// signature which keeps type arguments
+    pub fn data_type(&self, qualified: bool) -> String {
+        match self.signature {
+            Some(signature) => parse_field_signature(signature, qualified),
+            None => parse_field_descriptor(self.descriptor, qualified),
+        }
+    }
+}
+
+/// Get a record's components in order from its `Record` attribute. Empty
+/// for classes which aren't records.
+pub fn get_record_components(class_file: &ClassFile) -> Vec<RecordComponent<'_>> {
+    let constant_pool = class_file.constant_pool();
+    class_file
+        .attributes()
+        .iter()
+        .find_map(|attr| match attr {
+            Attribute::Record { components } => Some(components),
+            _ => None,
+        })
+        .map(|components| {
+            components
+                .iter()
+                .filter_map(|component| {
+                    Some(RecordComponent {
+                        name: get_utf8(constant_pool, component.name_index())?,
+                        descriptor: get_utf8(constant_pool, component.descriptor_index())?,
+                        signature: get_signature(constant_pool, component.attributes()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Check if a method is one the compiler generates for a record: an accessor
+/// for one of its components, `equals`, `hashCode`, `toString`, or the
+/// canonical constructor taking every component in order
+fn is_record_boilerplate(name: &str, descriptor: &str, components: &[RecordComponent]) -> bool {
+    match (name, descriptor) {
+        ("equals", "(Ljava/lang/Object;)Z")
+        | ("hashCode", "()I")
+        | ("toString", "()Ljava/lang/String;") => true,
+        ("<init>", _) => {
+            let parameters: String = components.iter().map(|component| component.descriptor).collect();
+            descriptor == format!("({})V", parameters)
+        }
+        _ => components
+            .iter()
+            .any(|component| component.name == name && descriptor.starts_with("()")),
+    }
+}
+
+/// Count the fields and methods a class declares before any filtering,
+/// ignoring compiler noise (see `is_noise_member`)
+pub fn count_declared_members(class_file: &ClassFile) -> usize {
+    let constant_pool = class_file.constant_pool();
+    let fields = class_file
+        .fields()
+        .iter()
+        .filter(|field| {
+            let name = get_utf8(constant_pool, field.name_index()).unwrap_or("");
+            let synthetic = field.access_flags().contains(FieldFlags::ACC_SYNTHETIC);
+            !is_noise_member(name, synthetic, field.attributes())
+        })
+        .count();
+    let methods = class_file
+        .methods()
+        .iter()
+        .filter(|method| {
+            let name = get_utf8(constant_pool, method.name_index()).unwrap_or("");
+            let synthetic = is_synthetic_method(method);
+            !is_noise_member(name, synthetic, method.attributes())
+        })
+        .count();
+    fields + methods
+}
+
+/// Which members of a class `classfile_to_mermaid_class` lists and how. The
+/// default lists every field and method, leaving out constructors, with
+/// simple type names in declaration order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemberOptions<'a> {
+    /// Members with any of these annotations are left out
+    pub skip_annotations: &'a [Option<&'a str>],
+    /// Members with any of these annotations are drawn as relations instead
+    pub relationship_annotations: &'a [Option<&'a str>],
+    /// List constructors, named after the class
+    pub show_constructors: bool,
+    pub min_visibility: MinVisibility,
+    /// Keep the package of member types (e.g. `java.util.List`)
+    pub qualified_types: bool,
+    pub member_order: MemberOrder,
+}
+
+/// Why a field or method is left out of its class
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberExclusion<'a> {
+    /// Hidden by one of the skip annotations
+    Skipped(&'a str),
+    /// Drawn as a relation by one of the relationship annotations instead
+    Relation(&'a str),
+    /// A constructor, only listed with `show_constructors`
+    Constructor,
+    StaticInitializer,
+    LambdaBody,
+    /// Generated by the compiler, such as bridge methods and `this$0`
+    Synthetic,
+    /// Less visible than `min_visibility`
+    Visibility,
+    /// `values()` or `valueOf()`, which every enum gets
+    EnumMethod,
+    /// An accessor or object method the compiler generates for a record
+    RecordMethod,
+}
+
+impl std::fmt::Display for MemberExclusion<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MemberExclusion::Skipped(annotation) => write!(f, "skipped by @{}", annotation),
+            MemberExclusion::Relation(annotation) => write!(f, "drawn as a relation by @{}", annotation),
+            MemberExclusion::Constructor => f.write_str("constructor"),
+            MemberExclusion::StaticInitializer => f.write_str("static initializer"),
+            MemberExclusion::LambdaBody => f.write_str("lambda body"),
+            MemberExclusion::Synthetic => f.write_str("generated by the compiler"),
+            MemberExclusion::Visibility => f.write_str("less visible than asked for"),
+            MemberExclusion::EnumMethod => f.write_str("implicit enum method"),
+            MemberExclusion::RecordMethod => f.write_str("implicit record method"),
+        }
+    }
+}
+
+/// Find the first of `annotations` present in `attributes`
+fn find_annotation<'a>(
+    constant_pool: &[ConstantPool],
+    attributes: &[Attribute],
+    annotations: &[Option<&'a str>],
+) -> Option<&'a str> {
+    annotations
+        .iter()
+        .flatten()
+        .copied()
+        .find(|annotation| has_annotation(constant_pool, attributes, Some(annotation)))
+}
+
+/// Work out why `classfile_to_mermaid_class` leaves a field out of its class,
+/// if it does. Enum constants are always listed.
+pub fn field_exclusion<'a>(
+    class_file: &ClassFile,
+    field: &FieldInfo,
+    options: &MemberOptions<'a>,
+) -> Option<MemberExclusion<'a>> {
+    let constant_pool = class_file.constant_pool();
+    if let Some(annotation) = find_annotation(constant_pool, field.attributes(), options.skip_annotations) {
+        return Some(MemberExclusion::Skipped(annotation));
+    }
+    if let Some(annotation) = find_annotation(constant_pool, field.attributes(), options.relationship_annotations) {
+        return Some(MemberExclusion::Relation(annotation));
+    }
+
+    let name = get_utf8(constant_pool, field.name_index()).unwrap_or("unknown");
+    let synthetic = field.access_flags().contains(FieldFlags::ACC_SYNTHETIC);
+    if is_noise_member(name, synthetic, field.attributes()) {
+        return Some(MemberExclusion::Synthetic);
+    }
+    if is_enum_constant_field(field) {
+        return None;
+    }
+
+    // A record's components are shown whatever the visibility of the fields
+    // backing them
+    let is_component = !field.access_flags().contains(FieldFlags::ACC_STATIC)
+        && get_record_components(class_file).iter().any(|component| component.name == name);
+    if !is_component && !options.min_visibility.allows(field_visibility(field.access_flags())) {
+        return Some(MemberExclusion::Visibility);
+    }
+    None
+}
+
+/// Work out why `classfile_to_mermaid_class` leaves a method out of its
+/// class, if it does
+pub fn method_exclusion<'a>(
+    class_file: &ClassFile,
+    method: &MethodInfo,
+    options: &MemberOptions<'a>,
+) -> Option<MemberExclusion<'a>> {
+    let constant_pool = class_file.constant_pool();
+    if let Some(annotation) = find_annotation(constant_pool, method.attributes(), options.skip_annotations) {
+        return Some(MemberExclusion::Skipped(annotation));
+    }
+    if let Some(annotation) = find_annotation(constant_pool, method.attributes(), options.relationship_annotations) {
+        return Some(MemberExclusion::Relation(annotation));
+    }
+
+    // Constructors are only shown when asked for, and never ones the
+    // compiler made up
+    let name = get_utf8(constant_pool, method.name_index()).unwrap_or("unknown");
+    let synthetic = is_synthetic_method(method);
+    let shown_constructor = options.show_constructors
+        && name == "<init>"
+        && !synthetic
+        && !has_synthetic_attribute(method.attributes());
+    if !shown_constructor && is_noise_member(name, synthetic, method.attributes()) {
+        return Some(match name {
+            "<init>" => MemberExclusion::Constructor,
+            "<clinit>" => MemberExclusion::StaticInitializer,
+            _ if name.starts_with("lambda$") => MemberExclusion::LambdaBody,
+            _ => MemberExclusion::Synthetic,
+        });
+    }
+
+    if !options.min_visibility.allows(method_visibility(method.access_flags())) {
+        return Some(MemberExclusion::Visibility);
+    }
+
+    let descriptor = get_utf8(constant_pool, method.descriptor_index()).unwrap_or("");
+    let full_class_name = get_full_class_name(class_file).unwrap_or_default();
+    if is_enum(class_file) && is_enum_implicit_method(name, descriptor, &full_class_name) {
+        return Some(MemberExclusion::EnumMethod);
+    }
+    if is_record(class_file) && is_record_boilerplate(name, descriptor, &get_record_components(class_file)) {
+        return Some(MemberExclusion::RecordMethod);
+    }
+    None
+}
+
+/// Convert a ClassFile to a Mermaid Class with the members `options` asks for
 pub fn classfile_to_mermaid_class<'a>(
     class_file: &'a ClassFile,
     class_name: &str,
-    skip_annotation: Option<&str>,
-    relationship_annotations: &[Option<&str>],
+    options: &MemberOptions,
 ) -> Class<'a> {
+    let MemberOptions {
+        qualified_types,
+        member_order,
+        ..
+    } = *options;
     let constant_pool = class_file.constant_pool();
 
     // Determine class annotation
-    let annotation = if is_interface(class_file) {
+    let is_annotation_type = is_annotation(class_file);
+    let annotation = if is_annotation_type {
+        Some("annotation".into())
+    } else if is_interface(class_file) {
         Some("interface".into())
     } else if is_enum(class_file) {
         Some("enumeration".into())
+    } else if is_record(class_file) {
+        Some("record".into())
     } else if is_abstract(class_file) {
         Some("abstract".into())
     } else {
         None
     };
 
-    let is_enum_class = is_enum(class_file);
+    // Functional, sealed and deprecated classes get a marker alongside their
+    // other stereotype
+    let mut stereotypes = Vec::new();
+    if is_interface(class_file)
+        && has_annotation(constant_pool, class_file.attributes(), Some("java.lang.FunctionalInterface"))
+    {
+        stereotypes.push("functional".into());
+    }
+    if is_sealed(class_file) {
+        stereotypes.push("sealed".into());
+    }
+    if is_deprecated(constant_pool, class_file.attributes()) {
+        stereotypes.push("deprecated".into());
+    }
+
+    let full_class_name = get_full_class_name(class_file).unwrap_or_default();
+    let record_components = get_record_components(class_file);
 
     // Extract fields
+    let mut enum_constants = Vec::new();
     let mut members = Vec::new();
     for field in class_file.fields() {
-        // Skip if field has the skip annotation
-        if has_annotation(constant_pool, field.attributes(), skip_annotation) {
+        if field_exclusion(class_file, field, options).is_some() {
             continue;
         }
 
-        // Skip if field has any relationship annotation
-        let has_relationship_annotation = relationship_annotations.iter().any(|rel_ann| {
-            has_annotation(constant_pool, field.attributes(), *rel_ann)
-        });
-        if has_relationship_annotation {
+        let name = get_utf8(constant_pool, field.name_index())
+            .unwrap_or("unknown");
+
+        // Enum constants are listed by name only, ahead of the other fields
+        if is_enum_constant_field(field) {
+            enum_constants.push(Member::Attribute(MermaidAttribute {
+                visibility: Visibility::Unspecified,
+                name: name.into(),
+                data_type: None,
+                is_static: false,
+                type_notation: TypeNotation::None,
+                value: None,
+                is_deprecated: false,
+            }));
             continue;
         }
 
-        let name = get_utf8(constant_pool, field.name_index())
-            .unwrap_or("unknown");
+        // A record's components are shown in place of the fields backing
+        // them, typed as in the `Record` attribute and without the private
+        // visibility the compiler gives those fields
+        let is_static = field.access_flags().contains(FieldFlags::ACC_STATIC);
+        let component = record_components
+            .iter()
+            .find(|component| !is_static && component.name == name);
+
         let descriptor = get_utf8(constant_pool, field.descriptor_index())
             .unwrap_or("");
-        let data_type = parse_field_descriptor(descriptor);
 
-        // Strip $ from field names (synthetic fields added by compiler)
-        let clean_name = name.trim_matches('$');
+        // Prefer the generic signature, which keeps type arguments
+        let data_type = match (component, get_signature(constant_pool, field.attributes())) {
+            (Some(component), _) => component.data_type(qualified_types),
+            (None, Some(signature)) => parse_field_signature(signature, qualified_types),
+            (None, None) => parse_field_descriptor(descriptor, qualified_types),
+        };
 
-        // Check if this is an enum constant (field type matches class name)
-        let is_enum_constant = is_enum_class && data_type == class_name;
+        let value = get_constant_value(constant_pool, field.attributes(), &data_type);
 
         members.push(Member::Attribute(MermaidAttribute {
-            visibility: if is_enum_constant {
+            visibility: if component.is_some() {
                 Visibility::Unspecified
             } else {
                 field_visibility(field.access_flags())
             },
-            name: clean_name.into(),
-            data_type: if is_enum_constant {
-                None
-            } else {
-                Some(data_type.into())
-            },
-            is_static: if is_enum_constant {
-                false
-            } else {
-                field.access_flags().contains(FieldFlags::ACC_STATIC)
-            },
-            type_notation: if is_enum_constant {
-                TypeNotation::None
-            } else {
-                TypeNotation::Postfix
-            },
+            name: name.into(),
+            data_type: Some(data_type.into()),
+            is_static,
+            type_notation: TypeNotation::Postfix,
+            value: value.map(Into::into),
+            is_deprecated: is_deprecated(constant_pool, field.attributes()),
         }));
     }
 
     // Extract methods
     for method in class_file.methods() {
-        // Skip if method has the skip annotation
-        if has_annotation(constant_pool, method.attributes(), skip_annotation) {
+        if method_exclusion(class_file, method, options).is_some() {
             continue;
         }
 
         let name = get_utf8(constant_pool, method.name_index())
             .unwrap_or("unknown");
+        let is_constructor = name == "<init>";
+        let descriptor = get_utf8(constant_pool, method.descriptor_index())
+            .unwrap_or("");
+        let (mut param_types, mut return_type) = parse_method_descriptor(descriptor, qualified_types);
 
-        // Skip constructors, static initializers, and lambda methods
-        if name == "<init>" || name == "<clinit>" || name.starts_with("lambda$") {
-            continue;
+        // Prefer the generic signature, which keeps type arguments. It can
+        // leave out parameters the compiler adds, so only use it if it has
+        // every parameter.
+        if let Some(signature) = get_signature(constant_pool, method.attributes()) {
+            let (signature_params, signature_return) = parse_method_signature(signature, qualified_types);
+            if signature_params.len() == param_types.len() {
+                param_types = signature_params;
+                return_type = signature_return;
+            }
+        }
+
+        // Varargs are compiled to a trailing array parameter, show it as
+        // written in Java instead
+        if method.access_flags().contains(MethodFlags::ACC_VARARGS)
+            && let Some(last) = param_types.last_mut()
+            && let Some(element_type) = last.strip_suffix("[]")
+        {
+            *last = format!("{}...", element_type);
         }
 
-        let descriptor = get_utf8(constant_pool, method.descriptor_index())
-            .unwrap_or("");
-        let (param_types, return_type) = parse_method_descriptor(descriptor);
         let param_names = extract_parameter_names(constant_pool, method.attributes(), param_types.len());
 
         let parameters: Vec<Parameter> = param_names
@@ -415,23 +1279,422 @@ pub fn classfile_to_mermaid_class<'a>(
             })
             .collect();
 
-        // Strip $ from method names (synthetic methods added by compiler)
-        let clean_name = name.trim_matches('$');
+        // Constructors take the class's own simple name. Other names are
+        // kept as they are, since `$` is a valid identifier character (and
+        // common in Kotlin and Scala output) while the names the compiler
+        // makes up are marked synthetic and were skipped above.
+        let clean_name: std::borrow::Cow<str> = if is_constructor {
+            let simple_name = full_class_name.rsplit('/').next().unwrap_or_default();
+            simple_name.rsplit('$').next().unwrap_or_default().to_string().into()
+        } else {
+            name.into()
+        };
+
+        // Annotation elements are implicitly abstract, show their default
+        // value (if any) in place of the abstract marker
+        let is_abstract = !is_annotation_type
+            && method.access_flags().contains(MethodFlags::ACC_ABSTRACT);
+
+        // Interface methods with a body, other than static and private
+        // ones, are default methods
+        let is_default = is_interface(class_file)
+            && !is_annotation_type
+            && !is_abstract
+            && !method.access_flags().contains(MethodFlags::ACC_STATIC)
+            && !method.access_flags().contains(MethodFlags::ACC_PRIVATE);
+        let default_value = is_annotation_type
+            .then(|| get_annotation_default(constant_pool, method.attributes(), &return_type))
+            .flatten();
 
         members.push(Member::Method(Method {
             visibility: method_visibility(method.access_flags()),
-            name: clean_name.into(),
+            name: clean_name,
             parameters,
-            return_type: Some(return_type.into()),
+            // Constructors have no return type
+            return_type: (!is_constructor).then(|| return_type.into()),
             is_static: method.access_flags().contains(MethodFlags::ACC_STATIC),
-            is_abstract: method.access_flags().contains(MethodFlags::ACC_ABSTRACT),
+            is_abstract,
             return_type_notation: TypeNotation::Postfix,
+            default_value: default_value.map(Into::into),
+            throws: get_thrown_exceptions(constant_pool, method.attributes())
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            is_default,
+            is_deprecated: is_deprecated(constant_pool, method.attributes()),
         }));
     }
 
+    member_order.sort(&mut members);
+    enum_constants.append(&mut members);
+
     Class {
         name: class_name.to_string().into(),
         annotation,
-        members,
+        stereotypes,
+        members: enum_constants,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_thrown_exceptions() {
+        let bytes = std::fs::read("test_data/class/com/example/storage/FileStore.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let constant_pool = class_file.constant_pool();
+
+        let thrown: Vec<(&str, Vec<String>)> = class_file
+            .methods()
+            .iter()
+            .filter_map(|method| {
+                let name = get_utf8(constant_pool, method.name_index())?;
+                Some((name, get_thrown_exceptions(constant_pool, method.attributes())))
+            })
+            .collect();
+        assert_eq!(
+            thrown,
+            [
+                ("<init>", vec![]),
+                ("save", vec!["IOException".to_string()]),
+                (
+                    "load",
+                    vec!["IOException".to_string(), "TimeoutException".to_string()]
+                ),
+                ("clear", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escaped_constants() {
+        let bytes = std::fs::read("test_data/class/com/example/literals/Escapes.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let constant_pool = class_file.constant_pool();
+
+        let constants: Vec<(&str, String)> = class_file
+            .fields()
+            .iter()
+            .filter_map(|field| {
+                let name = get_utf8(constant_pool, field.name_index())?;
+                let descriptor = get_utf8(constant_pool, field.descriptor_index())?;
+                let field_type = parse_field_descriptor(descriptor, false);
+                Some((name, get_constant_value(constant_pool, field.attributes(), &field_type)?))
+            })
+            .collect();
+        assert_eq!(
+            constants,
+            [
+                ("GREETING", r#""say \"hi\"\n""#.to_string()),
+                ("PATH", r#""C:\\tmp\tdir""#.to_string()),
+                ("QUOTE", r"'\''".to_string()),
+                ("NEWLINE", r"'\n'".to_string()),
+                ("BACKSLASH", r"'\\'".to_string()),
+            ]
+        );
+
+        let bytes = std::fs::read("test_data/class/com/example/literals/Caption.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let default = class_file.methods().iter().find_map(|method| {
+            get_annotation_default(class_file.constant_pool(), method.attributes(), "String")
+        });
+        assert_eq!(default.as_deref(), Some(r#""\"untitled\"""#));
+    }
+
+    #[test]
+    fn test_get_record_components() {
+        let bytes = std::fs::read("test_data/class/com/example/geometry/Point.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let components: Vec<(&str, &str, String)> = get_record_components(&class_file)
+            .iter()
+            .map(|component| (component.name, component.descriptor, component.data_type(true)))
+            .collect();
+        assert_eq!(
+            components,
+            [
+                ("x", "I", "int".to_string()),
+                ("y", "I", "int".to_string()),
+                ("labels", "Ljava/util/List;", "java.util.List<java.lang.String>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_min_visibility() {
+        assert!(MinVisibility::Private.allows(Visibility::Private));
+        assert!(MinVisibility::Protected.allows(Visibility::Public));
+        assert!(MinVisibility::Protected.allows(Visibility::Protected));
+        assert!(!MinVisibility::Protected.allows(Visibility::Package));
+        assert!(!MinVisibility::Public.allows(Visibility::Protected));
+        assert!(MinVisibility::Public.allows(Visibility::Unspecified));
+    }
+
+    #[test]
+    fn test_nested_class_names() {
+        let bytes = std::fs::read("test_data/class/com/example/tree/Tree.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+
+        // The anonymous `Tree$1` is left out
+        let mut nested = get_nested_class_names(&class_file);
+        nested.sort();
+        assert_eq!(nested, ["Tree.Leaf", "Tree.Node"]);
+
+        // Nested classes list their siblings, which aren't nested in them
+        let bytes = std::fs::read("test_data/class/com/example/tree/Tree$Leaf.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        assert!(get_nested_class_names(&class_file).is_empty());
+    }
+
+    #[test]
+    fn test_enclosing_class_name() {
+        let bytes = std::fs::read("test_data/class/com/example/tree/Tree$Leaf.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        assert_eq!(get_enclosing_class_name(&class_file), Some("Tree".to_string()));
+
+        let bytes = std::fs::read("test_data/class/com/example/tree/Tree$1.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        assert_eq!(get_enclosing_class_name(&class_file), Some("Tree".to_string()));
+
+        let bytes = std::fs::read("test_data/class/com/example/tree/Tree.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        assert_eq!(get_enclosing_class_name(&class_file), None);
+    }
+
+    #[test]
+    fn test_implicit_superclasses() {
+        let superclass = |path: &str| {
+            let bytes = std::fs::read(path).expect("Failed to read fixture classfile");
+            let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+            get_superclass_name(&class_file)
+        };
+
+        assert_eq!(superclass("test_data/class/com/example/geometry/Point.class"), None);
+        assert_eq!(superclass("test_data/class/com/example/io/KeyCode.class"), None);
+        assert_eq!(superclass("test_data/class/com/example/ledger/Record.class"), None);
+        // A class named `Record` outside java.lang is a superclass like any other
+        assert_eq!(
+            superclass("test_data/class/com/example/ledger/Invoice.class").as_deref(),
+            Some("Record")
+        );
+    }
+
+    #[test]
+    fn test_enum_constants() {
+        let bytes = std::fs::read("test_data/class/com/example/io/KeyCode.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let constant_pool = class_file.constant_pool();
+
+        let constants: Vec<&str> = class_file
+            .fields()
+            .iter()
+            .filter(|field| is_enum_constant_field(field))
+            .filter_map(|field| get_utf8(constant_pool, field.name_index()))
+            .collect();
+        assert_eq!(constants, ["A", "B", "C", "D", "E", "F", "G"]);
+
+        // Constants come first, without the `$VALUES` field or the
+        // `values()`/`valueOf()` methods
+        let class = classfile_to_mermaid_class(&class_file, "KeyCode", &MemberOptions::default());
+        let names: Vec<&str> = class
+            .members
+            .iter()
+            .map(|member| match member {
+                Member::Attribute(attribute) => attribute.name.as_ref(),
+                Member::Method(method) => method.name.as_ref(),
+            })
+            .collect();
+        assert_eq!(names, ["A", "B", "C", "D", "E", "F", "G", "toAscii"]);
+    }
+
+    #[test]
+    fn test_synthetic_attribute_is_noise() {
+        assert!(is_noise_member("access$000", false, &[Attribute::Synthetic]));
+        assert!(is_noise_member("this$0", true, &[]));
+        assert!(is_noise_member("<init>", false, &[]));
+        assert!(!is_noise_member("getName", false, &[Attribute::Deprecated]));
+    }
+
+    #[test]
+    fn test_default_methods() {
+        let bytes = std::fs::read("test_data/class/com/example/functions/Formatter.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let class = classfile_to_mermaid_class(&class_file, "Formatter", &MemberOptions::default());
+
+        let methods: BTreeMap<&str, (&str, bool, bool)> = class
+            .members
+            .iter()
+            .filter_map(|member| match member {
+                Member::Method(method) => Some((
+                    method.name.as_ref(),
+                    (method.return_type.as_deref().unwrap_or_default(), method.is_abstract, method.is_default),
+                )),
+                Member::Attribute(_) => None,
+            })
+            .collect();
+        assert_eq!(methods["format"], ("String", true, false));
+        assert_eq!(methods["formatAll"], ("String", false, true));
+        assert_eq!(methods["plain"], ("Formatter", false, false));
+    }
+
+    #[test]
+    fn test_sealed_classes() {
+        let read = |path: &str| {
+            let bytes = std::fs::read(path).expect("Failed to read fixture classfile");
+            jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile")
+        };
+
+        let payment = read("test_data/class/com/example/payments/Payment.class");
+        assert!(is_sealed(&payment));
+        assert_eq!(get_permitted_subclasses(&payment), ["Card", "Cash"]);
+        let class = classfile_to_mermaid_class(&payment, "Payment", &MemberOptions::default());
+        assert_eq!(class.stereotype_text().as_deref(), Some("interface, sealed"));
+
+        let fee = read("test_data/class/com/example/payments/Fee.class");
+        let class = classfile_to_mermaid_class(&fee, "Fee", &MemberOptions::default());
+        assert_eq!(class.stereotype_text().as_deref(), Some("abstract, sealed"));
+
+        // Non-sealed and final subclasses are ordinary classes
+        let cash = read("test_data/class/com/example/payments/Cash.class");
+        assert!(!is_sealed(&cash));
+        assert!(get_permitted_subclasses(&cash).is_empty());
+        let class = classfile_to_mermaid_class(&cash, "Cash", &MemberOptions::default());
+        assert_eq!(class.stereotype_text(), None);
+    }
+
+    #[test]
+    fn test_bridge_methods() {
+        let bytes = std::fs::read("test_data/class/com/example/bridges/IntBox.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        assert!(class_file.methods().iter().any(|method| {
+            method.access_flags().contains(MethodFlags::ACC_BRIDGE)
+        }));
+
+        let class = classfile_to_mermaid_class(&class_file, "IntBox", &MemberOptions::default());
+        let methods: Vec<String> = class
+            .members
+            .iter()
+            .filter_map(|member| match member {
+                Member::Method(method) => {
+                    let parameters: Vec<&str> = method
+                        .parameters
+                        .iter()
+                        .filter_map(|parameter| parameter.data_type.as_deref())
+                        .collect();
+                    Some(format!(
+                        "{}({}) {}",
+                        method.name,
+                        parameters.join(", "),
+                        method.return_type.as_deref().unwrap_or_default()
+                    ))
+                }
+                Member::Attribute(_) => None,
+            })
+            .collect();
+        // Only the overrides as written, not the `Object` bridges
+        assert_eq!(methods, ["get() Integer", "set(Integer) void"]);
+    }
+
+    #[test]
+    fn test_functional_interface() {
+        let bytes = std::fs::read("test_data/class/com/example/functions/Transformer.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let class = classfile_to_mermaid_class(&class_file, "Transformer", &MemberOptions::default());
+        assert_eq!(class.stereotype_text().as_deref(), Some("interface, functional"));
+
+        let bytes = std::fs::read("test_data/class/com/example/functions/Listener.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let class = classfile_to_mermaid_class(&class_file, "Listener", &MemberOptions::default());
+        assert_eq!(class.stereotype_text().as_deref(), Some("interface"));
+    }
+
+    #[test]
+    fn test_deprecated_attribute() {
+        assert!(is_deprecated(&[], &[Attribute::Deprecated]));
+        assert!(!is_deprecated(&[], &[Attribute::Synthetic]));
+    }
+
+    #[test]
+    fn test_member_order() {
+        let bytes = std::fs::read("test_data/class/com/example/bank/Account.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let names = |member_order| {
+            classfile_to_mermaid_class(&class_file, "Account", &MemberOptions { member_order, ..Default::default() })
+                .members
+                .iter()
+                .map(|member| match member {
+                    Member::Attribute(attribute) => attribute.name.to_string(),
+                    Member::Method(method) => method.name.to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(MemberOrder::Alpha), ["balance", "branch", "ledger", "owner", "audit", "getBalance", "log", "reconcile"]);
+        assert_eq!(names(MemberOrder::Visibility), ["owner", "balance", "branch", "ledger", "getBalance", "audit", "reconcile", "log"]);
+        // The same classfile always gives the same order
+        assert_eq!(names(MemberOrder::Visibility), names(MemberOrder::Visibility));
+    }
+
+    #[test]
+    fn test_dollar_names_are_kept() {
+        // Synthetic members are noise whatever they're called
+        assert!(is_noise_member("val$x", true, &[]));
+        assert!(is_noise_member("$val$x", false, &[Attribute::Synthetic]));
+        assert!(!is_noise_member("$val$x", false, &[]));
+        assert!(!is_noise_member("foo$bar", false, &[]));
+
+        let bytes = std::fs::read("test_data/class/com/example/names/Mangled.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let class = classfile_to_mermaid_class(&class_file, "Mangled", &MemberOptions::default());
+        let names: Vec<&str> = class
+            .members
+            .iter()
+            .map(|member| match member {
+                Member::Attribute(attribute) => attribute.name.as_ref(),
+                Member::Method(method) => method.name.as_ref(),
+            })
+            .collect();
+        // Written names keep every `$`, while the synthetic
+        // `$assertionsDisabled` field is left out
+        assert_eq!(names, ["foo$bar", "$val$x", "total$", "$reset", "check"]);
+    }
+
+    #[test]
+    fn test_array_and_enum_annotation_params() {
+        let bytes = std::fs::read("test_data/class/com/example/access/Repository.class")
+            .expect("Failed to read fixture classfile");
+        let class_file = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+        let constant_pool = class_file.constant_pool();
+
+        // `@Uses(label = {"reads", "writes"})`
+        let field = &class_file.fields()[0];
+        assert_eq!(
+            get_annotation_params(constant_pool, field.attributes(), Some("com.example.access.Uses")),
+            Some((String::new(), "reads, writes".to_string(), String::new()))
+        );
+
+        // `@Status(Stage.DRAFT)`
+        assert_eq!(
+            get_annotation_value(constant_pool, class_file.attributes(), "com.example.access.Status"),
+            Some("DRAFT".to_string())
+        );
     }
 }
@@ -0,0 +1,161 @@
+//! Approximate per-class complexity read from method bytecode
+//!
+//! A class's complexity is the number of methods it declares plus a
+//! cyclomatic proxy for each of them: one for every conditional branch,
+//! switch case, and exception handler in the method's `Code` attribute. This
+//! is only an approximation of cyclomatic complexity, as the compiler may add
+//! branches of its own (e.g. for string switches or `assert`) and
+//! short-circuit operators count once per operand.
+
+use crate::classfile_utils::{get_utf8, is_noise_member, is_synthetic_method};
+use jclassfile::{attributes::Attribute, class_file::ClassFile};
+
+const TABLESWITCH: u8 = 0xaa;
+const LOOKUPSWITCH: u8 = 0xab;
+const WIDE: u8 = 0xc4;
+const IINC: u8 = 0x84;
+
+/// Whether an opcode is a conditional branch (`ifeq` through `if_acmpne`,
+/// `ifnull`, and `ifnonnull`)
+fn is_conditional_branch(opcode: u8) -> bool {
+    matches!(opcode, 0x99..=0xa6 | 0xc6 | 0xc7)
+}
+
+/// Length in bytes of an instruction with a fixed size, including the opcode
+fn instruction_length(opcode: u8) -> usize {
+    match opcode {
+        0x10 | 0x12 | 0x15..=0x19 | 0x36..=0x3a | 0xa9 | 0xbc => 2,
+        0x11
+        | 0x13
+        | 0x14
+        | IINC
+        | 0x99..=0xa8
+        | 0xb2..=0xb8
+        | 0xbb
+        | 0xbd
+        | 0xc0
+        | 0xc1
+        | 0xc6
+        | 0xc7 => 3,
+        0xc5 => 4,
+        0xb9 | 0xba | 0xc8 | 0xc9 => 5,
+        _ => 1,
+    }
+}
+
+/// Read a big-endian `i32` operand, or zero past the end of the code
+fn read_i32(code: &[u8], at: usize) -> i32 {
+    code.get(at..at + 4)
+        .map(|bytes| i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .unwrap_or(0)
+}
+
+/// Count the decision points in a method's bytecode: conditional branches
+/// and the cases of `tableswitch`/`lookupswitch` instructions
+pub fn count_branches(code: &[u8]) -> usize {
+    let mut branches = 0;
+    let mut pc = 0;
+    while let Some(&opcode) = code.get(pc) {
+        match opcode {
+            TABLESWITCH | LOOKUPSWITCH => {
+                // Operands are aligned to four bytes from the start of the code
+                let operands = (pc + 4) & !3;
+                let (cases, length) = if opcode == TABLESWITCH {
+                    let low = read_i32(code, operands + 4);
+                    let high = read_i32(code, operands + 8);
+                    let cases = (high as i64 - low as i64 + 1).max(0) as usize;
+                    (cases, 12 + cases * 4)
+                } else {
+                    let pairs = read_i32(code, operands + 4).max(0) as usize;
+                    (pairs, 8 + pairs * 8)
+                };
+                branches += cases;
+                pc = operands + length;
+            }
+            WIDE => {
+                pc += if code.get(pc + 1) == Some(&IINC) {
+                    6
+                } else {
+                    4
+                };
+            }
+            _ => {
+                if is_conditional_branch(opcode) {
+                    branches += 1;
+                }
+                pc += instruction_length(opcode);
+            }
+        }
+    }
+    branches
+}
+
+/// Complexity of a class: each method it declares counts one, plus the
+/// branches and exception handlers in its code. Compiler noise such as
+/// constructors and synthetic methods isn't counted.
+pub fn class_complexity(class_file: &ClassFile) -> usize {
+    let constant_pool = class_file.constant_pool();
+    class_file
+        .methods()
+        .iter()
+        .filter(|method| {
+            let name = get_utf8(constant_pool, method.name_index()).unwrap_or("");
+            let synthetic = is_synthetic_method(method);
+            !is_noise_member(name, synthetic, method.attributes())
+        })
+        .map(|method| {
+            let decisions: usize = method
+                .attributes()
+                .iter()
+                .map(|attr| match attr {
+                    Attribute::Code {
+                        code,
+                        exception_table,
+                        ..
+                    } => count_branches(code) + exception_table.len(),
+                    _ => 0,
+                })
+                .sum();
+            1 + decisions
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_branches() {
+        // iload_1; ifle +5; iconst_1; ireturn; iconst_0; ireturn
+        assert_eq!(
+            count_branches(&[0x1b, 0x9e, 0x00, 0x05, 0x04, 0xac, 0x03, 0xac]),
+            1
+        );
+        // No branches: aload_0; getfield #2; areturn
+        assert_eq!(count_branches(&[0x2a, 0xb4, 0x00, 0x02, 0xb0]), 0);
+    }
+
+    #[test]
+    fn test_count_switch_cases() {
+        // iload_1; tableswitch (padded to offset 4) low 1 high 3; ifnull
+        let mut code = vec![0x1b, TABLESWITCH, 0x00, 0x00];
+        code.extend(0i32.to_be_bytes());
+        code.extend(1i32.to_be_bytes());
+        code.extend(3i32.to_be_bytes());
+        for _ in 0..3 {
+            code.extend(0i32.to_be_bytes());
+        }
+        code.extend([0xc6, 0x00, 0x00]);
+        assert_eq!(count_branches(&code), 4);
+
+        // lookupswitch at offset 0 (padded to offset 4) with two pairs
+        let mut code = vec![LOOKUPSWITCH, 0x00, 0x00, 0x00];
+        code.extend(0i32.to_be_bytes());
+        code.extend(2i32.to_be_bytes());
+        for _ in 0..4 {
+            code.extend(0i32.to_be_bytes());
+        }
+        assert_eq!(count_branches(&code), 2);
+    }
+}
@@ -3,14 +3,29 @@
 //! Converts JVM field descriptors (like "Ljava/lang/String;") and method descriptors
 //! (like "(ILjava/lang/String;)V") into human-readable type names for Mermaid diagrams.
 
-/// Parse a field descriptor into a readable type name
+/// Parse a field descriptor into a readable type name. Class types are shown
+/// by their simple name unless `qualified` asks for their dotted fully
+/// qualified name.
 /// Examples:
 /// - "I" -> "int"
-/// - "Ljava/lang/String;" -> "String"
+/// - "Ljava/lang/String;" -> "String" ("java.lang.String" if qualified)
 /// - "[I" -> "int[]"
 /// - "Ljava/util/List;" -> "List"
-pub fn parse_field_descriptor(descriptor: &str) -> String {
-    parse_type_internal(descriptor, 0).0
+pub fn parse_field_descriptor(descriptor: &str, qualified: bool) -> String {
+    parse_type_internal(descriptor, 0, qualified).0
+}
+
+/// Readable name of a class path (e.g. "com/example/Outer$Inner"): its
+/// simple name ("Outer.Inner") or, if `qualified`, its dotted fully qualified
+/// name ("com.example.Outer.Inner")
+fn display_class_name(class_path: &str, qualified: bool) -> String {
+    let name = if qualified {
+        class_path.replace('/', ".")
+    } else {
+        class_path.rsplit('/').next().unwrap_or(class_path).to_string()
+    };
+    // Replace $ with . for inner classes
+    name.replace('$', ".")
 }
 
 /// Extract the class name from a field descriptor if it's an object type
@@ -34,9 +49,310 @@ pub fn extract_class_name_from_descriptor(descriptor: &str) -> Option<String> {
     }
 }
 
-/// Parse a method descriptor into (parameters, return_type)
+/// Field descriptors of the `java.util` map types whose type arguments are
+/// resolved as key/value relationships
+const MAP_DESCRIPTORS: [&str; 6] = [
+    "Ljava/util/Map;",
+    "Ljava/util/HashMap;",
+    "Ljava/util/LinkedHashMap;",
+    "Ljava/util/TreeMap;",
+    "Ljava/util/SortedMap;",
+    "Ljava/util/concurrent/ConcurrentHashMap;",
+];
+
+/// Check if a field descriptor refers to one of the common map types
+pub fn is_map_descriptor(descriptor: &str) -> bool {
+    MAP_DESCRIPTORS.contains(&descriptor.trim())
+}
+
+/// Field descriptors of the `java.util` collection types whose element type
+/// is related to with a "many" cardinality
+const COLLECTION_DESCRIPTORS: [&str; 12] = [
+    "Ljava/util/Collection;",
+    "Ljava/util/List;",
+    "Ljava/util/ArrayList;",
+    "Ljava/util/LinkedList;",
+    "Ljava/util/Set;",
+    "Ljava/util/HashSet;",
+    "Ljava/util/LinkedHashSet;",
+    "Ljava/util/TreeSet;",
+    "Ljava/util/SortedSet;",
+    "Ljava/util/Queue;",
+    "Ljava/util/Deque;",
+    "Ljava/util/ArrayDeque;",
+];
+
+/// Check if a field descriptor refers to one of the common collection types
+pub fn is_collection_descriptor(descriptor: &str) -> bool {
+    COLLECTION_DESCRIPTORS.contains(&descriptor.trim())
+}
+
+/// Extract the class name of an array's element type, ignoring any number
+/// of dimensions. Returns None for anything other than an array of objects.
+/// Examples:
+/// - "[Lcom/example/Item;" -> Some("Item")
+/// - "[[Lcom/example/Item;" -> Some("Item")
+/// - "[I" -> None
+/// - "Lcom/example/Item;" -> None
+pub fn extract_array_element_class_name(descriptor: &str) -> Option<String> {
+    let trimmed = descriptor.trim();
+    if !trimmed.starts_with('[') {
+        return None;
+    }
+    extract_class_name_from_descriptor(trimmed.trim_start_matches('['))
+}
+
+/// Extract the simple class names of the top-level type arguments of a
+/// generic signature, keeping their positions. Arguments which aren't class
+/// types (unbounded wildcards, type variables, arrays, primitives) are None.
+/// Bounded wildcards resolve to their bound.
+/// Examples:
+/// - "Ljava/util/Map<Lcom/example/User;Lcom/example/Order;>;" -> [Some("User"), Some("Order")]
+/// - "Ljava/util/List<*>;" -> [None]
+/// - "Ljava/util/List;" -> []
+pub fn extract_type_arguments(signature: &str) -> Vec<Option<String>> {
+    let Some(start) = signature.find('<') else {
+        return Vec::new();
+    };
+
+    let bytes = signature.as_bytes();
+    let mut args = Vec::new();
+    let mut idx = start + 1;
+    while idx < bytes.len() && bytes[idx] != b'>' {
+        match bytes[idx] {
+            b'*' => {
+                args.push(None);
+                idx += 1;
+            }
+            b'+' | b'-' => {
+                let end = skip_signature_type(signature, idx + 1);
+                args.push(signature_class_name(&signature[idx + 1..end]));
+                idx = end;
+            }
+            _ => {
+                let end = skip_signature_type(signature, idx);
+                args.push(signature_class_name(&signature[idx..end]));
+                idx = end;
+            }
+        }
+    }
+    args
+}
+
+/// Collect the class paths (e.g. "com/example/Item") of every class type in a
+/// descriptor or generic signature, including array element types and type
+/// arguments. A generic method's type parameters are skipped.
+/// Examples:
+/// - "(Lcom/example/Item;I)[Lcom/example/Box;" -> ["com/example/Item", "com/example/Box"]
+/// - "(Ljava/util/List<Lcom/example/Item;>;)V" -> ["java/util/List", "com/example/Item"]
+/// - "<T:Ljava/lang/Object;>(TT;)V" -> []
+pub fn referenced_class_paths(signature: &str) -> Vec<String> {
+    let bytes = signature.as_bytes();
+    let mut paths = Vec::new();
+    let mut idx = 0;
+
+    // Type parameters, e.g. `<T:Ljava/lang/Object;>`, only come first
+    if bytes.first() == Some(&b'<') {
+        let mut depth = 0;
+        while idx < bytes.len() {
+            match bytes[idx] {
+                b'<' => depth += 1,
+                b'>' => depth -= 1,
+                _ => {}
+            }
+            idx += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'L' => {
+                let start = idx + 1;
+                let end = signature[start..]
+                    .find(['<', ';'])
+                    .map_or(bytes.len(), |end| start + end);
+                paths.push(signature[start..end].to_string());
+                idx = end + 1;
+            }
+            // Type variables and the names of inner classes of generic
+            // classes (`.Inner`) hold no class paths
+            b'T' | b'.' => {
+                idx += signature[idx..]
+                    .find(['<', ';'])
+                    .map_or(bytes.len() - idx, |end| end + 1);
+            }
+            _ => idx += 1,
+        }
+    }
+    paths
+}
+
+/// Get the simple class name of a class type signature, ignoring any type
+/// arguments. Returns None for anything other than a class type.
+fn signature_class_name(signature: &str) -> Option<String> {
+    let class_path = signature.strip_prefix('L')?;
+    let end = class_path.find(['<', ';']).unwrap_or(class_path.len());
+    let class_path = &class_path[..end];
+    let simple_name = class_path.rsplit('/').next().unwrap_or(class_path);
+    // Replace $ with . for inner classes
+    Some(simple_name.replace('$', "."))
+}
+
+/// Returns the index just past the type signature starting at `start`,
+/// skipping over any nested type arguments.
+fn skip_signature_type(signature: &str, start: usize) -> usize {
+    let bytes = signature.as_bytes();
+    let mut idx = start;
+
+    // Array dimensions prefix the element type
+    while idx < bytes.len() && bytes[idx] == b'[' {
+        idx += 1;
+    }
+
+    match bytes.get(idx) {
+        Some(b'L') | Some(b'T') => {
+            let mut depth = 0;
+            while idx < bytes.len() {
+                match bytes[idx] {
+                    b'<' => depth += 1,
+                    b'>' => depth -= 1,
+                    b';' if depth == 0 => return idx + 1,
+                    _ => {}
+                }
+                idx += 1;
+            }
+            bytes.len()
+        }
+        Some(_) => idx + 1,
+        None => bytes.len(),
+    }
+}
+
+/// Render the generic type signature starting at `start` (as found in a
+/// `Signature` attribute) in Java's `<>` generic notation, which serializers
+/// escape for their format. Returns the rendered type and the number of bytes
+/// consumed.
+///
+/// Wildcard type arguments are rendered compactly so they stay Mermaid-safe
+/// (no spaces or `?`): `?` as `*`, `? extends Foo` as `+Foo` and `? super Foo`
+/// as `-Foo`.
+/// Examples:
+/// - "Ljava/util/List<Lcom/example/Item;>;" -> ("List<Item>", 36)
+/// - "Ljava/util/Map<TK;+Ljava/lang/Number;>;" -> ("Map<K, +Number>", 39)
+/// - "Ljava/util/List<*>;" -> ("List<*>", 19)
+/// - "[TT;" -> ("T[]", 4)
+pub fn parse_signature_type(signature: &str, start: usize, qualified: bool) -> (String, usize) {
+    let bytes = signature.as_bytes();
+    let mut idx = start;
+
+    // Count array dimensions
+    let mut array_depth = 0;
+    while idx < bytes.len() && bytes[idx] == b'[' {
+        array_depth += 1;
+        idx += 1;
+    }
+
+    let mut result = match bytes.get(idx) {
+        Some(b'L') => {
+            let (class_type, consumed) = parse_class_type_signature(signature, idx, qualified);
+            idx += consumed;
+            class_type
+        }
+        Some(b'T') => {
+            // Type variable: TT;
+            let end = signature[idx..]
+                .find(';')
+                .map_or(signature.len(), |end| idx + end);
+            let name = signature[idx + 1..end].to_string();
+            idx = (end + 1).min(signature.len());
+            name
+        }
+        Some(_) => {
+            let (base_type, consumed) = parse_type_internal(signature, idx, qualified);
+            idx += consumed;
+            base_type
+        }
+        None => "Object".to_string(),
+    };
+
+    for _ in 0..array_depth {
+        result.push_str("[]");
+    }
+
+    (result, idx - start)
+}
+
+/// Render a class type signature (`L...;`) starting at `start`, returning the
+/// rendered type and the number of bytes consumed. Inner classes of generic
+/// classes (`Lcom/Outer<TT;>.Inner;`) keep the type arguments of the
+/// innermost class.
+fn parse_class_type_signature(signature: &str, start: usize, qualified: bool) -> (String, usize) {
+    let bytes = signature.as_bytes();
+    let mut idx = start + 1;
+    // The class path followed by the names of any inner classes reached
+    // through `.`
+    let mut segments = vec![String::new()];
+    let mut arguments = Vec::new();
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b';' => {
+                idx += 1;
+                break;
+            }
+            b'.' => {
+                segments.push(String::new());
+                arguments.clear();
+                idx += 1;
+            }
+            b'<' => {
+                idx += 1;
+                while idx < bytes.len() && bytes[idx] != b'>' {
+                    let (argument, consumed) = match bytes[idx] {
+                        b'*' => ("*".to_string(), 1),
+                        bound @ (b'+' | b'-') => {
+                            let (bound_type, consumed) = parse_signature_type(signature, idx + 1, qualified);
+                            (format!("{}{}", bound as char, bound_type), consumed + 1)
+                        }
+                        _ => parse_signature_type(signature, idx, qualified),
+                    };
+                    arguments.push(argument);
+                    // Always make progress on malformed input
+                    idx += consumed.max(1);
+                }
+                idx += 1;
+            }
+            _ => {
+                let end = signature[idx..]
+                    .find([';', '<', '.'])
+                    .map_or(signature.len(), |end| idx + end);
+                if let Some(segment) = segments.last_mut() {
+                    segment.push_str(&signature[idx..end]);
+                }
+                idx = end;
+            }
+        }
+    }
+
+    let mut result = display_class_name(&segments[0], qualified);
+    for inner in &segments[1..] {
+        result.push('.');
+        result.push_str(&inner.replace('$', "."));
+    }
+    if !arguments.is_empty() {
+        result = format!("{}<{}>", result, arguments.join(", "));
+    }
+
+    (result, idx - start)
+}
+
+/// Parse a method descriptor into (parameters, return_type), naming class
+/// types like `parse_field_descriptor`
 /// Example: "(ILjava/lang/String;)V" -> (vec!["int", "String"], "void")
-pub fn parse_method_descriptor(descriptor: &str) -> (Vec<String>, String) {
+pub fn parse_method_descriptor(descriptor: &str, qualified: bool) -> (Vec<String>, String) {
     let mut params = Vec::new();
 
     if !descriptor.starts_with('(') {
@@ -45,28 +361,93 @@ pub fn parse_method_descriptor(descriptor: &str) -> (Vec<String>, String) {
 
     let end_params = descriptor.find(')').unwrap_or(descriptor.len());
     let params_part = &descriptor[1..end_params];
-    let return_part = &descriptor[end_params + 1..];
+    let return_part = descriptor.get(end_params + 1..).unwrap_or_default();
 
     // Parse parameters
     let mut idx = 0;
     while idx < params_part.len() {
-        let (param_type, consumed) = parse_type_internal(params_part, idx);
+        let (param_type, consumed) = parse_type_internal(params_part, idx, qualified);
         params.push(param_type);
-        idx += consumed;
+        // Always make progress on malformed input
+        idx += consumed.max(1);
     }
 
     // Parse return type
     let return_type = if return_part == "V" {
         "void".to_string()
     } else {
-        parse_type_internal(return_part, 0).0
+        parse_type_internal(return_part, 0, qualified).0
+    };
+
+    (params, return_type)
+}
+
+/// The return type of a method descriptor or generic signature, which reads
+/// like a field descriptor or signature. Thrown types (`^...`) are dropped.
+/// Example: "(I)Ljava/util/List<Lcom/example/Item;>;^Ljava/io/IOException;"
+/// -> "Ljava/util/List<Lcom/example/Item;>;"
+pub fn method_return_descriptor(descriptor: &str) -> &str {
+    let return_part = descriptor.split_once(')').map_or("", |(_, return_part)| return_part);
+    return_part.split('^').next().unwrap_or_default()
+}
+
+/// Parse a field's generic signature (from its `Signature` attribute) into a
+/// readable type name with its type arguments
+/// Examples:
+/// - "Ljava/util/List<Lcom/example/Notification;>;" -> "List<Notification>"
+/// - "TT;" -> "T"
+pub fn parse_field_signature(signature: &str, qualified: bool) -> String {
+    parse_signature_type(signature, 0, qualified).0
+}
+
+/// Parse a method's generic signature (from its `Signature` attribute) into
+/// (parameters, return_type), keeping type arguments. The method's own type
+/// parameters (`<T:Ljava/lang/Object;>`) and thrown types (`^...`) are skipped.
+/// Example: "<T:Ljava/lang/Object;>(TT;)Ljava/util/List<TT;>;" -> (vec!["T"], "List<T>")
+pub fn parse_method_signature(signature: &str, qualified: bool) -> (Vec<String>, String) {
+    let bytes = signature.as_bytes();
+    let mut idx = 0;
+
+    // Skip the type parameters, which may themselves hold generic bounds
+    if bytes.first() == Some(&b'<') {
+        let mut depth = 0;
+        while idx < bytes.len() {
+            match bytes[idx] {
+                b'<' => depth += 1,
+                b'>' => depth -= 1,
+                _ => {}
+            }
+            idx += 1;
+            if depth == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut params = Vec::new();
+    if bytes.get(idx) != Some(&b'(') {
+        return (params, "void".to_string());
+    }
+    idx += 1;
+
+    while idx < bytes.len() && bytes[idx] != b')' {
+        let (param_type, consumed) = parse_signature_type(signature, idx, qualified);
+        params.push(param_type);
+        // Always make progress on malformed input
+        idx += consumed.max(1);
+    }
+    idx += 1;
+
+    let return_type = match bytes.get(idx) {
+        Some(b'V') | None => "void".to_string(),
+        Some(_) => parse_signature_type(signature, idx, qualified).0,
     };
 
     (params, return_type)
 }
 
 /// Internal helper that returns (type_name, bytes_consumed)
-fn parse_type_internal(descriptor: &str, start: usize) -> (String, usize) {
+fn parse_type_internal(descriptor: &str, start: usize, qualified: bool) -> (String, usize) {
     if start >= descriptor.len() {
         return ("void".to_string(), 0);
     }
@@ -96,14 +477,24 @@ fn parse_type_internal(descriptor: &str, start: usize) -> (String, usize) {
         b'Z' => ("boolean".to_string(), 1),
         b'V' => ("void".to_string(), 1),
         b'L' => {
-            // Object type: Ljava/lang/String;
-            let end = descriptor[idx..].find(';').unwrap_or(descriptor.len() - idx);
-            let class_path = &descriptor[idx + 1..idx + end];
+            // Object type: Ljava/lang/String; A truncated descriptor without
+            // the `;` takes the rest of the string as the class name.
+            let (class_path, consumed) = match descriptor[idx..].find(';') {
+                Some(end) => (&descriptor[idx + 1..idx + end], end + 1),
+                None => (&descriptor[idx + 1..], descriptor.len() - idx),
+            };
             let simple_name = class_path.rsplit('/').next().unwrap_or(class_path);
-            // Replace $ with . for inner classes
-            (simple_name.replace('$', "."), end + 1)
+            if simple_name.is_empty() {
+                ("Object".to_string(), consumed)
+            } else {
+                (display_class_name(class_path, qualified), consumed)
+            }
+        }
+        // Skip a whole character so later slices stay on a char boundary
+        _ => {
+            let width = descriptor[idx..].chars().next().map_or(1, char::len_utf8);
+            ("Object".to_string(), width)
         }
-        _ => ("Object".to_string(), 1),
     };
 
     let mut result = base_type;
@@ -120,35 +511,280 @@ mod tests {
 
     #[test]
     fn test_primitives() {
-        assert_eq!(parse_field_descriptor("I"), "int");
-        assert_eq!(parse_field_descriptor("J"), "long");
-        assert_eq!(parse_field_descriptor("Z"), "boolean");
+        assert_eq!(parse_field_descriptor("I", false), "int");
+        assert_eq!(parse_field_descriptor("J", false), "long");
+        assert_eq!(parse_field_descriptor("Z", false), "boolean");
     }
 
     #[test]
     fn test_objects() {
-        assert_eq!(parse_field_descriptor("Ljava/lang/String;"), "String");
-        assert_eq!(parse_field_descriptor("Ljava/util/List;"), "List");
+        assert_eq!(parse_field_descriptor("Ljava/lang/String;", false), "String");
+        assert_eq!(parse_field_descriptor("Ljava/util/List;", false), "List");
     }
 
     #[test]
     fn test_arrays() {
-        assert_eq!(parse_field_descriptor("[I"), "int[]");
-        assert_eq!(parse_field_descriptor("[[Ljava/lang/String;"), "String[][]");
+        assert_eq!(parse_field_descriptor("[I", false), "int[]");
+        assert_eq!(parse_field_descriptor("[[Ljava/lang/String;", false), "String[][]");
+    }
+
+    #[test]
+    fn test_qualified_types() {
+        assert_eq!(parse_field_descriptor("Ljava/util/List;", true), "java.util.List");
+        assert_eq!(parse_field_descriptor("[Lcom/example/Outer$Inner;", true), "com.example.Outer.Inner[]");
+        assert_eq!(parse_field_descriptor("Lcom/example/Default;", true), "com.example.Default");
+        assert_eq!(parse_field_descriptor("LTopLevel;", true), "TopLevel");
+        assert_eq!(parse_field_descriptor("I", true), "int");
+        assert_eq!(
+            parse_field_signature("Ljava/util/Map<Ljava/lang/String;+Lcom/example/Item;>;", true),
+            "java.util.Map<java.lang.String, +com.example.Item>"
+        );
+        assert_eq!(
+            parse_field_signature("Lcom/example/Outer<TT;>.Inner;", true),
+            "com.example.Outer.Inner"
+        );
+        assert_eq!(
+            parse_method_descriptor("(Ljava/lang/String;)Ljava/util/List;", true),
+            (vec!["java.lang.String".to_string()], "java.util.List".to_string())
+        );
+    }
+
+    #[test]
+    fn test_array_element_class_name() {
+        assert_eq!(
+            extract_array_element_class_name("[Lcom/example/Item;"),
+            Some("Item".to_string())
+        );
+        assert_eq!(
+            extract_array_element_class_name("[[Lcom/example/Outer$Inner;"),
+            Some("Outer.Inner".to_string())
+        );
+        assert_eq!(extract_array_element_class_name("[I"), None);
+        assert_eq!(extract_array_element_class_name("Lcom/example/Item;"), None);
+        assert!(is_collection_descriptor("Ljava/util/List;"));
+        assert!(!is_collection_descriptor("Ljava/util/Map;"));
+    }
+
+    #[test]
+    fn test_referenced_class_paths() {
+        assert_eq!(
+            referenced_class_paths("(Lcom/example/Item;I)[Lcom/example/Box;"),
+            ["com/example/Item", "com/example/Box"]
+        );
+        assert_eq!(
+            referenced_class_paths("(Ljava/util/Map<TK;Lcom/example/Item;>;)V"),
+            ["java/util/Map", "com/example/Item"]
+        );
+        assert_eq!(
+            referenced_class_paths("<T:Lcom/example/Bound;>(TT;)Lcom/example/Outer<TT;>.Leaf;"),
+            ["com/example/Outer"]
+        );
+        assert_eq!(referenced_class_paths("()V"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_malformed_descriptors() {
+        // Missing semicolons take the rest of the descriptor
+        assert_eq!(parse_field_descriptor("Ljava/lang/String", false), "String");
+        assert_eq!(parse_type_internal("Ljava/lang/String", 0, false), ("String".to_string(), 17));
+        assert_eq!(parse_type_internal("[L", 0, false), ("Object[]".to_string(), 2));
+        assert_eq!(parse_type_internal("L;", 0, false), ("Object".to_string(), 2));
+        assert_eq!(
+            parse_method_descriptor("(ILjava/lang/String", false),
+            (vec!["int".to_string(), "String".to_string()], "void".to_string())
+        );
+
+        // Empty descriptors
+        assert_eq!(parse_field_descriptor("", false), "void");
+        assert_eq!(parse_method_descriptor("", false), (vec![], "void".to_string()));
+        assert_eq!(parse_method_descriptor("(", false), (vec![], "void".to_string()));
+        assert_eq!(parse_method_descriptor("()", false), (vec![], "void".to_string()));
+
+        // Trailing garbage is ignored and unknown characters always make progress
+        assert_eq!(parse_field_descriptor("Ljava/lang/String;xyz", false), "String");
+        assert_eq!(
+            parse_method_descriptor("(I)Vxyz", false),
+            (vec!["int".to_string()], "void".to_string())
+        );
+        assert_eq!(
+            parse_method_descriptor("(Iλ)I", false),
+            (vec!["int".to_string(), "Object".to_string()], "int".to_string())
+        );
+        assert_eq!(parse_type_internal("λ", 0, false), ("Object".to_string(), 2));
+    }
+
+    #[test]
+    fn test_type_arguments() {
+        assert_eq!(
+            extract_type_arguments("Ljava/util/Map<Lcom/example/User;Lcom/example/Order;>;"),
+            vec![Some("User".to_string()), Some("Order".to_string())]
+        );
+        assert_eq!(
+            extract_type_arguments(
+                "Ljava/util/Map<Ljava/lang/String;Ljava/util/List<Lcom/example/Order;>;>;"
+            ),
+            vec![Some("String".to_string()), Some("List".to_string())]
+        );
+        assert_eq!(
+            extract_type_arguments("Ljava/util/Map<TK;+Lcom/example/Order;>;"),
+            vec![None, Some("Order".to_string())]
+        );
+        assert_eq!(extract_type_arguments("Ljava/util/List<*>;"), vec![None]);
+        assert!(extract_type_arguments("Ljava/util/List;").is_empty());
+    }
+
+    #[test]
+    fn test_signature_types() {
+        assert_eq!(
+            parse_signature_type("Ljava/util/List<Lcom/example/Item;>;", 0, false),
+            ("List<Item>".to_string(), 36)
+        );
+        assert_eq!(
+            parse_signature_type("Ljava/util/Map<TK;+Ljava/lang/Number;>;", 0, false),
+            ("Map<K, +Number>".to_string(), 39)
+        );
+        assert_eq!(parse_signature_type("TT;", 0, false), ("T".to_string(), 3));
+        assert_eq!(parse_signature_type("[TT;", 0, false), ("T[]".to_string(), 4));
+        assert_eq!(parse_signature_type("I", 0, false), ("int".to_string(), 1));
+        assert_eq!(
+            parse_signature_type("Lcom/example/Outer<TT;>.Inner;", 0, false),
+            ("Outer.Inner".to_string(), 30)
+        );
+        assert_eq!(
+            parse_signature_type("Lcom/example/Outer$Nested;", 0, false),
+            ("Outer.Nested".to_string(), 26)
+        );
+    }
+
+    #[test]
+    fn test_nested_signature_types() {
+        // Two levels, with the nested list as the last argument
+        let signature = "Ljava/util/Map<Ljava/lang/String;Ljava/util/List<Ljava/lang/Integer;>;>;";
+        assert_eq!(
+            parse_signature_type(signature, 0, false),
+            ("Map<String, List<Integer>>".to_string(), signature.len())
+        );
+
+        // Two levels, with the nested map as the first argument
+        let signature = "Ljava/util/Map<Ljava/util/Map<TK;TV;>;Ljava/lang/String;>;";
+        assert_eq!(
+            parse_signature_type(signature, 0, false),
+            ("Map<Map<K, V>, String>".to_string(), signature.len())
+        );
+
+        // Three levels, each with several arguments
+        let signature = "Ljava/util/Map<Ljava/lang/String;Ljava/util/Map<TK;Ljava/util/List<[Ljava/lang/Integer;>;>;>;";
+        assert_eq!(
+            parse_signature_type(signature, 0, false),
+            ("Map<String, Map<K, List<Integer[]>>>".to_string(), signature.len())
+        );
+        let signature = "Lcom/example/Triple<Ljava/util/List<Ljava/util/Set<TT;>;>;TU;Ljava/util/Map<Ljava/util/List<*>;-TT;>;>;";
+        assert_eq!(
+            parse_signature_type(signature, 0, false),
+            ("Triple<List<Set<T>>, U, Map<List<*>, -T>>".to_string(), signature.len())
+        );
+
+        // Consumes exactly one type when followed by others
+        let signature = "(Ljava/util/Map<Ljava/lang/String;Ljava/util/List<Ljava/lang/Integer;>;>;I)V";
+        assert_eq!(
+            parse_signature_type(signature, 1, false),
+            ("Map<String, List<Integer>>".to_string(), 72)
+        );
+        assert_eq!(
+            parse_method_signature(signature, false),
+            (vec!["Map<String, List<Integer>>".to_string(), "int".to_string()], "void".to_string())
+        );
+    }
+
+    #[test]
+    fn test_signature_wildcards() {
+        assert_eq!(
+            parse_signature_type("Ljava/util/List<*>;", 0, false).0,
+            "List<*>"
+        );
+        assert_eq!(
+            parse_signature_type("Ljava/util/List<+Lcom/example/Shape;>;", 0, false).0,
+            "List<+Shape>"
+        );
+        assert_eq!(
+            parse_signature_type("Ljava/util/Comparator<-Ljava/lang/Integer;>;", 0, false).0,
+            "Comparator<-Integer>"
+        );
+        assert_eq!(
+            parse_signature_type("Ljava/util/Map<*+Ljava/util/List<-TT;>;>;", 0, false).0,
+            "Map<*, +List<-T>>"
+        );
+    }
+
+    #[test]
+    fn test_field_signature() {
+        assert_eq!(
+            parse_field_signature("Ljava/util/List<Lcom/example/Notification;>;", false),
+            "List<Notification>"
+        );
+        assert_eq!(
+            parse_field_signature("Ljava/util/Map<Ljava/lang/String;Ljava/util/List<+TT;>;>;", false),
+            "Map<String, List<+T>>"
+        );
+        assert_eq!(parse_field_signature("[TE;", false), "E[]");
+    }
+
+    #[test]
+    fn test_method_signature() {
+        assert_eq!(
+            parse_method_signature("()Ljava/util/Map<Ljava/lang/String;Ljava/lang/Integer;>;", false),
+            (vec![], "Map<String, Integer>".to_string())
+        );
+        assert_eq!(
+            parse_method_signature(
+                "<T::Ljava/lang/Comparable<-TT;>;>(Ljava/util/List<TT;>;I)TT;^Ljava/io/IOException;",
+                false
+            ),
+            (vec!["List<T>".to_string(), "int".to_string()], "T".to_string())
+        );
+        assert_eq!(
+            parse_method_signature("(Ljava/util/Set<*>;)V", false),
+            (vec!["Set<*>".to_string()], "void".to_string())
+        );
+    }
+
+    #[test]
+    fn test_method_return_descriptor() {
+        assert_eq!(method_return_descriptor("()Lcom/example/Coach;"), "Lcom/example/Coach;");
+        assert_eq!(method_return_descriptor("(I)V"), "V");
+        assert_eq!(
+            method_return_descriptor("<T:Ljava/lang/Object;>(TT;)Ljava/util/List<TT;>;^Ljava/io/IOException;"),
+            "Ljava/util/List<TT;>;"
+        );
+        assert_eq!(method_return_descriptor(""), "");
     }
 
     #[test]
     fn test_method_descriptor() {
-        let (params, ret) = parse_method_descriptor("()V");
+        let (params, ret) = parse_method_descriptor("()V", false);
         assert_eq!(params, Vec::<String>::new());
         assert_eq!(ret, "void");
 
-        let (params, ret) = parse_method_descriptor("(I)V");
+        let (params, ret) = parse_method_descriptor("(I)V", false);
         assert_eq!(params, vec!["int"]);
         assert_eq!(ret, "void");
 
-        let (params, ret) = parse_method_descriptor("(ILjava/lang/String;)Ljava/lang/Object;");
+        let (params, ret) = parse_method_descriptor("(ILjava/lang/String;)Ljava/lang/Object;", false);
         assert_eq!(params, vec!["int", "String"]);
         assert_eq!(ret, "Object");
     }
+
+    #[test]
+    fn test_malformed_method_descriptor() {
+        // Nothing left to parse consumes nothing, which mustn't stall the
+        // parameter loop
+        assert_eq!(parse_type_internal("I", 1, false), ("void".to_string(), 0));
+
+        // Degenerate descriptors still terminate with something to show
+        assert_eq!(parse_method_descriptor("(", false), (vec![], "void".to_string()));
+        assert_eq!(parse_method_descriptor("([[[", false).0, vec!["void"]);
+        assert_eq!(parse_method_descriptor("(Lcom/example/Item", false).0, vec!["Item"]);
+        assert_eq!(parse_method_descriptor("(\u{e9}\u{e9}I)V", false).0, vec!["Object", "Object", "int"]);
+        assert_eq!(parse_method_descriptor("(L;L;)", false).0, vec!["Object", "Object"]);
+    }
 }
@@ -0,0 +1,118 @@
+//! Warnings and errors printed to stderr, colored when stderr is a terminal,
+//! along with informational messages printed when verbose. Quiet mode leaves
+//! only errors, even when verbose.
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// When diagnostics are colored
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Color only when stderr is a terminal
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Whether diagnostics are currently colored. Off until `set_color` is
+/// called, so library users get plain text.
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Whether informational messages are printed. Off until `set_verbose` is
+/// called.
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Whether everything but errors is silenced. Off until `set_quiet` is
+/// called.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+const CYAN: &str = "\x1b[36m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// Decide whether diagnostics printed from now on are colored
+pub fn set_color(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Auto => std::io::stderr().is_terminal(),
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+    };
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+/// Decide whether informational messages printed from now on are shown
+pub fn set_verbose(enabled: bool) {
+    VERBOSE.store(enabled, Ordering::Relaxed);
+}
+
+/// Decide whether messages other than errors printed from now on are
+/// silenced, which takes precedence over verbose
+pub fn set_quiet(enabled: bool) {
+    QUIET.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether messages other than errors are silenced
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Format a diagnostic as `LEVEL: message`, coloring the level if asked to
+fn format_diagnostic(level: &str, color: &str, message: impl Display, colored: bool) -> String {
+    if colored {
+        format!("{}{}{}: {}", color, level, RESET, message)
+    } else {
+        format!("{}: {}", level, message)
+    }
+}
+
+/// Print an informational message to stderr, if verbose and not quiet
+pub fn info(message: impl Display) {
+    if !VERBOSE.load(Ordering::Relaxed) || is_quiet() {
+        return;
+    }
+    let colored = COLOR.load(Ordering::Relaxed);
+    eprintln!("{}", format_diagnostic("INFO", CYAN, message, colored));
+}
+
+/// Print a warning to stderr, unless quiet
+pub fn warn(message: impl Display) {
+    if is_quiet() {
+        return;
+    }
+    let colored = COLOR.load(Ordering::Relaxed);
+    eprintln!("{}", format_diagnostic("WARN", YELLOW, message, colored));
+}
+
+/// Print an error to stderr
+pub fn error(message: impl Display) {
+    let colored = COLOR.load(Ordering::Relaxed);
+    eprintln!("{}", format_diagnostic("ERROR", RED, message, colored));
+}
+
+/// Print a status line (e.g. where the output was written) to stdout, unless
+/// quiet
+pub fn status(message: impl Display) {
+    if !is_quiet() {
+        println!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_diagnostic() {
+        assert_eq!(
+            format_diagnostic("WARN", YELLOW, "careful", false),
+            "WARN: careful"
+        );
+        assert_eq!(
+            format_diagnostic("ERROR", RED, "broken", true),
+            "\x1b[31mERROR\x1b[0m: broken"
+        );
+    }
+}
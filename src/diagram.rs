@@ -0,0 +1,190 @@
+//! The class diagram umlink links and serializes
+//!
+//! This mirrors `mermaid_parser::types::Diagram`, with room for what the
+//! parser's types have none for: the arrow a relation is drawn with, the
+//! stereotypes of a class beyond its first and the modifiers of its members
+//! (thrown exceptions, constant values, deprecation, ...). These are kept as
+//! fields and only turned into text when the diagram is serialized. Seed
+//! diagrams are parsed with `mermaid_parser::parserv2` and converted.
+
+use mermaid_parser::types::{self, Parameter, RelationKind, TypeNotation, Visibility};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+/// A class diagram: its frontmatter, its classes by namespace and the
+/// relations between them
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diagram<'a> {
+    pub yaml: Option<serde_yml::Value>,
+    pub namespaces: BTreeMap<Cow<'a, str>, Namespace<'a>>,
+    pub relations: Vec<Relation<'a>>,
+}
+
+/// The classes of a namespace keyed by name
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Namespace<'a> {
+    pub classes: BTreeMap<Cow<'a, str>, Class<'a>>,
+}
+
+/// A class with its stereotypes and members
+#[derive(Debug, Clone, PartialEq)]
+pub struct Class<'a> {
+    pub name: Cow<'a, str>,
+    /// The class's kind as a stereotype (e.g. `interface` or `enumeration`)
+    pub annotation: Option<Cow<'a, str>>,
+    /// Stereotypes following the annotation, such as `sealed`, `final` or
+    /// `deprecated`
+    pub stereotypes: Vec<Cow<'a, str>>,
+    pub members: Vec<Member<'a>>,
+}
+
+impl Class<'_> {
+    /// The annotation and the other stereotypes joined into one, as written
+    /// between `<<` and `>>` (e.g. `interface, sealed`)
+    pub fn stereotype_text(&self) -> Option<String> {
+        let stereotypes: Vec<&str> = self
+            .annotation
+            .iter()
+            .chain(&self.stereotypes)
+            .map(Cow::as_ref)
+            .collect();
+        (!stereotypes.is_empty()).then(|| stereotypes.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Member<'a> {
+    Attribute(Attribute<'a>),
+    Method(Method<'a>),
+}
+
+/// A field
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute<'a> {
+    pub visibility: Visibility,
+    pub name: Cow<'a, str>,
+    pub data_type: Option<Cow<'a, str>>,
+    pub is_static: bool,
+    pub type_notation: TypeNotation,
+    /// Value of a constant as written in Java (e.g. `"rocket"`)
+    pub value: Option<Cow<'a, str>>,
+    pub is_deprecated: bool,
+}
+
+/// A method or constructor
+#[derive(Debug, Clone, PartialEq)]
+pub struct Method<'a> {
+    pub visibility: Visibility,
+    pub name: Cow<'a, str>,
+    pub parameters: Vec<Parameter<'a>>,
+    /// Type the method returns, `None` for constructors
+    pub return_type: Option<Cow<'a, str>>,
+    pub is_static: bool,
+    pub is_abstract: bool,
+    pub return_type_notation: TypeNotation,
+    /// Default value of an annotation element as written in Java
+    pub default_value: Option<Cow<'a, str>>,
+    /// Simple names of the checked exceptions the method declares
+    pub throws: Vec<Cow<'a, str>>,
+    /// An interface method with a body
+    pub is_default: bool,
+    pub is_deprecated: bool,
+}
+
+/// A relation directed from its `tail` to its `head`, with a kind, optional
+/// cardinalities on either end and an optional label
+#[derive(Debug, Clone, PartialEq)]
+pub struct Relation<'a> {
+    pub tail: Cow<'a, str>,
+    pub head: Cow<'a, str>,
+    pub kind: RelationKind,
+    pub cardinality_tail: Option<Cow<'a, str>>,
+    pub cardinality_head: Option<Cow<'a, str>>,
+    pub label: Option<Cow<'a, str>>,
+    /// Mermaid arrow token drawn in place of the kind's standard arrow, such
+    /// as a user defined relation kind's (e.g. `..>>`) or the undirected `--`
+    /// of links. `None` draws the standard arrow.
+    pub arrow: Option<Cow<'a, str>>,
+}
+
+impl Relation<'_> {
+    /// Check if the relation is drawn without a head marker (e.g. `--`), in
+    /// which case it doesn't point either way
+    pub fn is_undirected(&self) -> bool {
+        self.arrow
+            .as_deref()
+            .is_some_and(|arrow| arrow.chars().all(|c| c == '-' || c == '.'))
+    }
+}
+
+impl<'a> From<types::Relation<'a>> for Relation<'a> {
+    fn from(relation: types::Relation<'a>) -> Self {
+        Relation {
+            tail: relation.tail,
+            head: relation.head,
+            kind: relation.kind,
+            cardinality_tail: relation.cardinality_tail,
+            cardinality_head: relation.cardinality_head,
+            label: relation.label,
+            arrow: None,
+        }
+    }
+}
+
+impl<'a> From<types::Member<'a>> for Member<'a> {
+    fn from(member: types::Member<'a>) -> Self {
+        match member {
+            types::Member::Attribute(attribute) => Member::Attribute(Attribute {
+                visibility: attribute.visibility,
+                name: attribute.name,
+                data_type: attribute.data_type,
+                is_static: attribute.is_static,
+                type_notation: attribute.type_notation,
+                value: None,
+                is_deprecated: false,
+            }),
+            types::Member::Method(method) => Member::Method(Method {
+                visibility: method.visibility,
+                name: method.name,
+                parameters: method.parameters,
+                return_type: method.return_type,
+                is_static: method.is_static,
+                is_abstract: method.is_abstract,
+                return_type_notation: method.return_type_notation,
+                default_value: None,
+                throws: Vec::new(),
+                is_default: false,
+                is_deprecated: false,
+            }),
+        }
+    }
+}
+
+impl<'a> From<types::Class<'a>> for Class<'a> {
+    fn from(class: types::Class<'a>) -> Self {
+        Class {
+            name: class.name,
+            annotation: class.annotation,
+            stereotypes: Vec::new(),
+            members: class.members.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<'a> From<types::Diagram<'a>> for Diagram<'a> {
+    fn from(diagram: types::Diagram<'a>) -> Self {
+        let namespaces = diagram.namespaces.into_iter().map(|(name, namespace)| {
+            let classes = namespace
+                .classes
+                .into_iter()
+                .map(|(name, class)| (name, class.into()))
+                .collect();
+            (name, Namespace { classes })
+        });
+        Diagram {
+            yaml: diagram.yaml,
+            namespaces: namespaces.collect(),
+            relations: diagram.relations.into_iter().map(Into::into).collect(),
+        }
+    }
+}
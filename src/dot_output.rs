@@ -0,0 +1,338 @@
+//! Serialization of a diagram into a Graphviz DOT graph
+//!
+//! Each class is a record shaped node listing its members, and relations are
+//! edges styled after their UML arrows, so very large diagrams can be laid
+//! out with Graphviz rather than Mermaid.
+
+use crate::diagram::{Class, Diagram, Member, Relation};
+use crate::graph::has_class;
+use crate::manual_relations::parse_manual_relation;
+use crate::mermaid_output::{
+    NamespaceOrder, SerializeOptions, external_classes, hidden_member_counts, namespace_depth,
+    node_name, ordered_classes, relation_kind_rank, synthetic_relation_label, undefined_endpoints,
+};
+use crate::plantuml_output::serialize_member;
+use mermaid_parser::types::{DEFAULT_NAMESPACE, RelationKind};
+use std::collections::BTreeMap;
+
+/// Serialize a whole diagram as a `digraph`. Namespaces become clusters. The
+/// frontmatter has no DOT equivalent and is left out.
+pub fn serialize_dot(diagram: &Diagram, options: &SerializeOptions) -> String {
+    let mut out = String::from("digraph classDiagram {\n");
+    out.push_str("  rankdir=BT;\n");
+    out.push_str("  node [shape=record, fontname=\"Helvetica\"];\n");
+    out.push_str("  edge [fontname=\"Helvetica\"];\n");
+
+    let mut namespaces: Vec<_> = diagram.namespaces.iter().collect();
+    if options.namespace_order == NamespaceOrder::Depth {
+        namespaces.sort_by_key(|(namespace_name, _)| namespace_depth(namespace_name));
+    }
+
+    for (namespace_name, namespace) in namespaces {
+        let is_default = namespace_name == DEFAULT_NAMESPACE;
+        let indent = if is_default { "  " } else { "    " };
+        if !is_default {
+            out.push_str(&format!(
+                "  subgraph {} {{\n    label={};\n",
+                quote(&format!("cluster_{}", namespace_name)),
+                quote(namespace_name)
+            ));
+        }
+        for class in ordered_classes(namespace, options) {
+            out.push_str(indent);
+            serialize_class(&mut out, class, options);
+        }
+        if !is_default {
+            out.push_str("  }\n");
+        }
+    }
+
+    if options.stub_referenced_types {
+        for name in undefined_endpoints(diagram) {
+            out.push_str(&format!(
+                "  {} [shape=box];\n",
+                quote(&node_name(name, options))
+            ));
+        }
+    }
+
+    if options.mark_external {
+        for name in external_classes(diagram) {
+            out.push_str(&format!(
+                "  {} [shape=box, style=dashed, label=\"«external»\\n{}\"];\n",
+                quote(&node_name(name, options)),
+                escape_string(&node_name(name, options))
+            ));
+        }
+    }
+
+    for (class_name, links) in &options.member_links {
+        for (member, target) in links {
+            out.push_str(&format!(
+                "  // link {}.{} \"{}\"\n",
+                node_name(class_name, options),
+                member,
+                target
+            ));
+        }
+    }
+
+    // Attributes given to a node again are added to its earlier ones
+    for (class_name, url) in &options.source_links {
+        if has_class(diagram, class_name) {
+            out.push_str(&format!(
+                "  {} [URL={}];\n",
+                quote(&node_name(class_name, options)),
+                quote(url)
+            ));
+        }
+    }
+
+    // Notes are separate nodes tied to their class by a plain dashed edge
+    for (class_name, text) in &options.notes {
+        if has_class(diagram, class_name) {
+            let name = node_name(class_name, options);
+            let note = quote(&format!("note_{}", name));
+            out.push_str(&format!(
+                "  {} [shape=note, label={}];\n  {} -> {} [style=dashed, arrowhead=none];\n",
+                note,
+                quote(text),
+                note,
+                quote(&name)
+            ));
+        }
+    }
+
+    // Hand authored relations are Mermaid text, parsed one at a time so each
+    // becomes an edge
+    for line in &options.manual_relations {
+        if let Some(relation) = parse_manual_relation(line) {
+            serialize_relation(&mut out, &relation, options);
+        }
+    }
+
+    if options.group_relations_by_source {
+        let mut groups: BTreeMap<&str, Vec<&Relation>> = BTreeMap::new();
+        for relation in &diagram.relations {
+            groups.entry(&relation.tail).or_default().push(relation);
+        }
+        for group in groups.values_mut() {
+            group.sort_by(|a, b| {
+                a.head
+                    .cmp(&b.head)
+                    .then_with(|| relation_kind_rank(a.kind).cmp(&relation_kind_rank(b.kind)))
+            });
+            for relation in group.iter() {
+                serialize_relation(&mut out, relation, options);
+            }
+        }
+    } else {
+        for relation in &diagram.relations {
+            serialize_relation(&mut out, relation, options);
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Quote an identifier or attribute value as a DOT string
+fn quote(text: &str) -> String {
+    format!("\"{}\"", escape_string(text))
+}
+
+/// Escape the characters DOT strings give a meaning to
+fn escape_string(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escape the characters record labels give a meaning to, on top of those of
+/// DOT strings
+fn escape_record(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '{' | '}' | '|' | '<' | '>' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Serialize a class as a record node of its name (with its annotation as a
+/// stereotype), its attributes and its methods, each member on a left
+/// justified line. Complexity badges become the node's external label.
+pub fn serialize_class(out: &mut String, class: &Class, options: &SerializeOptions) {
+    let name = node_name(&class.name, options);
+    let mut title = String::new();
+    if let Some(stereotypes) = class.stereotype_text() {
+        title.push_str(&format!("«{}»\\n", escape_record(&stereotypes)));
+    }
+    title.push_str(&escape_record(&name));
+
+    let mut attributes = String::new();
+    let mut methods = String::new();
+    for member in &class.members {
+        let line = format!("{}\\l", escape_record(&serialize_member(member)));
+        match member {
+            Member::Attribute(_) => attributes.push_str(&line),
+            Member::Method(_) => methods.push_str(&line),
+        }
+    }
+    let (hidden_attributes, hidden_methods) = hidden_member_counts(class, options);
+    if hidden_attributes > 0 {
+        attributes.push_str(&format!("... ({} more)\\l", hidden_attributes));
+    }
+    if hidden_methods > 0 {
+        methods.push_str(&format!("... ({} more)\\l", hidden_methods));
+    }
+    for (superclass, members) in options
+        .inherited_members
+        .get(class.name.as_ref())
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+    {
+        methods.push_str(&format!(
+            "«inherited from {}»\\l",
+            escape_record(&node_name(superclass, options))
+        ));
+        for member in members {
+            methods.push_str(&format!("{}\\l", escape_record(member)));
+        }
+    }
+
+    out.push_str(&format!(
+        "{} [label=\"{{{}|{}|{}}}\"",
+        quote(&name),
+        title,
+        attributes,
+        methods
+    ));
+    if let Some(complexity) = options.complexity_badges.get(class.name.as_ref()) {
+        out.push_str(&format!(", xlabel=\"complexity: {}\"", complexity));
+    }
+    out.push_str("];\n");
+}
+
+/// Graphviz edge attributes drawing a relation kind's UML arrow at the head
+fn edge_style(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Inheritance => "arrowhead=empty",
+        RelationKind::Realization => "arrowhead=empty, style=dashed",
+        RelationKind::Composition => "arrowhead=diamond",
+        RelationKind::Aggregation => "arrowhead=odiamond",
+        RelationKind::Association => "arrowhead=vee",
+        RelationKind::Dependency => "arrowhead=vee, style=dashed",
+    }
+}
+
+/// Serialize a relation as an edge from its tail to its head, with its
+/// cardinalities at either end and its label in the middle
+pub fn serialize_relation(out: &mut String, relation: &Relation, options: &SerializeOptions) {
+    // Custom arrows without a head marker (e.g. `--` for links) are undirected
+    let undirected = relation.is_undirected();
+    let style = match relation.kind {
+        RelationKind::Dependency if undirected => "arrowhead=none, style=dashed",
+        _ if undirected => "arrowhead=none",
+        kind => edge_style(kind),
+    };
+    out.push_str(&format!(
+        "  {} -> {} [{}",
+        quote(&node_name(&relation.tail, options)),
+        quote(&node_name(&relation.head, options)),
+        style
+    ));
+    if let Some(cardinality) = &relation.cardinality_tail {
+        out.push_str(&format!(", taillabel={}", quote(cardinality)));
+    }
+    if let Some(cardinality) = &relation.cardinality_head {
+        out.push_str(&format!(", headlabel={}", quote(cardinality)));
+    }
+    if let Some(label) = &relation.label {
+        out.push_str(&format!(", label={}", quote(label)));
+    } else if options.synthetic_relation_labels {
+        out.push_str(&format!(
+            ", label={}",
+            quote(synthetic_relation_label(relation.kind))
+        ));
+    }
+    out.push_str("];\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::{Attribute, Namespace};
+    use mermaid_parser::types::{TypeNotation, Visibility};
+
+    #[test]
+    fn test_serialize_dot() {
+        let mut namespace = Namespace::default();
+        namespace.classes.insert(
+            "Registry".into(),
+            Class {
+                name: "Registry".into(),
+                annotation: Some("interface".into()),
+                stereotypes: Vec::new(),
+                members: vec![Member::Attribute(Attribute {
+                    visibility: Visibility::Private,
+                    name: "items".into(),
+                    data_type: Some("List<Item>".into()),
+                    is_static: false,
+                    type_notation: TypeNotation::Postfix,
+                    value: None,
+                    is_deprecated: false,
+                })],
+            },
+        );
+        let mut diagram = Diagram::default();
+        diagram
+            .namespaces
+            .insert(DEFAULT_NAMESPACE.into(), namespace);
+        diagram.relations.push(Relation {
+            tail: "Registry".into(),
+            head: "Item".into(),
+            kind: RelationKind::Aggregation,
+            cardinality_tail: None,
+            cardinality_head: Some("*".into()),
+            label: Some("holds".into()),
+            arrow: None,
+        });
+
+        assert_eq!(
+            serialize_dot(&diagram, &SerializeOptions::default()),
+            "digraph classDiagram {\n  \
+             rankdir=BT;\n  \
+             node [shape=record, fontname=\"Helvetica\"];\n  \
+             edge [fontname=\"Helvetica\"];\n  \
+             \"Registry\" [label=\"{«interface»\\nRegistry|-items : List\\<Item\\>\\l|}\"];\n  \
+             \"Registry\" -> \"Item\" [arrowhead=odiamond, headlabel=\"*\", label=\"holds\"];\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_undirected_custom_arrow() {
+        let options = SerializeOptions::default();
+        fn relation(tail: &str, head: &str, arrow: Option<&str>) -> Relation<'static> {
+            Relation {
+                tail: tail.to_string().into(),
+                head: head.to_string().into(),
+                kind: RelationKind::Association,
+                cardinality_tail: None,
+                cardinality_head: None,
+                label: None,
+                arrow: arrow.map(|arrow| arrow.to_string().into()),
+            }
+        }
+
+        let mut out = String::new();
+        serialize_relation(&mut out, &relation("Team", "Player", Some("--")), &options);
+        serialize_relation(&mut out, &relation("Team", "Coach", None), &options);
+        assert_eq!(
+            out,
+            "  \"Team\" -> \"Player\" [arrowhead=none];\n  \
+             \"Team\" -> \"Coach\" [arrowhead=vee];\n"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Diagnostic report describing why a class was rendered the way it was
+
+use crate::classfile_utils::{
+    MemberOptions, MinVisibility, field_exclusion, get_full_class_name, get_interface_full_names,
+    get_interface_names, get_package_name, get_signature, get_superclass_full_name,
+    get_superclass_name, get_utf8, has_annotation, method_exclusion,
+};
+use crate::descriptor::{
+    extract_array_element_class_name, extract_class_name_from_descriptor, extract_type_arguments,
+    method_return_descriptor, referenced_class_paths,
+};
+use crate::diagram::{Diagram, Relation};
+use crate::mermaid_output::{SerializeOptions, serialize_relation};
+use jclassfile::{attributes::Attribute, class_file::ClassFile, constant_pool::ConstantPool};
+use mermaid_parser::types::{DEFAULT_NAMESPACE, RelationKind};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Settings the diagram was built with which decide how members and
+/// relations are rendered
+pub struct ExplainContext<'a> {
+    /// Annotations which hide a member
+    pub skip_annotations: &'a [String],
+    /// Annotations which turn a field into a relation
+    pub relationship_annotations: &'a [String],
+    /// Whether constructors are listed
+    pub show_constructors: bool,
+    /// The least visible members listed
+    pub visibility: MinVisibility,
+    pub serialize_options: &'a SerializeOptions,
+}
+
+/// Find the first of `annotations` present in `attributes`
+fn find_annotation<'a>(
+    constant_pool: &[ConstantPool],
+    attributes: &[Attribute],
+    annotations: &'a [String],
+) -> Option<&'a str> {
+    annotations
+        .iter()
+        .find(|annotation| has_annotation(constant_pool, attributes, Some(annotation)))
+        .map(String::as_str)
+}
+
+/// Build a report for `class_name` given the final diagram. `exclusion` is the
+/// reason the class was left out of the diagram, if it was. Returns `None` if
+/// no classfile was loaded for the class.
+pub fn explain_class<'a>(
+    class_name: &str,
+    classfiles: &BTreeMap<String, ClassFile>,
+    diagram: &Diagram,
+    exclusion: Option<&str>,
+    context: &ExplainContext<'a>,
+) -> Option<String> {
+    let classfile = classfiles.get(class_name)?;
+    let constant_pool = classfile.constant_pool();
+    let full_name = get_full_class_name(classfile).unwrap_or_default();
+
+    let mut report = String::new();
+    let _ = writeln!(report, "Explaining {}", class_name);
+    let _ = writeln!(
+        report,
+        "  fully qualified name: {}",
+        full_name.replace('/', ".")
+    );
+    let _ = writeln!(
+        report,
+        "  package: {}",
+        get_package_name(&full_name).replace('/', ".")
+    );
+    let namespace = diagram
+        .namespaces
+        .iter()
+        .find(|(_, namespace)| namespace.classes.keys().any(|name| name == class_name))
+        .map(|(name, _)| name.as_ref());
+    let _ = match namespace {
+        Some(DEFAULT_NAMESPACE) => writeln!(report, "  namespace: (default)"),
+        Some(namespace) => writeln!(report, "  namespace: {}", namespace),
+        None => writeln!(report, "  namespace: (none)"),
+    };
+    let _ = match exclusion {
+        Some(reason) => writeln!(report, "  included in diagram: no ({})", reason),
+        None if namespace.is_none() => writeln!(report, "  included in diagram: no (pruned)"),
+        None => writeln!(report, "  included in diagram: yes"),
+    };
+
+    // Members are decided on by the same rules the diagram was built with
+    let as_options = |annotations: &'a [String]| -> Vec<Option<&'a str>> {
+        annotations
+            .iter()
+            .map(|annotation| Some(annotation.as_str()))
+            .collect()
+    };
+    let skip_annotations = as_options(context.skip_annotations);
+    let relationship_annotations = as_options(context.relationship_annotations);
+    let member_options = MemberOptions {
+        skip_annotations: &skip_annotations,
+        relationship_annotations: &relationship_annotations,
+        show_constructors: context.show_constructors,
+        min_visibility: context.visibility,
+        ..Default::default()
+    };
+
+    let _ = writeln!(report, "Fields:");
+    for field in classfile.fields() {
+        let name = get_utf8(constant_pool, field.name_index()).unwrap_or("unknown");
+        let _ = match field_exclusion(classfile, field, &member_options) {
+            Some(reason) => writeln!(report, "  {}: excluded ({})", name, reason),
+            None => writeln!(report, "  {}: included", name),
+        };
+    }
+
+    let _ = writeln!(report, "Methods:");
+    for method in classfile.methods() {
+        let name = get_utf8(constant_pool, method.name_index()).unwrap_or("unknown");
+        let _ = match method_exclusion(classfile, method, &member_options) {
+            Some(reason) => writeln!(report, "  {}: excluded ({})", name, reason),
+            None => writeln!(report, "  {}: included", name),
+        };
+    }
+
+    let _ = writeln!(report, "Relations:");
+    for relation in &diagram.relations {
+        if relation.tail != class_name && relation.head != class_name {
+            continue;
+        }
+
+        let mut line = String::new();
+        serialize_relation(&mut line, relation, context.serialize_options);
+        let source = relation_source(relation, classfiles, context);
+        let _ = writeln!(report, "  {} ({})", line.trim_end(), source);
+    }
+
+    Some(report)
+}
+
+/// Work out where a relation came from by looking at its tail's classfile
+fn relation_source(
+    relation: &Relation,
+    classfiles: &BTreeMap<String, ClassFile>,
+    context: &ExplainContext,
+) -> String {
+    let Some(classfile) = classfiles.get(relation.tail.as_ref()) else {
+        return "from the input diagram".to_string();
+    };
+    let constant_pool = classfile.constant_pool();
+
+    // Classes sharing a simple name can be in the diagram under other names,
+    // so a loaded head is matched by its fully qualified name. Heads which
+    // weren't loaded are named as relations name them.
+    let head_full_name = classfiles
+        .get(relation.head.as_ref())
+        .and_then(get_full_class_name);
+    let head = match &head_full_name {
+        Some(full_name) => full_name
+            .rsplit('/')
+            .next()
+            .unwrap_or(full_name)
+            .replace('$', "."),
+        None => relation.head.to_string(),
+    };
+    let head = head.as_str();
+    let references_head = |signature: &str| {
+        head_full_name
+            .as_ref()
+            .is_none_or(|full_name| referenced_class_paths(signature).contains(full_name))
+    };
+
+    if matches!(
+        relation.kind,
+        RelationKind::Inheritance | RelationKind::Realization
+    ) {
+        let is_superclass = match &head_full_name {
+            Some(full_name) => get_superclass_full_name(classfile).as_ref() == Some(full_name),
+            None => get_superclass_name(classfile).as_deref() == Some(head),
+        };
+        if is_superclass {
+            return "superclass".to_string();
+        }
+        let is_interface = match &head_full_name {
+            Some(full_name) => get_interface_full_names(classfile).contains(full_name),
+            None => get_interface_names(classfile)
+                .iter()
+                .any(|name| name == head),
+        };
+        if is_interface {
+            return "implemented interface".to_string();
+        }
+    }
+
+    for field in classfile.fields() {
+        let Some(annotation) = find_annotation(
+            constant_pool,
+            field.attributes(),
+            context.relationship_annotations,
+        ) else {
+            continue;
+        };
+
+        let descriptor = get_utf8(constant_pool, field.descriptor_index()).unwrap_or("");
+        let signature = get_signature(constant_pool, field.attributes());
+        let type_arguments = signature.map(extract_type_arguments).unwrap_or_default();
+        let field_type = extract_class_name_from_descriptor(descriptor)
+            .or_else(|| extract_array_element_class_name(descriptor));
+        let targets_head = (field_type.as_deref() == Some(head)
+            || type_arguments.iter().flatten().any(|name| name == head))
+            && references_head(signature.unwrap_or(descriptor));
+
+        if targets_head {
+            let name = get_utf8(constant_pool, field.name_index()).unwrap_or("unknown");
+            return format!("field `{}` annotated @{}", name, annotation);
+        }
+    }
+
+    for method in classfile.methods() {
+        let Some(annotation) = find_annotation(
+            constant_pool,
+            method.attributes(),
+            context.relationship_annotations,
+        ) else {
+            continue;
+        };
+
+        let descriptor = get_utf8(constant_pool, method.descriptor_index()).unwrap_or("");
+        let return_type = method_return_descriptor(descriptor);
+        let return_signature =
+            get_signature(constant_pool, method.attributes()).map(method_return_descriptor);
+        let type_arguments = return_signature
+            .map(extract_type_arguments)
+            .unwrap_or_default();
+        let return_class = extract_class_name_from_descriptor(return_type)
+            .or_else(|| extract_array_element_class_name(return_type));
+        let targets_head = (return_class.as_deref() == Some(head)
+            || type_arguments.iter().flatten().any(|name| name == head))
+            && references_head(return_signature.unwrap_or(return_type));
+
+        if targets_head {
+            let name = get_utf8(constant_pool, method.name_index()).unwrap_or("unknown");
+            return format!("method `{}()` annotated @{}", name, annotation);
+        }
+    }
+
+    if relation.kind == RelationKind::Dependency {
+        return "class annotation".to_string();
+    }
+
+    "from the input diagram".to_string()
+}
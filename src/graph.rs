@@ -0,0 +1,161 @@
+//! Operations treating the diagram's relations as a graph between classes
+
+use crate::diagram::{Diagram, Relation};
+use crate::mermaid_output::relation_kind_rank;
+use mermaid_parser::types::RelationKind;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Which way relations are followed when walking the diagram
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Follow relations from their tail to their head
+    #[default]
+    Outgoing,
+    /// Follow relations from their head to their tail
+    Incoming,
+    /// Follow relations either way
+    Both,
+}
+
+/// Find every class name reachable from `roots` by transitively following
+/// relations in the given direction. The roots themselves are included.
+pub fn reachable_from(
+    diagram: &Diagram,
+    roots: &[String],
+    direction: Direction,
+) -> BTreeSet<String> {
+    let mut reachable: BTreeSet<String> = roots.iter().cloned().collect();
+    let mut queue: VecDeque<String> = roots.iter().cloned().collect();
+
+    while let Some(current) = queue.pop_front() {
+        for relation in &diagram.relations {
+            let (tail, head): (&str, &str) = (&relation.tail, &relation.head);
+            let next = match direction {
+                Direction::Outgoing if tail == current => head,
+                Direction::Incoming if head == current => tail,
+                Direction::Both if tail == current => head,
+                Direction::Both if head == current => tail,
+                _ => continue,
+            };
+
+            if reachable.insert(next.to_string()) {
+                queue.push_back(next.to_string());
+            }
+        }
+    }
+
+    reachable
+}
+
+/// Remove every class and relation which doesn't only involve classes in
+/// `keep`. Namespaces left without classes are removed too.
+pub fn retain_classes(diagram: &mut Diagram, keep: &BTreeSet<String>) {
+    for namespace in diagram.namespaces.values_mut() {
+        namespace
+            .classes
+            .retain(|name, _| keep.contains(name.as_ref()));
+    }
+    diagram
+        .namespaces
+        .retain(|_, namespace| !namespace.classes.is_empty());
+
+    diagram.relations.retain(|relation| {
+        keep.contains(relation.tail.as_ref()) && keep.contains(relation.head.as_ref())
+    });
+}
+
+/// Check if the diagram has a class definition with the given name
+pub fn has_class(diagram: &Diagram, name: &str) -> bool {
+    diagram
+        .namespaces
+        .values()
+        .any(|namespace| namespace.classes.keys().any(|class| class == name))
+}
+
+/// Reduce the diagram to its type hierarchy by dropping every member and every
+/// relation other than inheritance and realization
+pub fn skeleton(diagram: &mut Diagram) {
+    for namespace in diagram.namespaces.values_mut() {
+        for class in namespace.classes.values_mut() {
+            class.members.clear();
+        }
+    }
+
+    diagram.relations.retain(|relation| {
+        matches!(
+            relation.kind,
+            RelationKind::Inheritance | RelationKind::Realization
+        )
+    });
+}
+
+/// Drop relations identical to an earlier one, i.e. with the same tail, head,
+/// kind, cardinalities, label and arrow. The first of each is kept in place,
+/// so an undirected link and a directed association between the same classes
+/// are both kept.
+pub fn dedup_relations(diagram: &mut Diagram) {
+    let mut seen = BTreeSet::new();
+    diagram.relations.retain(|relation| {
+        seen.insert((
+            relation.tail.to_string(),
+            relation.head.to_string(),
+            relation_kind_rank(relation.kind),
+            relation.cardinality_tail.as_deref().map(str::to_string),
+            relation.cardinality_head.as_deref().map(str::to_string),
+            relation.label.as_deref().map(str::to_string),
+            relation.arrow.as_deref().map(str::to_string),
+        ))
+    });
+}
+
+/// Sort relations by the full names of their tail and head (falling back on
+/// the class name when `full_names` doesn't have it), then by kind, arrow and
+/// label. Relations which only differ otherwise keep their order.
+pub fn sort_relations(diagram: &mut Diagram, full_names: &BTreeMap<String, String>) {
+    let full_name = |name: &str| {
+        full_names
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    };
+    diagram.relations.sort_by_cached_key(|relation| {
+        (
+            full_name(&relation.tail),
+            full_name(&relation.head),
+            relation_kind_rank(relation.kind),
+            relation.arrow.as_deref().map(str::to_string),
+            relation.label.as_deref().map(str::to_string),
+        )
+    });
+}
+
+/// Collapse relations sharing a tail, head, kind and arrow into a single
+/// relation labelled with how many there were. Cardinalities and labels of
+/// collapsed relations are dropped, while relations without duplicates are
+/// kept as is.
+pub fn weight_relations(diagram: &mut Diagram) {
+    let mut counts: BTreeMap<_, usize> = BTreeMap::new();
+    let key = |relation: &Relation| {
+        (
+            relation.tail.to_string(),
+            relation.head.to_string(),
+            relation_kind_rank(relation.kind),
+            relation.arrow.as_deref().map(str::to_string),
+        )
+    };
+    for relation in &diagram.relations {
+        *counts.entry(key(relation)).or_default() += 1;
+    }
+
+    let mut emitted = BTreeSet::new();
+    diagram.relations.retain_mut(|relation| {
+        let key = key(relation);
+        let count = counts[&key];
+        if count > 1 {
+            relation.cardinality_tail = None;
+            relation.cardinality_head = None;
+            relation.label = Some(count.to_string().into());
+        }
+        emitted.insert(key)
+    });
+}
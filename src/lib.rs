@@ -0,0 +1,2039 @@
+//! Link a Mermaid class diagram with the classes described by Java classfiles
+//!
+//! The binary loads classfiles and the seed diagram from disk, but the
+//! pipeline itself works on already parsed classfiles so it can be embedded.
+
+pub mod classfile_utils;
+pub mod complexity;
+pub mod descriptor;
+pub mod diagnostics;
+pub mod diagram;
+pub mod dot_output;
+pub mod explain;
+pub mod graph;
+pub mod manual_relations;
+pub mod mermaid_output;
+pub mod module_graph;
+pub mod plantuml_output;
+pub mod relations_file;
+pub mod report;
+pub mod select;
+pub mod stats;
+
+use anyhow::anyhow;
+use classfile_utils::{
+    MemberOptions, MemberOrder, MinVisibility, classfile_to_mermaid_class,
+    get_enclosing_class_name, get_full_class_name, get_interface_names,
+    get_method_signature_classes, get_nested_class_names, get_package_name,
+    get_permitted_subclasses, get_superclass_full_name, get_superclass_name, is_abstract,
+    is_annotation, is_interface,
+};
+use descriptor::{
+    extract_array_element_class_name, extract_class_name_from_descriptor, extract_type_arguments,
+    is_collection_descriptor, is_map_descriptor, method_return_descriptor,
+};
+use diagram::{Attribute as MermaidAttribute, Class, Diagram, Member, Relation};
+use jclassfile::attributes::Attribute;
+use jclassfile::class_file::{self, ClassFile};
+use jclassfile::constant_pool::ConstantPool;
+use mermaid_output::{
+    InnerSeparator, NamespaceOrder, SerializeOptions, normalize_output, parse_arrow_token,
+    serialize_diagram, serialize_member,
+};
+use mermaid_parser::types::{RelationKind, TypeNotation, Visibility};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The merged configuration after combining config file and CLI arguments
+#[derive(Debug, Clone, Default)]
+pub struct MergedConfig {
+    /// Annotations which hide classes and members
+    pub skip: Vec<String>,
+    pub aggregate: Option<String>,
+    pub compose: Option<String>,
+    pub link: Option<String>,
+    pub navigate: Option<String>,
+    /// Annotation whose text is attached to classes as a note
+    pub note: Option<String>,
+    /// Frontmatter style `umlink` options applied where the diagram doesn't
+    /// set them itself
+    pub options: Option<serde_yml::Mapping>,
+    /// Only show public and protected members, including those inherited from
+    /// loaded superclasses, and mark final classes
+    pub extension_api: bool,
+    /// The least visible members shown, along with the relations of fields
+    pub visibility: MinVisibility,
+    /// Relation kinds created by the relationship annotations (`aggregate`,
+    /// `compose`, `link`, `navigate`) in place of their default ones
+    pub kinds: BTreeMap<String, AnnotationKind>,
+}
+
+/// Relation kind a relationship annotation can be configured to create
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationKind {
+    Aggregation,
+    Composition,
+    Association,
+    Dependency,
+}
+
+impl From<AnnotationKind> for RelationKind {
+    fn from(kind: AnnotationKind) -> Self {
+        match kind {
+            AnnotationKind::Aggregation => RelationKind::Aggregation,
+            AnnotationKind::Composition => RelationKind::Composition,
+            AnnotationKind::Association => RelationKind::Association,
+            AnnotationKind::Dependency => RelationKind::Dependency,
+        }
+    }
+}
+
+/// A diagram populated from classfiles along with what's needed to render and
+/// explain it
+pub struct LinkedDiagram<'a> {
+    pub diagram: Diagram<'a>,
+    pub serialize_options: SerializeOptions,
+    /// Classfiles that made it into the diagram
+    pub classfiles: Vec<&'a ClassFile>,
+    /// Why classes were left out of the diagram, keyed by class name
+    pub excluded_classes: BTreeMap<&'a str, &'static str>,
+    /// Annotations which hide members
+    pub skip_annotations: Vec<String>,
+    /// Annotations which turn fields into relations
+    pub relationship_annotations: Vec<String>,
+    /// Whether constructors are listed
+    pub show_constructors: bool,
+}
+
+/// Text format a linked diagram is written in
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Mermaid `classDiagram`
+    #[default]
+    Mermaid,
+    /// PlantUML class diagram between `@startuml` and `@enduml`
+    Plantuml,
+    /// Graphviz DOT graph with a record node for each class
+    Dot,
+}
+
+impl OutputFormat {
+    /// Extension of files written in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Mermaid => "mmd",
+            OutputFormat::Plantuml => "puml",
+            OutputFormat::Dot => "dot",
+        }
+    }
+
+    /// Serialize a diagram in this format
+    pub fn serialize(self, diagram: &Diagram, options: &SerializeOptions) -> String {
+        match self {
+            OutputFormat::Mermaid => serialize_diagram(diagram, options),
+            OutputFormat::Plantuml => plantuml_output::serialize_plantuml(diagram, options),
+            OutputFormat::Dot => dot_output::serialize_dot(diagram, options),
+        }
+    }
+}
+
+/// Find the common base package among all classes
+/// Returns the common prefix package path (e.g., "com/example")
+fn find_common_base_package(packages: &[&str]) -> String {
+    if packages.is_empty() {
+        return String::new();
+    }
+
+    // Split all packages into components
+    let split_packages: Vec<Vec<&str>> = packages.iter().map(|p| p.split('/').collect()).collect();
+
+    if split_packages.is_empty() {
+        return String::new();
+    }
+
+    // Find common prefix
+    let mut common = Vec::new();
+    let first = &split_packages[0];
+
+    for (i, component) in first.iter().enumerate() {
+        if split_packages.iter().all(|p| p.get(i) == Some(component)) {
+            common.push(*component);
+        } else {
+            break;
+        }
+    }
+
+    common.join("/")
+}
+
+/// Convert a full package name to a relative namespace
+/// e.g., base="com/example", full="com/example/subpackage" -> "subpackage"
+/// Classes in the base package itself go in `default_namespace`.
+fn get_relative_namespace(base: &str, full: &str, default_namespace: &str) -> String {
+    if base.is_empty() {
+        return full.replace('/', ".");
+    }
+
+    if full == base {
+        return default_namespace.to_string();
+    }
+
+    if full.starts_with(base) {
+        let relative = &full[base.len()..];
+        let relative = relative.trim_start_matches('/');
+        if relative.is_empty() {
+            default_namespace.to_string()
+        } else {
+            relative.replace('/', ".")
+        }
+    } else {
+        full.replace('/', ".")
+    }
+}
+
+/// Look up an option in the `umlink` section of the YAML frontmatter
+fn get_umlink_option<'a>(diagram: &'a Diagram, key: &str) -> Option<&'a serde_yml::Value> {
+    diagram.yaml.as_ref()?.get("umlink")?.get(key)
+}
+
+/// Add the configured `umlink` options to the diagram's frontmatter. Options
+/// the diagram already sets are left as they are.
+pub fn apply_config_options(diagram: &mut Diagram, config: &MergedConfig) {
+    let Some(options) = &config.options else {
+        return;
+    };
+
+    let yaml = diagram
+        .yaml
+        .get_or_insert_with(|| serde_yml::Value::Mapping(Default::default()));
+    let Some(frontmatter) = yaml.as_mapping_mut() else {
+        return;
+    };
+    let umlink = frontmatter
+        .entry("umlink".into())
+        .or_insert_with(|| serde_yml::Value::Mapping(Default::default()));
+    let Some(umlink) = umlink.as_mapping_mut() else {
+        return;
+    };
+
+    for (key, value) in options {
+        if !umlink.contains_key(key) {
+            umlink.insert(key.clone(), value.clone());
+        }
+    }
+}
+
+/// Combine several seed diagrams into one, in order. Frontmatter mappings are
+/// merged key by key, with a later diagram's value winning when two disagree.
+/// Classes are collected into their namespaces (a later definition replacing
+/// an earlier one) and relations declared more than once are kept once.
+pub fn merge_diagrams<'a>(diagrams: impl IntoIterator<Item = Diagram<'a>>) -> Diagram<'a> {
+    let mut merged = Diagram::default();
+    for diagram in diagrams {
+        if let Some(yaml) = diagram.yaml {
+            match &mut merged.yaml {
+                Some(merged_yaml) => merge_frontmatter(merged_yaml, yaml, ""),
+                None => merged.yaml = Some(yaml),
+            }
+        }
+        for (namespace_name, namespace) in diagram.namespaces {
+            merged
+                .namespaces
+                .entry(namespace_name)
+                .or_default()
+                .classes
+                .extend(namespace.classes);
+        }
+        for relation in diagram.relations {
+            if !merged.relations.contains(&relation) {
+                merged.relations.push(relation);
+            }
+        }
+    }
+    merged
+}
+
+/// Merge the frontmatter `value` of a later diagram into `merged`, warning
+/// about keys they give different values. `path` is the dotted key of the
+/// values being merged, empty at the top level.
+fn merge_frontmatter(merged: &mut serde_yml::Value, value: serde_yml::Value, path: &str) {
+    match (merged, value) {
+        (serde_yml::Value::Mapping(merged), serde_yml::Value::Mapping(mapping)) => {
+            for (key, value) in mapping {
+                let key_name = key.as_str().unwrap_or("?");
+                let key_path = if path.is_empty() {
+                    key_name.to_string()
+                } else {
+                    format!("{}.{}", path, key_name)
+                };
+                match merged.get_mut(&key) {
+                    Some(existing) => merge_frontmatter(existing, value, &key_path),
+                    None => {
+                        merged.insert(key, value);
+                    }
+                }
+            }
+        }
+        (merged, value) => {
+            if *merged != value {
+                diagnostics::warn(format_args!(
+                    "`{}` is set differently by more than one diagram, using the last value",
+                    path
+                ));
+                *merged = value;
+            }
+        }
+    }
+}
+
+/// With `recordInputs` set, note how the diagram was generated in a
+/// `generatedFrom` block of its `umlink` frontmatter: the classfile paths
+/// scanned, the annotations configured and a Unix timestamp. A block left by
+/// an earlier run is replaced. Nothing reads the block back, so the output can
+/// still be used as input.
+pub fn record_inputs(diagram: &mut Diagram, class_paths: &[String], config: &MergedConfig) {
+    if !is_umlink_flag_set(diagram, "recordInputs") {
+        return;
+    }
+
+    let mut annotations = serde_yml::Mapping::new();
+    match config.skip.as_slice() {
+        [] => {}
+        [skip] => {
+            annotations.insert("skip".into(), skip.as_str().into());
+        }
+        skip => {
+            annotations.insert(
+                "skip".into(),
+                skip.iter()
+                    .map(|annotation| annotation.as_str().into())
+                    .collect::<Vec<serde_yml::Value>>()
+                    .into(),
+            );
+        }
+    }
+    for (key, value) in [
+        ("aggregate", &config.aggregate),
+        ("compose", &config.compose),
+        ("link", &config.link),
+        ("navigate", &config.navigate),
+        ("note", &config.note),
+    ] {
+        if let Some(value) = value {
+            annotations.insert(key.into(), value.as_str().into());
+        }
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    let mut generated_from = serde_yml::Mapping::new();
+    generated_from.insert(
+        "classfiles".into(),
+        class_paths
+            .iter()
+            .map(|path| serde_yml::Value::from(path.as_str()))
+            .collect(),
+    );
+    generated_from.insert("config".into(), annotations.into());
+    generated_from.insert("timestamp".into(), timestamp.into());
+
+    // The flag was read from the frontmatter, so the `umlink` mapping exists
+    if let Some(umlink) = diagram
+        .yaml
+        .as_mut()
+        .and_then(|yaml| yaml.get_mut("umlink"))
+        .and_then(|umlink| umlink.as_mapping_mut())
+    {
+        umlink.insert("generatedFrom".into(), generated_from.into());
+    }
+}
+
+/// Check if a boolean option is enabled in the `umlink` section of the YAML
+/// frontmatter. Missing or non-boolean values count as disabled.
+fn is_umlink_flag_set(diagram: &Diagram, key: &str) -> bool {
+    get_umlink_option(diagram, key)
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false)
+}
+
+/// Annotation Lombok puts on the members it generates
+const LOMBOK_GENERATED: &str = "lombok.Generated";
+
+/// How relationship annotations on map typed fields are resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MapRelations {
+    /// Relate only to the map's value type
+    ValueOnly,
+    /// Relate to both the key and the value type
+    Both,
+}
+
+/// Read the `mapRelations` option from the YAML frontmatter, defaulting to
+/// `valueOnly`
+fn get_map_relations(diagram: &Diagram) -> MapRelations {
+    match get_umlink_option(diagram, "mapRelations").and_then(|value| value.as_str()) {
+        None | Some("valueOnly") => MapRelations::ValueOnly,
+        Some("both") => MapRelations::Both,
+        Some(other) => {
+            diagnostics::warn(format_args!(
+                "Unknown mapRelations value `{}`, expected `valueOnly` or `both`",
+                other
+            ));
+            MapRelations::ValueOnly
+        }
+    }
+}
+
+/// How classes which share a simple name are told apart
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Disambiguate {
+    /// Classes sharing a name are an error
+    #[default]
+    None,
+    /// Every class after the first gets a suffix derived from its package
+    /// (e.g. `Config_ui`)
+    Suffix,
+}
+
+/// Read the `disambiguate` option from the YAML frontmatter, defaulting to
+/// none, or to suffix when classes are grouped by package (where the suffix
+/// names the class's namespace)
+pub fn get_disambiguate(diagram: &Diagram) -> Disambiguate {
+    match get_umlink_option(diagram, "disambiguate").and_then(|value| value.as_str()) {
+        None if should_group_by_package(diagram) => Disambiguate::Suffix,
+        None | Some("none") => Disambiguate::None,
+        Some("suffix") => Disambiguate::Suffix,
+        Some(other) => {
+            diagnostics::warn(format_args!(
+                "Unknown disambiguate value `{}`, expected `none` or `suffix`",
+                other
+            ));
+            Disambiguate::None
+        }
+    }
+}
+
+/// Key classfiles by their class name. Classes sharing a name are an error
+/// unless `disambiguate` is `Suffix`. Then the first of them (by fully
+/// qualified name) keeps its name and the rest get the shortest suffix of
+/// their package which makes them unique (e.g. `Config` and `Config_ui`).
+pub fn index_classfiles(
+    classes: Vec<(String, ClassFile)>,
+    disambiguate: Disambiguate,
+) -> anyhow::Result<BTreeMap<String, ClassFile>> {
+    // Classes sharing a name, along with their fully qualified names
+    let mut groups: BTreeMap<String, Vec<(String, ClassFile)>> = BTreeMap::new();
+    for (name, classfile) in classes {
+        let full_name = get_full_class_name(&classfile).unwrap_or_else(|| name.clone());
+        groups.entry(name).or_default().push((full_name, classfile));
+    }
+
+    // The first class of each name is inserted before any are renamed, so
+    // suffixed names never take the name of another class
+    let mut classfiles = BTreeMap::new();
+    let mut renamed = Vec::new();
+    for (name, mut group) in groups {
+        if group.len() > 1 && disambiguate == Disambiguate::None {
+            let full_names: Vec<String> = group
+                .iter()
+                .map(|(full_name, _)| full_name.replace('/', "."))
+                .collect();
+            return Err(anyhow!(
+                "Duplicate class name `{}` ({}), set `disambiguate: suffix` to keep both",
+                name,
+                full_names.join(", ")
+            ));
+        }
+
+        group.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut group = group.into_iter();
+        if let Some((_, classfile)) = group.next() {
+            classfiles.insert(name.clone(), classfile);
+        }
+        renamed.extend(group.map(|(full_name, classfile)| (name.clone(), full_name, classfile)));
+    }
+
+    for (name, full_name, classfile) in renamed {
+        let package: Vec<&str> = get_package_name(&full_name)
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let unique_name = (1..=package.len())
+            .map(|count| format!("{}_{}", name, package[package.len() - count..].join("_")))
+            .chain((2..).map(|number| format!("{}_{}", name, number)))
+            .find(|candidate| !classfiles.contains_key(candidate))
+            .expect("Numbered names never run out");
+        classfiles.insert(unique_name, classfile);
+    }
+
+    Ok(classfiles)
+}
+
+/// Relations are created from simple class names, so when classes sharing a
+/// simple name were given distinct names by `index_classfiles` a relation may
+/// point at the wrong one. Use the classes the relation's tail actually refers
+/// to, to pick the right one.
+fn resolve_ambiguous_heads(diagram: &mut Diagram, classfiles: &BTreeMap<String, ClassFile>) {
+    let mut by_simple_name: BTreeMap<String, Vec<(String, &str)>> = BTreeMap::new();
+    for (name, classfile) in classfiles {
+        let Some(full_name) = get_full_class_name(classfile) else {
+            continue;
+        };
+        let simple_name = full_name
+            .rsplit('/')
+            .next()
+            .unwrap_or(&full_name)
+            .replace('$', ".");
+        by_simple_name
+            .entry(simple_name)
+            .or_default()
+            .push((full_name, name));
+    }
+    by_simple_name.retain(|_, candidates| candidates.len() > 1);
+    if by_simple_name.is_empty() {
+        return;
+    }
+
+    for relation in &mut diagram.relations {
+        let Some(candidates) = by_simple_name.get(relation.head.as_ref()) else {
+            continue;
+        };
+        let Some(tail) = classfiles.get(relation.tail.as_ref()) else {
+            continue;
+        };
+
+        let tail_name = get_full_class_name(tail);
+        let referenced = candidates.iter().find(|(full_name, _)| {
+            tail_name.as_ref() != Some(full_name)
+                && classfile_utils::references_class(tail, full_name)
+        });
+        if let Some((_, name)) = referenced {
+            relation.head = name.to_string().into();
+        }
+    }
+}
+
+/// Read the `namespaceOrder` option from the YAML frontmatter, defaulting to
+/// `name`
+fn get_namespace_order(diagram: &Diagram) -> NamespaceOrder {
+    match get_umlink_option(diagram, "namespaceOrder").and_then(|value| value.as_str()) {
+        None | Some("name") => NamespaceOrder::Name,
+        Some("depth") => NamespaceOrder::Depth,
+        Some(other) => {
+            diagnostics::warn(format_args!(
+                "Unknown namespaceOrder value `{}`, expected `name` or `depth`",
+                other
+            ));
+            NamespaceOrder::Name
+        }
+    }
+}
+
+/// Read the `innerSeparator` option from the YAML frontmatter
+fn get_inner_separator(diagram: &Diagram) -> InnerSeparator {
+    match get_umlink_option(diagram, "innerSeparator").and_then(|value| value.as_str()) {
+        None | Some("dot") => InnerSeparator::Dot,
+        Some("underscore") => InnerSeparator::Underscore,
+        Some("dollar") => InnerSeparator::Dollar,
+        Some(other) => {
+            diagnostics::warn(format_args!(
+                "Unknown innerSeparator value `{}`, expected `dot`, `underscore` or `dollar`",
+                other
+            ));
+            InnerSeparator::Dot
+        }
+    }
+}
+
+/// Read the `memberOrder` option from the YAML frontmatter: `declaration`
+/// (the default), `alpha` or `visibility`
+fn get_member_order(diagram: &Diagram) -> MemberOrder {
+    match get_umlink_option(diagram, "memberOrder").and_then(|value| value.as_str()) {
+        None | Some("declaration") => MemberOrder::Declaration,
+        Some("alpha") => MemberOrder::Alpha,
+        Some("visibility") => MemberOrder::Visibility,
+        Some(other) => {
+            diagnostics::warn(format_args!(
+                "Unknown memberOrder value `{}`, expected `declaration`, `alpha` or `visibility`",
+                other
+            ));
+            MemberOrder::Declaration
+        }
+    }
+}
+
+/// Read the `maxMembers` option from the YAML frontmatter: how many
+/// attributes and how many methods each class shows before the rest are
+/// summarized. Values which aren't a non-negative integer are ignored.
+fn get_max_members(diagram: &Diagram) -> Option<usize> {
+    let value = get_umlink_option(diagram, "maxMembers")?;
+    let max_members = value.as_u64().and_then(|max| usize::try_from(max).ok());
+    if max_members.is_none() {
+        diagnostics::warn(format_args!(
+            "Invalid maxMembers value `{}`, expected a non-negative integer",
+            serde_yml::to_string(value).unwrap_or_default().trim()
+        ));
+    }
+    max_members
+}
+
+/// Arrow of the undirected associations `link` annotations create
+const UNDIRECTED_ARROW: &str = "--";
+
+/// Relationship annotations in their default order of precedence
+const ANNOTATION_PRECEDENCE: [&str; 4] = ["aggregate", "compose", "link", "navigate"];
+
+/// Read the `annotationPrecedence` option from the YAML frontmatter: the order
+/// relationship annotations are tried in when a field has several of them, as
+/// only the first one present creates relations. Unknown or repeated entries
+/// are warned about and ignored, and annotations which aren't listed follow
+/// the listed ones in their default order.
+fn get_annotation_precedence(diagram: &Diagram) -> Vec<&'static str> {
+    let mut precedence: Vec<&'static str> = Vec::new();
+    let entries = get_umlink_option(diagram, "annotationPrecedence")
+        .and_then(|value| value.as_sequence())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    for entry in entries {
+        let name = entry.as_str().unwrap_or_default();
+        match ANNOTATION_PRECEDENCE.iter().find(|known| **known == name) {
+            Some(known) if !precedence.contains(known) => precedence.push(known),
+            Some(_) => diagnostics::warn(format_args!(
+                "Ignoring repeated annotationPrecedence entry `{}`",
+                name
+            )),
+            None => diagnostics::warn(format_args!(
+                "Ignoring unknown annotationPrecedence entry `{}`, expected one of {}",
+                serde_yml::to_string(entry).unwrap_or_default().trim_end(),
+                ANNOTATION_PRECEDENCE.join(", ")
+            )),
+        }
+    }
+
+    for name in ANNOTATION_PRECEDENCE {
+        if !precedence.contains(&name) {
+            precedence.push(name);
+        }
+    }
+    precedence
+}
+
+/// A user defined relation kind drawn with its own arrow for fields carrying
+/// `annotation`
+#[derive(Debug, Clone)]
+struct CustomRelation {
+    /// Fully qualified path to the annotation (e.g. `com.example.EmitsTo`)
+    annotation: String,
+    /// Mermaid arrow token drawn from the tail to the head (e.g. `..>`)
+    arrow: String,
+    /// Standard kind used for everything other than rendering the arrow
+    kind: RelationKind,
+}
+
+/// Read the `customRelations` option from the YAML frontmatter, a map of
+/// named edge styles each with an `annotation` and an `arrow` token. Invalid
+/// entries are skipped with a warning.
+fn get_custom_relations(diagram: &Diagram) -> Vec<CustomRelation> {
+    let Some(relations) =
+        get_umlink_option(diagram, "customRelations").and_then(|value| value.as_mapping())
+    else {
+        return Vec::new();
+    };
+
+    let mut custom_relations = Vec::new();
+    for (name, style) in relations {
+        let name = name.as_str().unwrap_or("?");
+        let annotation = style.get("annotation").and_then(|value| value.as_str());
+        let arrow = style.get("arrow").and_then(|value| value.as_str());
+
+        let (Some(annotation), Some(arrow)) = (annotation, arrow) else {
+            diagnostics::warn(format_args!(
+                "Custom relation `{}` needs both an `annotation` and an `arrow`",
+                name
+            ));
+            continue;
+        };
+        let Some(kind) = parse_arrow_token(arrow) else {
+            diagnostics::warn(format_args!(
+                "Custom relation `{}` has invalid arrow `{}`",
+                name, arrow
+            ));
+            continue;
+        };
+
+        custom_relations.push(CustomRelation {
+            annotation: annotation.to_string(),
+            arrow: arrow.to_string(),
+            kind,
+        });
+    }
+    custom_relations
+}
+
+/// A class a relationship annotated field points at
+struct RelationTarget {
+    class: String,
+    /// Role labelling the edge, e.g. `key` for a map's key type
+    role: Option<&'static str>,
+    /// Whether the field holds many of the class, as arrays, collections
+    /// and maps do
+    many: bool,
+}
+
+/// Resolve the classes a relationship annotated field points at. Most fields
+/// point at their own type, but arrays and collections point at their element
+/// type, and map fields at their value type, or with `mapRelations: both` at
+/// both their key and value types. Raw collections and maps fall back to the
+/// collection type itself. Fields of primitive types point at nothing.
+fn resolve_relation_targets(
+    descriptor: &str,
+    signature: Option<&str>,
+    map_relations: MapRelations,
+) -> Vec<RelationTarget> {
+    if let Some(element) = extract_array_element_class_name(descriptor) {
+        return vec![RelationTarget {
+            class: element,
+            role: None,
+            many: true,
+        }];
+    }
+    let Some(field_type) = extract_class_name_from_descriptor(descriptor) else {
+        return Vec::new();
+    };
+
+    let type_arguments = signature.map(extract_type_arguments).unwrap_or_default();
+    let targets: Vec<_> = if is_collection_descriptor(descriptor) {
+        type_arguments
+            .first()
+            .cloned()
+            .flatten()
+            .map(|element| (element, None))
+            .into_iter()
+            .collect()
+    } else if is_map_descriptor(descriptor) {
+        let key = type_arguments.first().cloned().flatten();
+        let value = type_arguments.get(1).cloned().flatten();
+        match map_relations {
+            MapRelations::ValueOnly => value.map(|value| (value, None)).into_iter().collect(),
+            MapRelations::Both => [(key, "key"), (value, "value")]
+                .into_iter()
+                .filter_map(|(target, role)| Some((target?, Some(role))))
+                .collect(),
+        }
+    } else {
+        Vec::new()
+    };
+
+    if targets.is_empty() {
+        vec![RelationTarget {
+            class: field_type,
+            role: None,
+            many: false,
+        }]
+    } else {
+        targets
+            .into_iter()
+            .map(|(class, role)| RelationTarget {
+                class,
+                role,
+                many: true,
+            })
+            .collect()
+    }
+}
+
+/// Read the `defaultNamespaceName` option from the YAML frontmatter: the
+/// namespace classes in the base package are placed in when grouping by
+/// package. Without it they aren't wrapped in a namespace.
+fn get_default_namespace_name(diagram: &Diagram) -> String {
+    get_umlink_option(diagram, "defaultNamespaceName")
+        .and_then(|value| value.as_str())
+        .unwrap_or(mermaid_parser::types::DEFAULT_NAMESPACE)
+        .to_string()
+}
+
+/// Read the `sourceBaseUrl` option from the YAML frontmatter: the URL source
+/// files are found under (e.g. `https://example.com/repo/src/main/java`)
+fn get_source_base_url(diagram: &Diagram) -> Option<String> {
+    get_umlink_option(diagram, "sourceBaseUrl")
+        .and_then(|value| value.as_str())
+        .map(|url| url.trim_end_matches('/').to_string())
+}
+
+/// URL of the source file declaring a class under `base_url`, from its fully
+/// qualified name. Nested classes link to the file of their top level class
+/// (e.g. `{base}/com/example/Outer.java`).
+fn source_url(base_url: &str, classfile: &ClassFile) -> Option<String> {
+    let full_name = get_full_class_name(classfile)?;
+    let top_level = full_name.split('$').next().unwrap_or(&full_name);
+    Some(format!("{}/{}.java", base_url, top_level))
+}
+
+/// Check if groupPackage is enabled in the YAML frontmatter
+fn should_group_by_package(diagram: &Diagram) -> bool {
+    is_umlink_flag_set(diagram, "groupPackage")
+}
+
+/// Key telling members apart: methods by name and parameter types (e.g.
+/// `area(double,int)`) and fields by name. Used to match overrides, hidden
+/// fields and overloads.
+fn member_key(member: &Member) -> String {
+    match member {
+        Member::Method(method) => format!(
+            "{}({})",
+            method.name,
+            method
+                .parameters
+                .iter()
+                .map(|parameter| parameter.data_type.as_deref().unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        Member::Attribute(attribute) => attribute.name.to_string(),
+    }
+}
+
+/// Check if a member is part of the API subclasses see: public and protected
+/// members, along with enum constants
+fn is_extension_visible(member: &Member) -> bool {
+    let visibility = match member {
+        Member::Attribute(attribute) => attribute.visibility,
+        Member::Method(method) => method.visibility,
+    };
+    matches!(
+        visibility,
+        Visibility::Public | Visibility::Protected | Visibility::Unspecified
+    )
+}
+
+/// Link each method of `class` which has line info to its line in the source
+/// file (e.g. `area()` to `com/example/Circle.java#L6`)
+fn member_links(
+    classfile: &ClassFile,
+    class: &Class,
+    qualified_types: bool,
+) -> Vec<(String, String)> {
+    let Some(source_file) = classfile_utils::get_source_file(classfile) else {
+        return Vec::new();
+    };
+    let source_path = match get_full_class_name(classfile)
+        .map(|full_name| get_package_name(&full_name).to_string())
+    {
+        Some(package) if !package.is_empty() => format!("{}/{}", package, source_file),
+        _ => source_file.to_string(),
+    };
+
+    // First source line of each method by its key
+    let constant_pool = classfile.constant_pool();
+    let lines: BTreeMap<String, u16> = classfile
+        .methods()
+        .iter()
+        .filter_map(|method| {
+            let line = classfile_utils::get_first_line(method.attributes())?;
+            let name = classfile_utils::get_utf8(constant_pool, method.name_index())?;
+            let descriptor = classfile_utils::get_utf8(constant_pool, method.descriptor_index())?;
+            let (param_types, _) = descriptor::parse_method_descriptor(descriptor, qualified_types);
+            let key = format!("{}({})", name, param_types.join(","));
+            Some((key, line))
+        })
+        .collect();
+
+    class
+        .members
+        .iter()
+        .filter_map(|member| {
+            let Member::Method(method) = member else {
+                return None;
+            };
+            let key = member_key(member);
+            let line = lines.get(&key)?;
+
+            // Only spell out parameter types where they tell overloads apart
+            let overloads = class
+                .members
+                .iter()
+                .filter(|other| matches!(other, Member::Method(other) if other.name == method.name))
+                .count();
+            let label = if overloads > 1 {
+                key
+            } else {
+                format!("{}()", method.name)
+            };
+            Some((label, format!("{}#L{}", source_path, line)))
+        })
+        .collect()
+}
+
+/// Property set by a fluent setter, taken from its name without a `with` or
+/// `set` prefix (e.g. `withCheese` sets `cheese`)
+fn builder_property(method_name: &str) -> String {
+    let property = ["with", "set"]
+        .iter()
+        .find_map(|prefix| {
+            method_name
+                .strip_prefix(prefix)
+                .filter(|rest| rest.starts_with(|ch: char| ch.is_ascii_uppercase()))
+        })
+        .unwrap_or(method_name);
+
+    let mut chars = property.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Collapse the fluent setters of a builder into the properties they set. A
+/// class is a builder when most of its instance methods take arguments and
+/// return the class itself. Its setters (and the fields backing them) are
+/// replaced by an attribute per property and the class is marked
+/// `<<builder>>`.
+fn collapse_builder(class: &mut Class) {
+    // With `qualifiedTypes` the return type carries the package the class
+    // name leaves out
+    let returns_class = |return_type: &str| {
+        return_type
+            .strip_suffix(class.name.as_ref())
+            .is_some_and(|package| package.is_empty() || package.ends_with('.'))
+    };
+    let is_fluent_setter = |member: &Member| {
+        matches!(member, Member::Method(method)
+            if !method.is_static
+                && !method.parameters.is_empty()
+                && method.return_type.as_deref().is_some_and(returns_class))
+    };
+    let instance_methods = class
+        .members
+        .iter()
+        .filter(|member| matches!(member, Member::Method(method) if !method.is_static))
+        .count();
+    let setters = class
+        .members
+        .iter()
+        .filter(|member| is_fluent_setter(member))
+        .count();
+    if setters < 2 || setters * 2 <= instance_methods {
+        return;
+    }
+
+    let mut properties = Vec::new();
+    for member in class
+        .members
+        .iter()
+        .filter(|member| is_fluent_setter(member))
+    {
+        let Member::Method(method) = member else {
+            continue;
+        };
+        let name = builder_property(&method.name);
+        if properties
+            .iter()
+            .any(|property: &Member| member_key(property) == name)
+        {
+            continue;
+        }
+        properties.push(Member::Attribute(MermaidAttribute {
+            visibility: Visibility::Unspecified,
+            name: name.into(),
+            data_type: method.parameters[0].data_type.clone(),
+            is_static: false,
+            type_notation: TypeNotation::Postfix,
+            value: None,
+            is_deprecated: false,
+        }));
+    }
+
+    // Drop the setters and the fields holding their values
+    let property_names: BTreeSet<String> = properties.iter().map(member_key).collect();
+    class.members.retain(|member| match member {
+        Member::Method(_) => !is_fluent_setter(member),
+        Member::Attribute(attribute) => {
+            attribute.is_static || !property_names.contains(attribute.name.as_ref())
+        }
+    });
+    properties.append(&mut class.members);
+    class.members = properties;
+
+    // Interfaces and other kinds of classes keep their own stereotype
+    if class.annotation.is_none() {
+        class.stereotypes.insert(0, "builder".into());
+    }
+}
+
+/// Add the relations a field or method's relationship annotation asks for,
+/// from `class_name` to each of `targets` (the classes the member's type
+/// points at). Only the first of `relation_annotations` present is used.
+/// `default_label` labels the relations when the annotation doesn't.
+fn add_annotated_relations(
+    diagram: &mut Diagram,
+    class_name: &str,
+    constant_pool: &[ConstantPool],
+    attributes: &[Attribute],
+    targets: &[RelationTarget],
+    relation_annotations: &[(Option<&str>, RelationKind, Option<&str>)],
+    default_label: Option<&str>,
+) {
+    if targets.is_empty() {
+        return;
+    }
+
+    for (annotation_name, relation_kind, custom_arrow) in relation_annotations {
+        let Some((self_card, label, other_card)) =
+            classfile_utils::get_annotation_params(constant_pool, attributes, *annotation_name)
+        else {
+            continue;
+        };
+        let label = match default_label {
+            Some(default_label) if label.is_empty() => default_label.to_string(),
+            _ => label,
+        };
+
+        for RelationTarget {
+            class: target,
+            role,
+            many,
+        } in targets
+        {
+            let label = match role {
+                Some(role) if label.is_empty() => Some(role.to_string()),
+                Some(role) => Some(format!("{} ({})", label, role)),
+                None if label.is_empty() => None,
+                None => Some(label.clone()),
+            };
+
+            let relation = Relation {
+                tail: class_name.to_string().into(),
+                head: target.clone().into(),
+                kind: *relation_kind,
+                cardinality_tail: if self_card.is_empty() {
+                    None
+                } else {
+                    Some(self_card.clone().into())
+                },
+                // Arrays and collections hold many of their target unless the
+                // annotation says otherwise
+                cardinality_head: if !other_card.is_empty() {
+                    Some(other_card.clone().into())
+                } else if *many {
+                    Some("*".into())
+                } else {
+                    None
+                },
+                label: label.map(Into::into),
+                arrow: custom_arrow.map(|arrow| arrow.to_string().into()),
+            };
+            diagram.relations.push(relation);
+        }
+        break; // Only create relations for the first matching annotation
+    }
+}
+
+/// Add an inheritance or realization relation from `tail` to `head`, unless
+/// they are already related that way. A subclass and the sealed class
+/// permitting it both describe the same edge.
+fn push_supertype_relation(diagram: &mut Diagram, tail: &str, head: &str, kind: RelationKind) {
+    let already_related = diagram.relations.iter().any(|relation| {
+        relation.tail == tail
+            && relation.head == head
+            && matches!(
+                relation.kind,
+                RelationKind::Inheritance | RelationKind::Realization
+            )
+    });
+    if !already_related {
+        diagram.relations.push(Relation {
+            tail: tail.to_string().into(),
+            head: head.to_string().into(),
+            kind,
+            cardinality_tail: None,
+            cardinality_head: None,
+            label: None,
+            arrow: None,
+        });
+    }
+}
+
+/// Keep only the first `max_members` attributes and the first `max_members`
+/// methods of a class. Returns how many attributes and methods were dropped.
+fn truncate_members(class: &mut Class, max_members: usize) -> (usize, usize) {
+    let mut attributes = 0;
+    let mut methods = 0;
+    class.members.retain(|member| {
+        let count = match member {
+            Member::Attribute(_) => &mut attributes,
+            Member::Method(_) => &mut methods,
+        };
+        *count += 1;
+        *count <= max_members
+    });
+    (
+        attributes.saturating_sub(max_members),
+        methods.saturating_sub(max_members),
+    )
+}
+
+/// Members a class inherits from its loaded superclasses, serialized and
+/// grouped by the superclass declaring them (nearest first). These are the
+/// methods, or with `extension_api` the public and protected methods and
+/// fields. Private and static members aren't inherited and overridden members
+/// are only listed once, under the class overriding them.
+fn inherited_members(
+    classfile: &ClassFile,
+    classfiles: &BTreeMap<String, ClassFile>,
+    member_options: &MemberOptions,
+    extension_api: bool,
+) -> Vec<(String, Vec<String>)> {
+    // Every member the class declares overrides, even ones which are skipped
+    let all_members = MemberOptions {
+        qualified_types: member_options.qualified_types,
+        ..Default::default()
+    };
+    let mut seen: BTreeSet<String> = classfile_to_mermaid_class(classfile, "", &all_members)
+        .members
+        .iter()
+        .map(member_key)
+        .collect();
+
+    let mut inherited = Vec::new();
+    let mut current = classfile;
+    while let Some(superclass_full_name) = get_superclass_full_name(current) {
+        let Some((superclass_name, superclass)) = classfiles.iter().find(|(_, classfile)| {
+            get_full_class_name(classfile).as_ref() == Some(&superclass_full_name)
+        }) else {
+            break;
+        };
+
+        let superclass_members = MemberOptions {
+            show_constructors: false,
+            member_order: MemberOrder::Declaration,
+            ..*member_options
+        };
+        let members: Vec<String> =
+            classfile_to_mermaid_class(superclass, superclass_name, &superclass_members)
+                .members
+                .iter()
+                .filter(|member| match member {
+                    Member::Method(method) => {
+                        !method.is_static && method.visibility != Visibility::Private
+                    }
+                    Member::Attribute(attribute) => extension_api && !attribute.is_static,
+                })
+                .filter(|member| !extension_api || is_extension_visible(member))
+                .filter(|member| seen.insert(member_key(member)))
+                .map(serialize_member)
+                .collect();
+
+        if !members.is_empty() {
+            inherited.push((superclass_name.clone(), members));
+        }
+        current = superclass;
+    }
+
+    inherited
+}
+
+/// Populate `diagram` with a class for each of `classfiles` (keyed by class
+/// name) and the relations described by their annotations, superclasses and
+/// interfaces. Relations already in `diagram` are kept.
+pub fn populate_diagram<'a>(
+    classfiles: &'a BTreeMap<String, ClassFile>,
+    mut diagram: Diagram<'a>,
+    config: &MergedConfig,
+) -> LinkedDiagram<'a> {
+    let skip_annotations: Vec<Option<&str>> = config
+        .skip
+        .iter()
+        .map(|annotation| Some(annotation.as_str()))
+        .collect();
+    let aggregate_annotation = config.aggregate.as_deref();
+    let compose_annotation = config.compose.as_deref();
+    let link_annotation = config.link.as_deref();
+    let navigate_annotation = config.navigate.as_deref();
+
+    // Determine if we should group by package
+    let group_by_package = should_group_by_package(&diagram);
+    let default_namespace_name = get_default_namespace_name(&diagram);
+
+    // Nested classes can be placed in a namespace named after their outer class
+    let nest_in_namespace = is_umlink_flag_set(&diagram, "nestInNamespace");
+
+    // Members generated by Lombok can be hidden along with skipped ones
+    let hide_lombok = is_umlink_flag_set(&diagram, "hideLombok");
+    let mut member_skip_annotations = skip_annotations.clone();
+    member_skip_annotations.push(hide_lombok.then_some(LOMBOK_GENERATED));
+
+    // How relationships on map fields resolve to the key/value types
+    let map_relations = get_map_relations(&diagram);
+
+    // Subclasses of a loaded abstract class can optionally be drawn as realizations
+    let abstract_as_realization = is_umlink_flag_set(&diagram, "abstractAsRealization");
+
+    // Classes using a loaded annotation type can optionally depend on it
+    let annotation_dependencies = is_umlink_flag_set(&diagram, "annotationDependencies");
+
+    // Classes can optionally depend on the loaded types their methods take or return
+    let infer_dependencies = is_umlink_flag_set(&diagram, "inferDependencies");
+
+    // User defined relation kinds, drawn after the standard ones are checked
+    let custom_relations = get_custom_relations(&diagram);
+
+    // The configured kinds of the relationship annotations, where they differ
+    // from the defaults
+    for name in config.kinds.keys() {
+        if !ANNOTATION_PRECEDENCE.contains(&name.as_str()) {
+            diagnostics::warn(format_args!(
+                "Unknown relationship annotation `{}` in kinds, expected one of: {}",
+                name,
+                ANNOTATION_PRECEDENCE.join(", ")
+            ));
+        }
+    }
+    let kind_of = |name: &str, default: RelationKind| {
+        config.kinds.get(name).map_or(default, |&kind| kind.into())
+    };
+
+    // Relationship annotations with the kind they create and, for custom
+    // kinds and links, the arrow to draw. The standard ones are tried in
+    // order of precedence, followed by the custom ones. Links are undirected
+    // associations, while navigate associations point at their target.
+    let relation_annotations: Vec<(Option<&str>, RelationKind, Option<&str>)> =
+        get_annotation_precedence(&diagram)
+            .into_iter()
+            .map(|name| match name {
+                "aggregate" => (
+                    aggregate_annotation,
+                    kind_of(name, RelationKind::Aggregation),
+                    None,
+                ),
+                "compose" => (
+                    compose_annotation,
+                    kind_of(name, RelationKind::Composition),
+                    None,
+                ),
+                "link" => {
+                    let kind = kind_of(name, RelationKind::Association);
+                    let arrow = (kind == RelationKind::Association).then_some(UNDIRECTED_ARROW);
+                    (link_annotation, kind, arrow)
+                }
+                _ => (
+                    navigate_annotation,
+                    kind_of(name, RelationKind::Association),
+                    None,
+                ),
+            })
+            .chain(custom_relations.iter().map(|custom| {
+                (
+                    Some(custom.annotation.as_str()),
+                    custom.kind,
+                    Some(custom.arrow.as_str()),
+                )
+            }))
+            .collect();
+    let relationship_annotations: Vec<Option<&str>> = relation_annotations
+        .iter()
+        .map(|(annotation, _, _)| *annotation)
+        .collect();
+
+    // Annotation types are left out of the diagram unless asked for
+    let show_annotations = is_umlink_flag_set(&diagram, "showAnnotations");
+
+    // Constructors are hidden unless asked for
+    let show_constructors = is_umlink_flag_set(&diagram, "showConstructors");
+
+    // Methods inherited from loaded superclasses can be listed in each class,
+    // which the extension API view always does
+    let show_inherited = is_umlink_flag_set(&diagram, "showInherited") || config.extension_api;
+
+    // Builders can be shown as the properties they set
+    let collapse_builders = is_umlink_flag_set(&diagram, "collapseBuilders");
+
+    // Member types can keep their package (e.g. `java.util.List`)
+    let qualified_types = is_umlink_flag_set(&diagram, "qualifiedTypes");
+
+    // Members can be sorted rather than left in declaration order
+    let member_order = get_member_order(&diagram);
+
+    let member_options = MemberOptions {
+        skip_annotations: &member_skip_annotations,
+        relationship_annotations: &relationship_annotations,
+        show_constructors,
+        min_visibility: config.visibility,
+        qualified_types,
+        member_order,
+    };
+
+    // Classes can be linked to their source files
+    let source_base_url = get_source_base_url(&diagram);
+
+    // Methods can be linked to the source lines they're declared on
+    let show_member_links = is_umlink_flag_set(&diagram, "memberLinks");
+
+    // Classes can be badged with an approximate complexity
+    let complexity_badges = is_umlink_flag_set(&diagram, "complexityBadges");
+
+    // Large classes can be cut down to their first few members
+    let max_members = get_max_members(&diagram);
+
+    // Lookup of loaded classes by fully qualified name (e.g. "com/example/Base")
+    let classes_by_name: BTreeMap<String, &ClassFile> = classfiles
+        .values()
+        .filter_map(|classfile| Some((get_full_class_name(classfile)?, classfile)))
+        .collect();
+
+    // If grouping by package, find the common base package
+    let base_package = if group_by_package {
+        let full_names: Vec<String> = classfiles
+            .values()
+            .filter_map(|classfile| get_full_class_name(classfile))
+            .collect();
+
+        let packages: Vec<&str> = full_names
+            .iter()
+            .map(|full_name| get_package_name(full_name))
+            .filter(|pkg| !pkg.is_empty())
+            .collect();
+
+        find_common_base_package(&packages)
+    } else {
+        String::new()
+    };
+
+    let mut serialize_options = SerializeOptions {
+        compact_empty_classes: is_umlink_flag_set(&diagram, "compactEmptyClasses"),
+        group_relations_by_source: is_umlink_flag_set(&diagram, "groupRelationsBySource"),
+        namespace_order: get_namespace_order(&diagram),
+        inner_separator: get_inner_separator(&diagram),
+        layout_hints: is_umlink_flag_set(&diagram, "layoutHints"),
+        mark_external: is_umlink_flag_set(&diagram, "markExternal"),
+        stub_referenced_types: is_umlink_flag_set(&diagram, "stubReferencedTypes"),
+        synthetic_relation_labels: is_umlink_flag_set(&diagram, "syntheticRelationLabels"),
+        ..Default::default()
+    };
+
+    // Classfiles that made it into the diagram, used for reporting stats
+    let mut included_classfiles: Vec<&ClassFile> = Vec::new();
+
+    // Why classes were left out, used by `--explain`
+    let mut excluded_classes: BTreeMap<&str, &str> = BTreeMap::new();
+
+    // Class names by their namespace qualified name, when grouping by package
+    let mut qualified_names: BTreeMap<String, String> = BTreeMap::new();
+
+    // Take the seed's classes out of the diagram (keeping only relations and
+    // YAML), which is repopulated with full class details from classfiles
+    let seed_namespaces = std::mem::take(&mut diagram.namespaces);
+
+    // Hand written classes without a classfile (e.g. external systems) can be
+    // kept rather than discarded
+    let keep_seed_classes = is_umlink_flag_set(&diagram, "keepSeedClasses");
+
+    // Annotated fields without a label of their own are labelled by name
+    let label_from_field_name = is_umlink_flag_set(&diagram, "labelFromFieldName");
+
+    let select = select::select_filters(&diagram);
+    let exclude = select::exclude_filters(&diagram);
+    let skip_patterns = select::skip_patterns(&diagram);
+
+    // Process all classfiles and add them to the diagram unless they have the skip annotation
+    for (class_name, classfile) in classfiles {
+        // Skip annotation type definitions unless they were asked for
+        if is_annotation(classfile) && !show_annotations {
+            excluded_classes.insert(class_name, "annotation type");
+            continue;
+        }
+
+        // Check if this classfile should be included based on select filters
+        if !select::should_include_classfile(select.as_deref(), classfile) {
+            excluded_classes.insert(class_name, "not matched by select");
+            continue;
+        }
+
+        // Exclude filters win over select
+        if select::is_excluded(&exclude, classfile) {
+            excluded_classes.insert(class_name, "matched by exclude");
+            continue;
+        }
+
+        // Whole families of classes can be skipped by name
+        if select::matches_skip_pattern(&skip_patterns, classfile) {
+            excluded_classes.insert(class_name, "matched by skipPattern");
+            continue;
+        }
+
+        // Check if the class itself has any of the skip annotations
+        if skip_annotations.iter().any(|skip_annotation| {
+            classfile_utils::has_annotation(
+                classfile.constant_pool(),
+                classfile.attributes(),
+                *skip_annotation,
+            )
+        }) {
+            excluded_classes.insert(class_name, "class has the skip annotation");
+            continue; // Skip this entire class
+        }
+
+        // Convert classfile to Mermaid class
+        let mut mermaid_class = classfile_to_mermaid_class(classfile, class_name, &member_options);
+
+        for (member, annotation) in
+            classfile_utils::get_skipped_members(classfile, &member_skip_annotations)
+        {
+            diagnostics::info(format_args!(
+                "Dropped member `{}.{}`: skipped by @{}",
+                class_name, member, annotation
+            ));
+        }
+
+        // Only show what subclasses can see, and which classes can't be extended
+        if config.extension_api {
+            mermaid_class.members.retain(is_extension_visible);
+            if mermaid_class.annotation.is_none() && classfile_utils::is_final(classfile) {
+                mermaid_class.stereotypes.insert(0, "final".into());
+            }
+        }
+
+        if collapse_builders {
+            collapse_builder(&mut mermaid_class);
+        }
+
+        // Remember classes that only look empty because their members were filtered
+        if mermaid_class.members.is_empty()
+            && classfile_utils::count_declared_members(classfile) > 0
+        {
+            serialize_options
+                .filtered_classes
+                .insert(class_name.clone());
+        }
+
+        if let Some(max_members) = max_members {
+            let hidden = truncate_members(&mut mermaid_class, max_members);
+            if hidden != (0, 0) {
+                serialize_options
+                    .truncated_members
+                    .insert(class_name.clone(), hidden);
+            }
+        }
+
+        if show_inherited {
+            let inherited =
+                inherited_members(classfile, classfiles, &member_options, config.extension_api);
+            if !inherited.is_empty() {
+                serialize_options
+                    .inherited_members
+                    .insert(class_name.clone(), inherited);
+            }
+        }
+
+        if show_member_links {
+            let links = member_links(classfile, &mermaid_class, qualified_types);
+            if !links.is_empty() {
+                serialize_options
+                    .member_links
+                    .insert(class_name.clone(), links);
+            }
+        }
+
+        if let Some(full_class_name) = get_full_class_name(classfile) {
+            serialize_options
+                .full_names
+                .insert(class_name.clone(), full_class_name.replace(['/', '$'], "."));
+        }
+
+        if let Some(url) = source_base_url
+            .as_deref()
+            .and_then(|base_url| source_url(base_url, classfile))
+        {
+            serialize_options
+                .source_links
+                .insert(class_name.clone(), url);
+        }
+
+        if let Some(note_annotation) = config.note.as_deref()
+            && let Some(text) = classfile_utils::get_annotation_value(
+                classfile.constant_pool(),
+                classfile.attributes(),
+                note_annotation,
+            )
+        {
+            serialize_options.notes.insert(class_name.clone(), text);
+        }
+
+        if complexity_badges {
+            serialize_options
+                .complexity_badges
+                .insert(class_name.clone(), complexity::class_complexity(classfile));
+        }
+
+        // Determine the namespace for this class
+        let namespace_name = if group_by_package {
+            if let Some(full_class_name) = get_full_class_name(classfile) {
+                let package = get_package_name(&full_class_name);
+                get_relative_namespace(&base_package, package, &default_namespace_name)
+            } else {
+                mermaid_parser::types::DEFAULT_NAMESPACE.to_string()
+            }
+        } else {
+            mermaid_parser::types::DEFAULT_NAMESPACE.to_string()
+        };
+
+        // Remember the namespace qualified name (e.g. `ui.Config`) relations
+        // can use to point at this class
+        if group_by_package && let Some(full_class_name) = get_full_class_name(classfile) {
+            let simple_name = full_class_name
+                .rsplit('/')
+                .next()
+                .unwrap_or(&full_class_name)
+                .replace('$', ".");
+            qualified_names.insert(
+                format!("{}.{}", namespace_name, simple_name),
+                class_name.clone(),
+            );
+        }
+
+        // Nested classes can be grouped in a namespace named after their
+        // enclosing class, within their package's namespace when grouping
+        let namespace_name = match get_enclosing_class_name(classfile) {
+            Some(outer) if nest_in_namespace => {
+                if namespace_name == mermaid_parser::types::DEFAULT_NAMESPACE {
+                    outer
+                } else {
+                    format!("{}.{}", namespace_name, outer)
+                }
+            }
+            _ => namespace_name,
+        };
+
+        // Add the class to the appropriate namespace
+        let namespace = diagram.namespaces.entry(namespace_name.into()).or_default();
+
+        namespace
+            .classes
+            .insert(class_name.clone().into(), mermaid_class);
+        included_classfiles.push(classfile);
+
+        // Process fields to find relationship annotations
+        let constant_pool = classfile.constant_pool();
+        for field in classfile.fields() {
+            // Fields hidden by the visibility threshold don't relate classes
+            if !config
+                .visibility
+                .allows(classfile_utils::field_visibility(field.access_flags()))
+            {
+                continue;
+            }
+
+            let field_descriptor =
+                classfile_utils::get_utf8(constant_pool, field.descriptor_index()).unwrap_or("");
+
+            // Resolve the classes the field points at (if it holds objects)
+            let targets = resolve_relation_targets(
+                field_descriptor,
+                classfile_utils::get_signature(constant_pool, field.attributes()),
+                map_relations,
+            );
+            let field_name = classfile_utils::get_utf8(constant_pool, field.name_index())
+                .filter(|_| label_from_field_name);
+            add_annotated_relations(
+                &mut diagram,
+                class_name,
+                constant_pool,
+                field.attributes(),
+                &targets,
+                &relation_annotations,
+                field_name,
+            );
+        }
+
+        // Methods (typically getters) can carry relationship annotations too,
+        // relating the class to their return type
+        for method in classfile.methods() {
+            if classfile_utils::is_synthetic_method(method)
+                || !config
+                    .visibility
+                    .allows(classfile_utils::method_visibility(method.access_flags()))
+            {
+                continue;
+            }
+
+            let method_descriptor =
+                classfile_utils::get_utf8(constant_pool, method.descriptor_index()).unwrap_or("");
+            let targets = resolve_relation_targets(
+                method_return_descriptor(method_descriptor),
+                classfile_utils::get_signature(constant_pool, method.attributes())
+                    .map(method_return_descriptor),
+                map_relations,
+            );
+            add_annotated_relations(
+                &mut diagram,
+                class_name,
+                constant_pool,
+                method.attributes(),
+                &targets,
+                &relation_annotations,
+                None,
+            );
+        }
+
+        // Add inheritance relationship if the class extends another class
+        if let Some(superclass) = get_superclass_name(classfile) {
+            let superclass_is_abstract = get_superclass_full_name(classfile)
+                .and_then(|full_name| classes_by_name.get(&full_name))
+                .is_some_and(|superclass| is_abstract(superclass));
+            let kind = if abstract_as_realization && superclass_is_abstract {
+                RelationKind::Realization
+            } else {
+                RelationKind::Inheritance
+            };
+
+            push_supertype_relation(&mut diagram, class_name, &superclass, kind);
+        }
+
+        // Add realization relationships for implemented interfaces, or
+        // inheritance for the interfaces an interface extends. Every
+        // annotation type implements `Annotation`, so that edge is left out.
+        let interface_kind = if is_interface(classfile) && !is_annotation(classfile) {
+            RelationKind::Inheritance
+        } else {
+            RelationKind::Realization
+        };
+        for interface in get_interface_names(classfile) {
+            if is_annotation(classfile) && interface == "Annotation" {
+                continue;
+            }
+
+            push_supertype_relation(&mut diagram, class_name, &interface, interface_kind);
+        }
+
+        // Sealed classes point out the subclasses they permit, in case those
+        // don't already relate to them
+        for subclass in get_permitted_subclasses(classfile) {
+            let Some(subclass_file) = classfiles.get(&subclass) else {
+                continue;
+            };
+            let permitted_kind = if is_interface(classfile) {
+                if is_interface(subclass_file) {
+                    RelationKind::Inheritance
+                } else {
+                    RelationKind::Realization
+                }
+            } else if abstract_as_realization && is_abstract(classfile) {
+                RelationKind::Realization
+            } else {
+                RelationKind::Inheritance
+            };
+            push_supertype_relation(&mut diagram, &subclass, class_name, permitted_kind);
+        }
+
+        // Nested classes are drawn as composed by their outer class
+        for nested in get_nested_class_names(classfile) {
+            if !classfiles.contains_key(&nested) {
+                continue;
+            }
+
+            let already_related = diagram.relations.iter().any(|relation| {
+                relation.tail == class_name.as_str()
+                    && relation.head == nested.as_str()
+                    && relation.kind == RelationKind::Composition
+            });
+            if already_related {
+                continue;
+            }
+
+            let relation = Relation {
+                tail: class_name.clone().into(),
+                head: nested.into(),
+                kind: RelationKind::Composition,
+                cardinality_tail: None,
+                cardinality_head: None,
+                label: None,
+                arrow: None,
+            };
+            diagram.relations.push(relation);
+        }
+
+        // Add dependencies on loaded annotation types applied to the class
+        if annotation_dependencies {
+            let configured_annotations: Vec<String> = skip_annotations
+                .iter()
+                .copied()
+                .chain([
+                    aggregate_annotation,
+                    compose_annotation,
+                    link_annotation,
+                    navigate_annotation,
+                ])
+                .flatten()
+                .map(|annotation| annotation.replace('.', "/"))
+                .collect();
+
+            let mut seen = std::collections::BTreeSet::new();
+            for annotation in classfile_utils::get_annotation_names(
+                classfile.constant_pool(),
+                classfile.attributes(),
+            ) {
+                if configured_annotations.contains(&annotation)
+                    || !classes_by_name.contains_key(&annotation)
+                    || !seen.insert(annotation.clone())
+                {
+                    continue;
+                }
+
+                let simple_name = annotation.rsplit('/').next().unwrap_or(&annotation);
+                let relation = Relation {
+                    tail: class_name.clone().into(),
+                    head: simple_name.replace('$', ".").into(),
+                    kind: RelationKind::Dependency,
+                    cardinality_tail: None,
+                    cardinality_head: None,
+                    label: None,
+                    arrow: None,
+                };
+                diagram.relations.push(relation);
+            }
+        }
+        // Add dependencies on loaded types used only in method signatures
+        if infer_dependencies {
+            let own_name = get_full_class_name(classfile).unwrap_or_default();
+            for used in get_method_signature_classes(classfile, &member_skip_annotations) {
+                if used == own_name || !classes_by_name.contains_key(&used) {
+                    continue;
+                }
+
+                let simple_name = used.rsplit('/').next().unwrap_or(&used).replace('$', ".");
+                // Repeated uses, and classes the class already relates to
+                // (e.g. through a field), add nothing
+                let already_related = diagram.relations.iter().any(|relation| {
+                    relation.tail == class_name.as_str() && relation.head == simple_name.as_str()
+                });
+                if already_related {
+                    continue;
+                }
+
+                let relation = Relation {
+                    tail: class_name.clone().into(),
+                    head: simple_name.into(),
+                    kind: RelationKind::Dependency,
+                    cardinality_tail: None,
+                    cardinality_head: None,
+                    label: None,
+                    arrow: None,
+                };
+                diagram.relations.push(relation);
+            }
+        }
+    }
+
+    for (class_name, reason) in &excluded_classes {
+        diagnostics::info(format_args!("Skipped class `{}`: {}", class_name, reason));
+    }
+
+    // Merge back seed classes no classfile was loaded for, in the namespace
+    // they were written in. Classes with a classfile were regenerated above,
+    // or were left out on purpose.
+    if keep_seed_classes {
+        for (namespace_name, namespace) in seed_namespaces {
+            for (name, class) in namespace.classes {
+                let qualified_name = format!("{}.{}", namespace_name, name);
+                if classfiles.contains_key(name.as_ref())
+                    || qualified_names.contains_key(&qualified_name)
+                    || graph::has_class(&diagram, &name)
+                {
+                    continue;
+                }
+                diagram
+                    .namespaces
+                    .entry(namespace_name.clone())
+                    .or_default()
+                    .classes
+                    .insert(name, class);
+            }
+        }
+    }
+
+    // Relations written with namespace qualified ends point at the class in
+    // that namespace
+    for relation in &mut diagram.relations {
+        if let Some(name) = qualified_names.get(relation.tail.as_ref()) {
+            relation.tail = name.clone().into();
+        }
+        if let Some(name) = qualified_names.get(relation.head.as_ref()) {
+            relation.head = name.clone().into();
+        }
+    }
+
+    // Point relations at the right class when simple names were disambiguated
+    resolve_ambiguous_heads(&mut diagram, classfiles);
+
+    // Relations between the same classes can be collapsed into one weighted edge
+    if is_umlink_flag_set(&diagram, "weightedEdges") {
+        graph::weight_relations(&mut diagram);
+    }
+
+    // Keep the output stable as classes come and go
+    graph::sort_relations(&mut diagram, &serialize_options.full_names);
+
+    let skip_annotations = member_skip_annotations
+        .iter()
+        .flatten()
+        .map(|annotation| annotation.to_string())
+        .collect();
+    let relationship_annotations = relationship_annotations
+        .iter()
+        .flatten()
+        .map(|annotation| annotation.to_string())
+        .collect();
+
+    LinkedDiagram {
+        diagram,
+        serialize_options,
+        classfiles: included_classfiles,
+        excluded_classes,
+        skip_annotations,
+        relationship_annotations,
+        show_constructors,
+    }
+}
+
+/// Parse a seed diagram (with its `%% @manual` relations already split out)
+/// and apply the config's options to it. An empty seed gives an empty diagram.
+fn parse_seed<'a>(seed: &'a str, config: &MergedConfig) -> anyhow::Result<Diagram<'a>> {
+    let mut diagram = if seed.is_empty() {
+        Diagram::default()
+    } else {
+        mermaid_parser::parserv2::parse_mermaid(seed)
+            .map_err(|why| anyhow!("{}", why))?
+            .1
+            .into()
+    };
+    apply_config_options(&mut diagram, config);
+    Ok(diagram)
+}
+
+/// How a populated diagram is reshaped before it's serialized
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+    /// Paths the classfiles were loaded from, noted in the frontmatter with
+    /// `recordInputs`
+    pub class_paths: Vec<String>,
+    /// Only keep the type hierarchy (see `graph::skeleton`)
+    pub skeleton: bool,
+    /// Only keep the classes reachable from these, unless there are none
+    pub roots: Vec<String>,
+    /// Which way relations are followed from `roots`
+    pub roots_direction: graph::Direction,
+}
+
+/// Populate a parsed seed diagram from the classfiles and reshape the result:
+/// the hand authored `manual` relations take the place of generated ones
+/// between the same classes, identical relations are only kept once and
+/// `options` may reduce the diagram further
+pub fn link_classfiles<'a>(
+    classfiles: &'a BTreeMap<String, ClassFile>,
+    diagram: Diagram<'a>,
+    manual: Vec<String>,
+    config: &MergedConfig,
+    options: &LinkOptions,
+) -> LinkedDiagram<'a> {
+    let mut linked = populate_diagram(classfiles, diagram, config);
+
+    let pinned = manual_relations::pinned_relations(&manual);
+    manual_relations::remove_pinned_relations(&mut linked.diagram, &pinned);
+    linked.serialize_options.manual_relations = manual;
+
+    // Identical relations (e.g. from two annotated fields of the same type)
+    // are only drawn once
+    graph::dedup_relations(&mut linked.diagram);
+
+    record_inputs(&mut linked.diagram, &options.class_paths, config);
+
+    // Reduce to the type hierarchy, members are dropped rather than filtered
+    // so classes shouldn't keep a body hinting at hidden members
+    if options.skeleton {
+        graph::skeleton(&mut linked.diagram);
+        linked.serialize_options.filtered_classes.clear();
+        linked.serialize_options.inherited_members.clear();
+        linked.serialize_options.member_links.clear();
+    }
+
+    // Prune everything not reachable from the requested roots
+    if !options.roots.is_empty() {
+        for root in &options.roots {
+            if !graph::has_class(&linked.diagram, root) {
+                diagnostics::warn(format_args!("Root class `{}` is not in the diagram", root));
+            }
+        }
+
+        let reachable =
+            graph::reachable_from(&linked.diagram, &options.roots, options.roots_direction);
+        graph::retain_classes(&mut linked.diagram, &reachable);
+    }
+
+    linked
+}
+
+/// Populate a parsed seed diagram from the classfiles and serialize it, with
+/// the hand authored `manual` relations written back verbatim
+fn link_parsed<'a>(
+    classfiles: &'a BTreeMap<String, ClassFile>,
+    diagram: Diagram<'a>,
+    manual: Vec<String>,
+    config: &MergedConfig,
+) -> String {
+    let linked = link_classfiles(classfiles, diagram, manual, config, &LinkOptions::default());
+    normalize_output(&serialize_diagram(
+        &linked.diagram,
+        &linked.serialize_options,
+    ))
+}
+
+/// Link a diagram from classfiles already loaded and indexed by their name in
+/// the diagram (see `index_classfiles`). `seed` is the optional Mermaid source
+/// to link. Returns the linked Mermaid text, as the `umlink` binary would
+/// write it.
+pub fn link_diagram(
+    classfiles: &BTreeMap<String, ClassFile>,
+    seed: Option<&str>,
+    config: &MergedConfig,
+) -> anyhow::Result<String> {
+    let (seed, manual) = manual_relations::split_manual_relations(seed.unwrap_or_default());
+    let diagram = parse_seed(&seed, config)?;
+    Ok(link_parsed(classfiles, diagram, manual, config))
+}
+
+/// Generate a diagram from in-memory classfiles rather than reading them from
+/// disk. Each class is given as its name in the diagram (e.g. `Outer.Inner`)
+/// and the classfile's bytes. `seed` is the optional Mermaid source to link.
+pub fn generate_from_bytes(
+    classes: Vec<(String, Vec<u8>)>,
+    seed: Option<&str>,
+    config: &MergedConfig,
+) -> anyhow::Result<String> {
+    let mut parsed = Vec::new();
+    for (name, bytes) in classes {
+        let classfile = class_file::parse(&bytes)
+            .map_err(|why| anyhow!("Failed to parse classfile `{}`: {}", name, why))?;
+        parsed.push((name, classfile));
+    }
+
+    // The seed decides how duplicate names are indexed
+    let (seed, manual) = manual_relations::split_manual_relations(seed.unwrap_or_default());
+    let diagram = parse_seed(&seed, config)?;
+    let classfiles = index_classfiles(parsed, get_disambiguate(&diagram))?;
+    Ok(link_parsed(&classfiles, diagram, manual, config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        Diagram, Relation, find_common_base_package, get_annotation_precedence,
+        get_relative_namespace, merge_diagrams,
+    };
+    use mermaid_parser::types::{DEFAULT_NAMESPACE, RelationKind};
+
+    #[test]
+    fn test_find_common_base_package() {
+        let prefix = find_common_base_package(&[
+            "com/MainActivity",
+            "com/example/example/Helper",
+            "com/example/Second",
+        ]);
+
+        assert_eq!("com", prefix);
+
+        let prefix = find_common_base_package(&[
+            "com/example/example/Helper",
+            "com/example/Second",
+            "com/example/Third",
+        ]);
+
+        assert_eq!("com/example", prefix);
+
+        let prefix = find_common_base_package(&[
+            "other/example/example/Helper",
+            "com/example/Second",
+            "com/example/Third",
+        ]);
+
+        assert_eq!("", prefix);
+    }
+
+    #[test]
+    fn test_relative_namespace() {
+        assert_eq!(
+            get_relative_namespace("com/example", "com/example/ui", DEFAULT_NAMESPACE),
+            "ui"
+        );
+        assert_eq!(
+            get_relative_namespace("com/example", "com/example", DEFAULT_NAMESPACE),
+            DEFAULT_NAMESPACE
+        );
+        assert_eq!(
+            get_relative_namespace("com/example", "com/example", "root"),
+            "root"
+        );
+        assert_eq!(
+            get_relative_namespace("", "com/example", "root"),
+            "com.example"
+        );
+    }
+
+    #[test]
+    fn test_annotation_precedence() {
+        let diagram = Diagram {
+            yaml: serde_yml::from_str(
+                "umlink:\n  annotationPrecedence: [link, bogus, compose, link]\n",
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_annotation_precedence(&diagram),
+            ["link", "compose", "aggregate", "navigate"]
+        );
+        assert_eq!(
+            get_annotation_precedence(&Diagram::default()),
+            ["aggregate", "compose", "link", "navigate"]
+        );
+    }
+
+    #[test]
+    fn test_merge_diagrams() {
+        let relation = |tail: &'static str, head: &'static str| Relation {
+            tail: tail.into(),
+            head: head.into(),
+            kind: RelationKind::Association,
+            cardinality_tail: None,
+            cardinality_head: None,
+            label: None,
+            arrow: None,
+        };
+        let first = Diagram {
+            yaml: serde_yml::from_str("umlink:\n  groupPackage: true\n  maxMembers: 5\n").unwrap(),
+            relations: vec![relation("Shop", "Order"), relation("Order", "User")],
+            ..Default::default()
+        };
+        let second = Diagram {
+            yaml: serde_yml::from_str("title: Shop\numlink:\n  maxMembers: 10\n").unwrap(),
+            relations: vec![relation("Order", "User"), relation("Shop", "User")],
+            ..Default::default()
+        };
+
+        let merged = merge_diagrams([first, second]);
+        let yaml = merged.yaml.unwrap();
+        assert_eq!(yaml["title"], "Shop");
+        assert_eq!(yaml["umlink"]["groupPackage"], true);
+        assert_eq!(yaml["umlink"]["maxMembers"], 10);
+        assert_eq!(
+            merged.relations,
+            [
+                relation("Shop", "Order"),
+                relation("Order", "User"),
+                relation("Shop", "User")
+            ]
+        );
+    }
+}
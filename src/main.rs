@@ -1,28 +1,38 @@
-mod classfile_utils;
-mod descriptor;
-
 use anyhow::anyhow;
 use clap::Parser;
-use classfile_utils::{
-    classfile_to_mermaid_class, get_full_class_name, get_interface_names, get_package_name,
-    get_superclass_name, is_annotation,
-};
-use descriptor::extract_class_name_from_descriptor;
 use jclassfile::class_file::{self, ClassFile};
-use mermaid_parser::serializer::serialize_diagram;
-use mermaid_parser::types::{Diagram, RelationKind};
+use notify::{EventKind, RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     fs,
+    io::{Read, Write},
     path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use umlink::classfile_utils::MinVisibility;
+use umlink::diagram::Diagram;
+use umlink::{
+    AnnotationKind, LinkOptions, LinkedDiagram, MergedConfig, OutputFormat, classfile_utils,
+    diagnostics, explain, graph, manual_relations, mermaid_output::normalize_output, module_graph,
+    relations_file, report, stats,
 };
 
 /// Configuration that can be loaded from a YAML file
+///
+/// Values are layered with the following precedence (highest first):
+/// command-line arguments, `UMLINK_*` environment variables, then the
+/// configuration file.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
-    /// The fully qualified path of the skip annotation
-    pub skip: Option<String>,
+    /// Fully qualified paths of the skip annotations, given as a single path
+    /// or a list of them
+    #[serde(
+        default,
+        deserialize_with = "deserialize_one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub skip: Vec<String>,
     /// Fully qualified path to the aggregate annotation
     pub aggregate: Option<String>,
     /// Fully qualified path to the compose annotation
@@ -31,99 +41,278 @@ pub struct Config {
     pub link: Option<String>,
     /// Fully qualified path to the navigate annotation
     pub navigate: Option<String>,
+    /// Fully qualified path to the note annotation
+    pub note: Option<String>,
+    /// Relation kinds created by the relationship annotations in place of
+    /// their defaults (e.g. `link: composition`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub kinds: BTreeMap<String, AnnotationKind>,
+    /// Frontmatter style `umlink` options (e.g. `hideLombok: true`), used
+    /// where the diagram doesn't set them itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub options: Option<serde_yml::Mapping>,
+    /// Named sets of settings, one of which is picked by `--profile`. A
+    /// profile's settings take precedence over the top-level ones.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Config>,
+}
+
+/// Accept either a single string or a list of strings
+fn deserialize_one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
 }
 
+/// File names recognised as umlink configuration, in order of preference
+const CONFIG_FILE_NAMES: [&str; 2] = ["umlink.yml", "umlink.toml"];
+
 impl Config {
-    /// Load configuration from a file path
+    /// Load configuration from a file path. Files with a `.toml` extension are
+    /// parsed as TOML, everything else as YAML.
     fn load_from_file(path: &Path) -> anyhow::Result<Self> {
         let content = fs::read_to_string(path)?;
-        let config: Config = serde_yml::from_str(&content)?;
+        let config: Config = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)?
+        } else {
+            serde_yml::from_str(&content)?
+        };
         Ok(config)
     }
 
-    /// Attempt to load configuration, first from the provided path,
-    /// then from umlink.yml in the current directory if no path is provided
-    fn load(config_path: Option<&Path>) -> Option<Self> {
+    /// Find a configuration file in `start`, and if `search_parents` is set,
+    /// in each of its ancestors until the filesystem root is reached.
+    fn find_config_file(start: &Path, search_parents: bool) -> Option<PathBuf> {
+        let mut dir = Some(start);
+        while let Some(current) = dir {
+            for name in CONFIG_FILE_NAMES {
+                let candidate = current.join(name);
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+
+            if !search_parents {
+                break;
+            }
+            dir = current.parent();
+        }
+        None
+    }
+
+    /// Attempt to load configuration, first from the provided path, then from
+    /// umlink.yml/umlink.toml in the current directory or (if `search_parents`
+    /// is set) the nearest parent directory containing one.
+    fn load(config_path: Option<&Path>, search_parents: bool) -> Option<Self> {
         if let Some(path) = config_path {
             // Explicit config path provided
             match Self::load_from_file(path) {
                 Ok(config) => {
-                    eprintln!("Loaded configuration from {}", path.display());
+                    if !diagnostics::is_quiet() {
+                        eprintln!("Loaded configuration from {}", path.display());
+                    }
                     return Some(config);
                 }
                 Err(e) => {
-                    eprintln!("WARN: Failed to load config from {}: {}", path.display(), e);
+                    diagnostics::warn(format_args!(
+                        "Failed to load config from {}: {}",
+                        path.display(),
+                        e
+                    ));
                     return None;
                 }
             }
         }
 
-        // Try to load from umlink.yml in current directory
-        let default_path = PathBuf::from("umlink.yml");
-        if default_path.exists() {
-            match Self::load_from_file(&default_path) {
-                Ok(config) => {
-                    eprintln!("Loaded configuration from umlink.yml");
-                    Some(config)
-                }
-                Err(e) => {
-                    eprintln!("WARN: Failed to load config from umlink.yml: {}", e);
-                    None
+        let current_dir = std::env::current_dir().ok()?;
+        let path = Self::find_config_file(&current_dir, search_parents)?;
+        match Self::load_from_file(&path) {
+            Ok(config) => {
+                if !diagnostics::is_quiet() {
+                    eprintln!("Loaded configuration from {}", path.display());
                 }
+                Some(config)
+            }
+            Err(e) => {
+                diagnostics::warn(format_args!(
+                    "Failed to load config from {}: {}",
+                    path.display(),
+                    e
+                ));
+                None
             }
-        } else {
-            None
         }
     }
 
-    /// Merge with command-line arguments, where args take precedence
+    /// Read overrides from the `UMLINK_SKIP` (comma separated),
+    /// `UMLINK_AGGREGATE`, `UMLINK_COMPOSE`, `UMLINK_LINK`, `UMLINK_NAVIGATE`
+    /// and `UMLINK_NOTE` environment variables. Unset or empty variables are
+    /// ignored, as are blank entries of `UMLINK_SKIP`.
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Config {
+            skip: var("UMLINK_SKIP")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|entry| !entry.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            aggregate: var("UMLINK_AGGREGATE"),
+            compose: var("UMLINK_COMPOSE"),
+            link: var("UMLINK_LINK"),
+            navigate: var("UMLINK_NAVIGATE"),
+            note: var("UMLINK_NOTE"),
+            ..Default::default()
+        }
+    }
+
+    /// Layer the settings of the named profile on top of the top-level ones.
+    /// Without a name the `default` profile is used if there is one.
+    fn select_profile(mut self, name: Option<&str>) -> anyhow::Result<Config> {
+        let profile = match name {
+            Some(name) => Some(self.profiles.remove(name).ok_or_else(|| {
+                let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+                anyhow!(
+                    "Unknown profile `{}`, expected one of: {}",
+                    name,
+                    known.join(", ")
+                )
+            })?),
+            None => self.profiles.remove("default"),
+        };
+        self.profiles.clear();
+
+        Ok(match profile {
+            Some(profile) => self.overlay(profile),
+            None => self,
+        })
+    }
+
+    /// Layer `overrides` on top of this config, where values set in
+    /// `overrides` take precedence
+    fn overlay(self, overrides: Config) -> Config {
+        Config {
+            skip: if overrides.skip.is_empty() {
+                self.skip
+            } else {
+                overrides.skip
+            },
+            aggregate: overrides.aggregate.or(self.aggregate),
+            compose: overrides.compose.or(self.compose),
+            link: overrides.link.or(self.link),
+            navigate: overrides.navigate.or(self.navigate),
+            note: overrides.note.or(self.note),
+            kinds: {
+                let mut kinds = self.kinds;
+                kinds.extend(overrides.kinds);
+                kinds
+            },
+            options: match (self.options, overrides.options) {
+                (Some(mut options), Some(overrides)) => {
+                    options.extend(overrides);
+                    Some(options)
+                }
+                (options, overrides) => overrides.or(options),
+            },
+            profiles: self.profiles,
+        }
+    }
+
+    /// Merge with command-line arguments, where args take precedence. Skip
+    /// annotations from both are used.
     fn merge_with_args(&self, args: &Args) -> MergedConfig {
+        let mut skip = args.skip.clone();
+        for annotation in &self.skip {
+            if !skip.contains(annotation) {
+                skip.push(annotation.clone());
+            }
+        }
+
         MergedConfig {
-            skip: args.skip.clone().or_else(|| self.skip.clone()),
+            skip,
             aggregate: args.aggregate.clone().or_else(|| self.aggregate.clone()),
             compose: args.compose.clone().or_else(|| self.compose.clone()),
             link: args.link.clone().or_else(|| self.link.clone()),
             navigate: args.navigate.clone().or_else(|| self.navigate.clone()),
+            note: args.note.clone().or_else(|| self.note.clone()),
+            options: self.options.clone(),
+            extension_api: args.extension_api,
+            visibility: args.visibility,
+            kinds: self.kinds.clone(),
         }
     }
 }
 
-/// The merged configuration after combining config file and CLI arguments
-#[derive(Debug, Clone)]
-pub struct MergedConfig {
-    pub skip: Option<String>,
-    pub aggregate: Option<String>,
-    pub compose: Option<String>,
-    pub link: Option<String>,
-    pub navigate: Option<String>,
-}
-
 /// This program will take in a list of mermaid files which need "linking"
 /// according to some list of targets.
 #[derive(clap::Parser)]
 pub struct Args {
-    /// Some mermaid diagram file, generally containing relationships but
-    /// can also have classes. It is basically a starting off point for the
-    /// diagram generation.
-    diagram: Option<PathBuf>,
+    /// Some mermaid diagram files, generally containing relationships but
+    /// can also have classes. They are basically a starting off point for the
+    /// diagram generation. Several files are merged into one diagram: their
+    /// frontmatter is combined (the last file wins when they disagree) and
+    /// relations declared in more than one file are kept once.
+    diagram: Vec<PathBuf>,
     /// Files and folders to search for class definitions. Folders will be
-    /// searched recursively any folder. These should be java class files.
+    /// searched recursively any folder. These should be java class files, or
+    /// .jar and .zip archives of them.
     #[arg(short, long)]
     classfiles: Vec<PathBuf>,
+    /// A Java style classpath of directories and archives separated by the
+    /// platform's path separator (`:`, or `;` on Windows), such as
+    /// `--classpath "$CLASSPATH"`. Each entry is searched like a
+    /// `--classfiles` path, and empty entries are ignored.
+    #[arg(long, value_name = "PATHS")]
+    classpath: Option<std::ffi::OsString>,
     /// Directory or filename for output file. If a directory is given this
     /// will be the same as the input name.
     #[arg(short, long)]
     output: PathBuf,
-    /// Path to the YAML configuration file. If not provided, will look for
-    /// umlink.yml in the current directory.
+    /// Name of the output file when `--output` is a directory, instead of the
+    /// input diagram's name (or `output.mmd`). `.mmd` is appended if it has no
+    /// extension. Ignored, with a warning, when `--output` names a file.
+    #[arg(long)]
+    name: Option<String>,
+    /// Format the diagram is written in. When `--output` is a directory, the
+    /// file written gets this format's extension (e.g. `.puml` or `.dot`).
+    /// Module graphs are always written as Mermaid.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Mermaid)]
+    format: OutputFormat,
+    /// Path to the YAML (or TOML) configuration file. If not provided, will
+    /// look for umlink.yml or umlink.toml in the current directory and then
+    /// each parent directory.
     #[arg(long)]
     config: Option<PathBuf>,
+    /// Only look for a configuration file in the current directory rather
+    /// than also searching parent directories.
+    #[arg(long)]
+    no_config_search: bool,
+    /// Use the settings of this profile from the configuration file's
+    /// `profiles`. Defaults to the `default` profile if there is one.
+    #[arg(long)]
+    profile: Option<String>,
     /// The fully qualified path of the skip annotation to optionally enable
     /// ommiting some types, fields, or methods. (e.g. `com.rocket.radar.Skip`)
     /// Note that this annotation must have a retention policy of RUNTIME
-    /// or CLASS.
+    /// or CLASS. Can be given more than once, and is used along with any
+    /// from the config file.
     #[arg(long)]
-    skip: Option<String>,
+    skip: Vec<String>,
     /// Fully qualified path to the aggregate annotation.
     #[arg(long)]
     aggregate: Option<String>,
@@ -136,6 +325,90 @@ pub struct Args {
     /// Fully qualified path to the navigate annotation.
     #[arg(long)]
     navigate: Option<String>,
+    /// Fully qualified path to the note annotation. The text of a class's
+    /// note annotation (e.g. `@DiagramNote("Handles auth")`) is attached to
+    /// the class as a note.
+    #[arg(long)]
+    note: Option<String>,
+    /// Also write metrics about the generated diagram (class kinds, relation
+    /// counts, members per class, classes per package) as JSON to this path.
+    #[arg(long)]
+    stats_json: Option<PathBuf>,
+    /// Also write a summary of the generated diagram as JSON to this path:
+    /// every class with its package and member counts, and every relation
+    /// with its kind, cardinalities and label.
+    #[arg(long)]
+    report: Option<PathBuf>,
+    /// Only keep classes reachable from these classes (comma separated) by
+    /// transitively following relations. Everything else is pruned.
+    #[arg(long, value_delimiter = ',')]
+    roots: Vec<String>,
+    /// Which way relations are followed when collecting classes for `--roots`.
+    #[arg(long, value_enum, default_value_t = graph::Direction::Outgoing)]
+    roots_direction: graph::Direction,
+    /// Only emit the type hierarchy: classes without members and only
+    /// inheritance and realization relations.
+    #[arg(long)]
+    skeleton: bool,
+    /// Only show what subclass authors can use: public and protected members,
+    /// including those inherited from loaded superclasses. Final classes are
+    /// marked `<<final>>`.
+    #[arg(long)]
+    extension_api: bool,
+    /// Only show members at least this visible. Relations from fields which
+    /// are hidden are left out too. Defaults to showing every member.
+    #[arg(long, value_enum, default_value_t = MinVisibility::Private)]
+    visibility: MinVisibility,
+    /// Instead of classes, draw the modules described by the loaded
+    /// `module-info.class` files with their `requires` dependencies and notes
+    /// listing the packages they export and open.
+    #[arg(long)]
+    module_graph: bool,
+    /// Instead of writing the diagram, print a report explaining how this
+    /// class was rendered: which members were kept or dropped and why, and
+    /// where each of its relations came from.
+    #[arg(long, value_name = "CLASS_NAME")]
+    explain: Option<String>,
+    /// When to color warnings and errors. `auto` colors them only when stderr
+    /// is a terminal.
+    #[arg(long, value_enum, default_value_t = diagnostics::ColorChoice::Auto)]
+    color: diagnostics::ColorChoice,
+    /// YAML file listing extra relations to add to the diagram, each with a
+    /// `from` and `to` class and an optional `kind` and `label`.
+    #[arg(long)]
+    relations_file: Option<PathBuf>,
+    /// Replace the input diagram's relations with the generated ones rather
+    /// than keeping them. Relations marked with a trailing `%% @manual`
+    /// comment are always kept verbatim.
+    #[arg(long)]
+    overwrite_relations: bool,
+    /// Number of threads used to read and parse classfiles, which speeds up
+    /// loading large trees. `0` uses one per available CPU.
+    #[arg(short, long, default_value_t = 1)]
+    jobs: usize,
+    /// Skip classfiles (loose or in an archive) larger than this many bytes
+    /// with a warning, rather than parsing them. Guards against corrupt or
+    /// hostile inputs.
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_CLASSFILE_BYTES)]
+    max_classfile_bytes: u64,
+    /// Report on stderr each class, classfile and member left out of the
+    /// diagram and why (e.g. a skip annotation or `select` filters).
+    #[arg(short, long)]
+    verbose: bool,
+    /// Only print errors: no warnings, no status lines such as where the
+    /// output was written, and no `--verbose` reports.
+    #[arg(short, long)]
+    quiet: bool,
+    /// Run the whole pipeline but print a summary of the classes and
+    /// relations found instead of writing the output. Load and parse failures
+    /// still exit with an error.
+    #[arg(long, visible_alias = "dry-run")]
+    check: bool,
+    /// Keep running after writing the diagram and regenerate it whenever a
+    /// classfile or archive under the `--classfiles` paths changes. Not used
+    /// with `--module-graph`, `--explain` or `--check`.
+    #[arg(long)]
+    watch: bool,
 }
 
 #[derive(thiserror::Error, derive_more::From, Debug)]
@@ -144,26 +417,100 @@ enum LoadClassError {
     Io(std::io::Error),
     #[error("{0}")]
     Parse(jclassfile::error::Error),
+    #[error("{0}")]
+    Archive(zip::result::ZipError),
+    #[error("the classfile is over the limit of {0} bytes (see --max-classfile-bytes)")]
+    #[from(skip)]
+    TooLarge(u64),
 }
 
-/// Helper to load a single classfile.
-fn load_classfile(path: &Path) -> Result<ClassFile, LoadClassError> {
+/// Helper to load a single classfile, refusing ones over `max_bytes`.
+fn load_classfile(path: &Path, max_bytes: u64) -> Result<ClassFile, LoadClassError> {
+    if fs::metadata(path)?.len() > max_bytes {
+        return Err(LoadClassError::TooLarge(max_bytes));
+    }
     let data = std::fs::read(path)?;
     Ok(class_file::parse(&data)?)
 }
 
-/// Load classfile for single file and all classfiles recursively if directory.
-/// It will only load classfiles with a .class extension. If there is a file
-/// with a .class extension which is not parseable as a classfile will issue a
-/// warning and continue. All other errors will halt.
-///
-/// Note that this will skip loading the classfiles for anonymous classes. Such
-/// as those generated by lambdas. (These are the classfiles whose names end with
-/// $ and some number).
-fn load_classfiles(
-    store: &mut BTreeMap<String, ClassFile>,
-    include_path: &Path,
-) -> anyhow::Result<()> {
+/// Name a classfile is loaded under given its file stem, with `$` replaced by
+/// `.` for inner classes. Returns `None` for anonymous classes (stems ending
+/// in `$` and some number), which aren't loaded.
+fn class_name_from_stem(filestem: &str) -> Option<String> {
+    if let Some((_, maybe_num)) = filestem.rsplit_once('$')
+        && maybe_num.chars().all(|ch| ch.is_numeric())
+    {
+        return None;
+    }
+    Some(filestem.replace('$', "."))
+}
+
+/// Load every classfile inside a `.jar` or `.zip` archive. Entries are
+/// treated like loose files: anonymous classes are skipped and entries which
+/// fail to parse or are over `max_bytes` are warned about.
+fn load_archive_classfiles(
+    store: &mut Vec<(String, ClassFile)>,
+    archive_path: &Path,
+    max_bytes: u64,
+) -> Result<(), LoadClassError> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(archive_path)?)?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let Some(filestem) = entry.name().strip_suffix(".class") else {
+            continue;
+        };
+        let filestem = filestem.rsplit('/').next().unwrap_or(filestem);
+        let entry_name = entry.name().to_string();
+        let Some(class_name) = class_name_from_stem(filestem) else {
+            diagnostics::info(format_args!(
+                "Ignored anonymous class `{}!{}`",
+                archive_path.display(),
+                entry_name
+            ));
+            continue;
+        };
+
+        // Read at most one byte past the limit, which is enough to tell
+        // whether the entry is over it
+        let mut data = Vec::new();
+        (&mut entry)
+            .take(max_bytes.saturating_add(1))
+            .read_to_end(&mut data)?;
+        if data.len() as u64 > max_bytes {
+            diagnostics::warn(format_args!(
+                "Skipped archive entry `{}!{}`: {}",
+                archive_path.display(),
+                entry_name,
+                LoadClassError::TooLarge(max_bytes)
+            ));
+            continue;
+        }
+        match class_file::parse(&data) {
+            Ok(classfile) if classfile_utils::is_synthetic_class(&classfile) => {
+                diagnostics::info(format_args!(
+                    "Ignored synthetic class `{}!{}`",
+                    archive_path.display(),
+                    entry_name
+                ));
+            }
+            Ok(classfile) => store.push((class_name, classfile)),
+            Err(why) => {
+                diagnostics::warn(format_args!(
+                    "Found an archive entry with extension .class but failed to parse `{}!{}`\n{}",
+                    archive_path.display(),
+                    entry_name,
+                    why
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Find the classfiles to load for a single file and recursively if
+/// directory. It will only collect files with a .class extension, or .jar and
+/// .zip archives. Errors (such as a missing include path) halt.
+fn collect_include_files(files: &mut Vec<PathBuf>, include_path: &Path) -> anyhow::Result<()> {
     if !include_path.exists() {
         return Err(anyhow!(
             "ERROR: Missing include path {}",
@@ -173,43 +520,15 @@ fn load_classfiles(
 
     if include_path.is_dir() {
         for entry in include_path.read_dir()? {
-            load_classfiles(store, &entry?.path())?;
+            collect_include_files(files, &entry?.path())?;
         }
     } else if include_path.is_file() {
         if include_path
             .extension()
-            .map(|ext| ext == "class")
+            .map(|ext| ext == "class" || ext == "jar" || ext == "zip")
             .unwrap_or(false)
         {
-            let mut filestem = include_path
-                .file_stem()
-                .expect("If we have an ext we should have a stem")
-                .to_string_lossy()
-                .to_string();
-
-            // Skip this classfile if it has an anonymous class
-            if let Some((_, maybe_num)) = filestem.rsplit_once('$') {
-                if maybe_num.chars().all(|ch| ch.is_numeric()) {
-                    return Ok(());
-                }
-                // PERF: This is wasteful. We could do better.
-                filestem = filestem.replace('$', ".");
-            }
-
-            match load_classfile(include_path) {
-                Ok(classfile) => {
-                    let old = store.insert(filestem, classfile);
-                    assert!(old.is_none(), "All the class names should be unique");
-                }
-                Err(LoadClassError::Parse(why)) => {
-                    eprintln!(
-                        "WARN: Found an include file with extension .class but failed to parse `{}`\n{}",
-                        include_path.display(),
-                        why
-                    );
-                }
-                Err(why) => return Err(why.into()),
-            }
+            files.push(include_path.to_path_buf());
         }
     } else {
         return Err(anyhow!(
@@ -221,9 +540,106 @@ fn load_classfiles(
     Ok(())
 }
 
+/// Load a classfile, or every classfile inside an archive, found by
+/// `collect_include_files`. If there is a file with a .class extension which
+/// is not parseable as a classfile will issue a warning and continue. All
+/// other errors will halt.
+///
+/// Note that this will skip loading the classfiles for anonymous classes. Such
+/// as those generated by lambdas. (These are the classfiles whose names end with
+/// $ and some number). Classes the compiler marks as synthetic are skipped too.
+fn load_include_file(
+    store: &mut Vec<(String, ClassFile)>,
+    path: &Path,
+    max_bytes: u64,
+) -> anyhow::Result<()> {
+    if path.extension().map(|ext| ext == "class").unwrap_or(false) {
+        let filestem = path
+            .file_stem()
+            .expect("If we have an ext we should have a stem")
+            .to_string_lossy();
+
+        // Skip this classfile if it has an anonymous class
+        let Some(filestem) = class_name_from_stem(&filestem) else {
+            diagnostics::info(format_args!("Ignored anonymous class `{}`", path.display()));
+            return Ok(());
+        };
+
+        match load_classfile(path, max_bytes) {
+            Ok(classfile) if classfile_utils::is_synthetic_class(&classfile) => {
+                diagnostics::info(format_args!("Ignored synthetic class `{}`", path.display()));
+            }
+            Ok(classfile) => store.push((filestem, classfile)),
+            Err(LoadClassError::Parse(why)) => {
+                diagnostics::warn(format_args!(
+                    "Found an include file with extension .class but failed to parse `{}`\n{}",
+                    path.display(),
+                    why
+                ));
+            }
+            Err(why @ LoadClassError::TooLarge(_)) => {
+                diagnostics::warn(format_args!("Skipped `{}`: {}", path.display(), why));
+            }
+            Err(why) => return Err(why.into()),
+        }
+    } else {
+        load_archive_classfiles(store, path, max_bytes)
+            .map_err(|why| anyhow!("Failed to read archive {}: {}", path.display(), why))?;
+    }
+
+    Ok(())
+}
+
+/// Load every file found by `collect_include_files`, on `jobs` threads when
+/// there is more than one. Classfiles are returned in the order of `files`
+/// whatever the number of threads, so duplicate class names are resolved (or
+/// reported) the same way. The first error in that order halts loading.
+fn load_include_files(
+    files: &[PathBuf],
+    jobs: usize,
+    max_bytes: u64,
+) -> anyhow::Result<Vec<(String, ClassFile)>> {
+    let load_all = |files: &[PathBuf]| {
+        let mut store = Vec::new();
+        for path in files {
+            load_include_file(&mut store, path, max_bytes)?;
+        }
+        anyhow::Ok(store)
+    };
+
+    if jobs <= 1 || files.len() <= 1 {
+        return load_all(files);
+    }
+
+    // Each thread loads a contiguous chunk so the results can be joined in order
+    let chunk_size = files.len().div_ceil(jobs);
+    let chunks: Vec<anyhow::Result<Vec<(String, ClassFile)>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || load_all(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("Classfile loading thread panicked"))
+            .collect()
+    });
+
+    let mut store = Vec::new();
+    for chunk in chunks {
+        store.extend(chunk?);
+    }
+    Ok(store)
+}
+
+/// Far larger than any classfile a compiler writes
+const DEFAULT_MAX_CLASSFILE_BYTES: u64 = 16 * 1024 * 1024;
+
 const FAILED_TO_LOAD_CLASSFILES: i32 = 1;
 const FAILED_TO_LOAD_DIAGRAM: i32 = 2;
 const FAILED_TO_WRITE_OUTPUT: i32 = 3;
+const UNKNOWN_CLASS: i32 = 4;
+const FAILED_TO_LOAD_RELATIONS: i32 = 5;
+const UNKNOWN_PROFILE: i32 = 6;
 
 #[derive(thiserror::Error, derive_more::From, Debug)]
 enum LoadMermaidError {
@@ -233,439 +649,527 @@ enum LoadMermaidError {
     Parse(mermaid_parser::parserv2::MermaidParseError),
 }
 
-/// Find the common base package among all classes
-/// Returns the common prefix package path (e.g., "com/example")
-fn find_common_base_package(packages: &[&str]) -> String {
-    if packages.is_empty() {
-        return String::new();
-    }
-
-    // Split all packages into components
-    let split_packages: Vec<Vec<&str>> = packages.iter().map(|p| p.split('/').collect()).collect();
-
-    if split_packages.is_empty() {
-        return String::new();
-    }
-
-    // Find common prefix
-    let mut common = Vec::new();
-    let first = &split_packages[0];
-
-    for (i, component) in first.iter().enumerate() {
-        if split_packages.iter().all(|p| p.get(i) == Some(component)) {
-            common.push(*component);
-        } else {
-            break;
-        }
-    }
-
-    common.join("/")
+/// Whether a path has a `.gz` extension
+fn is_gzip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
 }
 
-/// Convert a full package name to a relative namespace
-/// e.g., base="com/example", full="com/example/subpackage" -> "subpackage"
-fn get_relative_namespace(base: &str, full: &str) -> String {
-    if base.is_empty() {
-        return full.replace('/', ".");
+/// Read a seed diagram, decompressing it first if it is gzipped (by its
+/// `.gz` extension or the gzip magic bytes)
+fn read_diagram(path: &Path) -> anyhow::Result<String> {
+    let bytes = fs::read(path)?;
+    if is_gzip_path(path) || bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut content)
+            .map_err(|why| anyhow!("Failed to decompress `{}`: {}", path.display(), why))?;
+        Ok(content)
+    } else {
+        Ok(String::from_utf8(bytes)
+            .map_err(|why| anyhow!("`{}` is not valid UTF-8: {}", path.display(), why))?)
     }
+}
 
-    if full == base {
-        return mermaid_parser::types::DEFAULT_NAMESPACE.to_string();
+/// Write `output_text` to `path`, gzipping it when `path` ends in `.gz`
+fn write_text(path: &Path, output_text: String) -> std::io::Result<()> {
+    if is_gzip_path(path) {
+        let mut encoder =
+            flate2::write::GzEncoder::new(fs::File::create(path)?, flate2::Compression::default());
+        encoder.write_all(output_text.as_bytes())?;
+        encoder.finish()?;
+        Ok(())
+    } else {
+        fs::write(path, output_text)
     }
+}
 
-    if full.starts_with(base) {
-        let relative = &full[base.len()..];
-        let relative = relative.trim_start_matches('/');
-        if relative.is_empty() {
-            mermaid_parser::types::DEFAULT_NAMESPACE.to_string()
+/// Write `output_text` to the path given by `--output`. A directory gets a
+/// file named by `--name`, otherwise after the input diagram, while `--name`
+/// is warned about and ignored for any other path. Exits rather than
+/// overwriting an existing file or writing into a missing directory. A path
+/// ending in `.gz` is gzipped.
+fn write_output(args: &Args, output_text: String) -> PathBuf {
+    // Determine output file path based on whether output is a file or directory
+    let output_path = if args.output.exists() {
+        if args.output.is_dir() {
+            // Output path exists and is a directory - use the given name, or
+            // the input diagram's, or a default filename
+            if let Some(name) = &args.name {
+                let mut output_filename = PathBuf::from(name);
+                if output_filename.extension().is_none() {
+                    output_filename.set_extension(args.format.extension());
+                }
+                args.output.join(output_filename)
+            } else {
+                let default_name = || std::ffi::OsStr::new("output.mmd");
+                let output_filename = args
+                    .diagram
+                    .first()
+                    .map(|path| path.file_name().unwrap_or_else(default_name));
+                let mut output_path = args
+                    .output
+                    .join(output_filename.unwrap_or_else(default_name));
+                // Other formats don't keep the input diagram's extension, nor
+                // its compression
+                if args.format != OutputFormat::Mermaid {
+                    if is_gzip_path(&output_path) {
+                        output_path.set_extension("");
+                    }
+                    output_path.set_extension(args.format.extension());
+                }
+                output_path
+            }
         } else {
-            relative.replace('/', ".")
+            // Output path exists and is a file - abort to avoid overwriting
+            diagnostics::error(format_args!(
+                "Output path {} already exists as a file. Refusing to overwrite.",
+                args.output.display()
+            ));
+            std::process::exit(FAILED_TO_WRITE_OUTPUT);
         }
     } else {
-        full.replace('/', ".")
-    }
-}
+        // Output path doesn't exist, so it names the file itself
+        if let Some(name) = &args.name {
+            diagnostics::warn(format_args!(
+                "Ignoring --name `{}` as --output {} isn't a directory",
+                name,
+                args.output.display()
+            ));
+        }
 
-/// Check if groupPackage is enabled in the YAML frontmatter
-fn should_group_by_package(diagram: &Diagram) -> bool {
-    if let Some(yaml) = &diagram.yaml {
-        if let Some(umlink) = yaml.get("umlink") {
-            if let Some(group_package) = umlink.get("groupPackage") {
-                return group_package.as_bool().unwrap_or(false);
+        // Check if parent directory exists
+        if let Some(parent) = args.output.parent() {
+            // Check if parent is empty (e.g., just a filename like "sample.mmd")
+            if parent.as_os_str().is_empty() {
+                // No parent directory specified - use current directory
+                args.output.clone()
+            } else if parent.exists() && parent.is_dir() {
+                // Parent directory exists - use the given path as the output filename
+                args.output.clone()
+            } else {
+                // Parent directory doesn't exist
+                diagnostics::error(format_args!(
+                    "Parent directory {} does not exist",
+                    parent.display()
+                ));
+                std::process::exit(FAILED_TO_WRITE_OUTPUT);
             }
+        } else {
+            // No parent (shouldn't normally happen, but handle it)
+            args.output.clone()
         }
+    };
+
+    // Write to file
+    if let Err(why) = write_text(&output_path, output_text) {
+        diagnostics::error(format_args!(
+            "Failed to write output file {}: {}",
+            output_path.display(),
+            why
+        ));
+        std::process::exit(FAILED_TO_WRITE_OUTPUT);
     }
-    false
-}
 
-/// Check if a classfile should be included based on the select filters in the YAML frontmatter
-/// Returns true if the classfile should be included, false otherwise.
-///
-/// Behavior:
-/// - If no "select" directive is present, include all classfiles (return true)
-/// - If "select" is present but has no filters, include no classfiles (return false)
-/// - If "select" has filters, include classfile if it matches ANY filter (return true)
-fn should_include_classfile(diagram: &Diagram, classfile: &ClassFile) -> bool {
-    let Some(yaml) = &diagram.yaml else {
-        return true; // No YAML, include all
-    };
+    output_path
+}
 
-    let Some(umlink) = yaml.get("umlink") else {
-        return true; // No umlink section, include all
+/// Populate the seed diagram from the classfiles and apply the flags which
+/// reshape the result: `--skeleton` and `--root`
+fn link_diagram<'a>(
+    args: &Args,
+    merged_config: &MergedConfig,
+    classfiles: &'a BTreeMap<String, ClassFile>,
+    diagram: Diagram<'a>,
+    manual_relations: Vec<String>,
+) -> LinkedDiagram<'a> {
+    let options = LinkOptions {
+        class_paths: args
+            .classfiles
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect(),
+        skeleton: args.skeleton,
+        roots: args.roots.clone(),
+        roots_direction: args.roots_direction,
     };
+    umlink::link_classfiles(
+        classfiles,
+        diagram,
+        manual_relations,
+        merged_config,
+        &options,
+    )
+}
 
-    let Some(select) = umlink.get("select") else {
-        return true; // No select directive, include all
-    };
+/// How long classfiles have to stay unchanged before the diagram is
+/// regenerated, so a build rewriting many of them only regenerates it once
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Check if a filesystem event changed a classfile or archive
+fn is_classfile_event(event: &notify::Event) -> bool {
+    !matches!(event.kind, EventKind::Access(_))
+        && event.paths.iter().any(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "class" || ext == "jar" || ext == "zip")
+        })
+}
 
-    // select directive is present
-    let Some(filters) = select.as_sequence() else {
-        // select is present but not a sequence (invalid format), include nothing
-        return false;
-    };
+/// Current time of day (UTC) as `HH:MM:SS`
+fn clock_time() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+        % 86400;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
 
-    // If filters array is empty, include nothing
-    if filters.is_empty() {
-        return false;
+/// Reload the classfiles and write the diagram linked from them to
+/// `output_path`, overwriting the previous one. Returns how many classes it
+/// has.
+fn regenerate(
+    args: &Args,
+    merged_config: &MergedConfig,
+    seed: &Diagram,
+    manual_relations: &[String],
+    jobs: usize,
+    output_path: &Path,
+) -> anyhow::Result<usize> {
+    let mut include_files = Vec::new();
+    for include_path in &args.classfiles {
+        collect_include_files(&mut include_files, include_path)?;
     }
+    let loaded_classfiles = load_include_files(&include_files, jobs, args.max_classfile_bytes)?;
+    let classfiles = umlink::index_classfiles(loaded_classfiles, umlink::get_disambiguate(seed))?;
+
+    let linked = link_diagram(
+        args,
+        merged_config,
+        &classfiles,
+        seed.clone(),
+        manual_relations.to_vec(),
+    );
+    let output_text = normalize_output(
+        &args
+            .format
+            .serialize(&linked.diagram, &linked.serialize_options),
+    );
+    write_text(output_path, output_text)?;
+    Ok(linked.classfiles.len())
+}
 
-    // Get the package name of this classfile
-    let package = if let Some(full_name) = get_full_class_name(classfile) {
-        get_package_name(&full_name).replace('/', ".")
-    } else {
-        String::new() // Default package
+/// Regenerate the diagram at `output_path` each time classfiles under the
+/// `--classfiles` paths change, until the process is stopped. A failed
+/// regeneration is reported and leaves the previous diagram in place.
+fn watch(
+    args: &Args,
+    merged_config: &MergedConfig,
+    seed: &Diagram,
+    manual_relations: &[String],
+    jobs: usize,
+    output_path: &Path,
+) {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(sender) {
+        Ok(watcher) => watcher,
+        Err(why) => {
+            diagnostics::error(format_args!("Failed to watch for changes: {}", why));
+            std::process::exit(FAILED_TO_LOAD_CLASSFILES);
+        }
     };
-
-    // Check if any filter matches
-    for filter in filters {
-        let Some(filter_map) = filter.as_mapping() else {
-            continue;
-        };
-
-        let Some(field) = filter_map.get("field") else {
-            continue;
-        };
-
-        let Some(field_str) = field.as_str() else {
-            continue;
-        };
-
-        if field_str != "package" {
-            continue; // Only "package" field is supported for now
+    for path in &args.classfiles {
+        if let Err(why) = watcher.watch(path, RecursiveMode::Recursive) {
+            diagnostics::error(format_args!("Failed to watch {}: {}", path.display(), why));
+            std::process::exit(FAILED_TO_LOAD_CLASSFILES);
         }
+    }
+    diagnostics::status("Watching for classfile changes, press Ctrl-C to stop");
 
-        let Some(pattern) = filter_map.get("pattern") else {
-            continue;
-        };
+    for event in &receiver {
+        match event {
+            Ok(event) if is_classfile_event(&event) => {}
+            Ok(_) => continue,
+            Err(why) => {
+                diagnostics::warn(format_args!("Error while watching for changes: {}", why));
+                continue;
+            }
+        }
 
-        let Some(pattern_str) = pattern.as_str() else {
-            continue;
-        };
+        // Let the rest of a burst of changes (e.g. a rebuild) arrive first
+        while receiver.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
 
-        // Match the package against the pattern
-        if package == pattern_str {
-            return true; // Found a matching filter
+        match regenerate(
+            args,
+            merged_config,
+            seed,
+            manual_relations,
+            jobs,
+            output_path,
+        ) {
+            Ok(classes) => diagnostics::status(format_args!(
+                "[{}] Regenerated {} with {} classes",
+                clock_time(),
+                output_path.display(),
+                classes
+            )),
+            Err(why) => diagnostics::error(format_args!(
+                "[{}] Failed to regenerate {}: {}",
+                clock_time(),
+                output_path.display(),
+                why
+            )),
         }
     }
-
-    // No filters matched
-    false
 }
 
 fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    diagnostics::set_color(args.color);
+    diagnostics::set_verbose(args.verbose);
+    diagnostics::set_quiet(args.quiet);
+
+    // Classpath entries are loaded the same way as `--classfiles` paths
+    if let Some(classpath) = args.classpath.take() {
+        args.classfiles.extend(
+            std::env::split_paths(&classpath).filter(|entry| !entry.as_os_str().is_empty()),
+        );
+    }
 
-    // Load configuration file and merge with CLI arguments
-    let config = Config::load(args.config.as_deref()).unwrap_or_default();
+    // Load configuration file, apply environment overrides, then merge with CLI arguments
+    let config = match Config::load(args.config.as_deref(), !args.no_config_search)
+        .unwrap_or_default()
+        .select_profile(args.profile.as_deref())
+    {
+        Ok(config) => config.overlay(Config::from_env()),
+        Err(why) => {
+            diagnostics::error(why);
+            std::process::exit(UNKNOWN_PROFILE);
+        }
+    };
     let merged_config = config.merge_with_args(&args);
 
     // Load all relevant classfiles and diagrams. We halt if there is an error.
-    let mut classfiles = BTreeMap::<String, ClassFile>::new();
+    let mut include_files = Vec::new();
     for include_path in &args.classfiles {
-        if let Err(why) = load_classfiles(&mut classfiles, include_path) {
-            eprintln!("ERROR: {}", why);
+        if let Err(why) = collect_include_files(&mut include_files, include_path) {
+            diagnostics::error(why);
             std::process::exit(FAILED_TO_LOAD_CLASSFILES);
         }
     }
 
-    let diagram_source = if let Some(diagram_path) = &args.diagram {
-        match fs::read_to_string(&diagram_path) {
-            Ok(content) => content,
-            Err(why) => {
-                eprintln!("ERROR: {}", why);
-                std::process::exit(FAILED_TO_LOAD_DIAGRAM);
-            }
-        }
-    } else {
-        String::new()
+    let jobs = match args.jobs {
+        0 => std::thread::available_parallelism().map_or(1, usize::from),
+        jobs => jobs,
     };
-
-    let mut diagram = if !diagram_source.is_empty() {
-        match mermaid_parser::parserv2::parse_mermaid(&diagram_source) {
-            Ok(diagram) => diagram.1,
-            Err(why) => {
-                eprintln!("ERROR: {}", why);
-                std::process::exit(FAILED_TO_LOAD_DIAGRAM);
-            }
+    let loaded_classfiles = match load_include_files(&include_files, jobs, args.max_classfile_bytes)
+    {
+        Ok(loaded_classfiles) => loaded_classfiles,
+        Err(why) => {
+            diagnostics::error(why);
+            std::process::exit(FAILED_TO_LOAD_CLASSFILES);
         }
-    } else {
-        Diagram::default()
     };
 
-    let skip_annotation = merged_config.skip.as_deref();
-    let aggregate_annotation = merged_config.aggregate.as_deref();
-    let compose_annotation = merged_config.compose.as_deref();
-    let link_annotation = merged_config.link.as_deref();
-    let navigate_annotation = merged_config.navigate.as_deref();
-
-    // Determine if we should group by package
-    let group_by_package = should_group_by_package(&diagram);
-
-    // If grouping by package, find the common base package
-    let base_package = if group_by_package {
-        let full_names: Vec<String> = classfiles
-            .values()
-            .filter_map(|classfile| get_full_class_name(classfile))
-            .collect();
-
-        let packages: Vec<&str> = full_names
+    // Draw the modules described by any module-info classfiles instead of classes
+    if args.module_graph {
+        if args.format != OutputFormat::Mermaid {
+            diagnostics::warn("Module graphs are always written as Mermaid");
+        }
+        let modules: Vec<_> = loaded_classfiles
             .iter()
-            .map(|full_name| get_package_name(full_name))
-            .filter(|pkg| !pkg.is_empty())
+            .filter_map(|(_, classfile)| module_graph::read_module_info(classfile))
             .collect();
-
-        find_common_base_package(&packages)
-    } else {
-        String::new()
-    };
-
-    // Clear existing classes from namespaces (keep only relations and YAML)
-    // We'll repopulate with full class details from classfiles
-    diagram.namespaces.clear();
-
-    // Process all classfiles and add them to the diagram unless they have the skip annotation
-    for (class_name, classfile) in &classfiles {
-        // Skip annotation type definitions
-        if is_annotation(classfile) {
-            continue;
-        }
-
-        // Check if this classfile should be included based on select filters
-        if !should_include_classfile(&diagram, classfile) {
-            continue;
+        if modules.is_empty() {
+            diagnostics::warn("No module-info.class was found in the given classfiles");
         }
-
-        // Check if the class itself has the skip annotation
-        if classfile_utils::has_annotation(
-            classfile.constant_pool(),
-            classfile.attributes(),
-            skip_annotation,
-        ) {
-            continue; // Skip this entire class
+        if args.check {
+            diagnostics::status(format_args!("Checked {} modules", modules.len()));
+            return;
         }
 
-        // Convert classfile to Mermaid class
-        let relationship_annotations = [
-            aggregate_annotation,
-            compose_annotation,
-            link_annotation,
-            navigate_annotation,
-        ];
-        let mermaid_class = classfile_to_mermaid_class(
-            classfile,
-            class_name,
-            skip_annotation,
-            &relationship_annotations,
-        );
+        let output_text = normalize_output(&module_graph::serialize_module_graph(&modules));
+        let output_path = write_output(&args, output_text);
+        diagnostics::status(format_args!(
+            "Successfully wrote module graph to {}",
+            output_path.display()
+        ));
+        return;
+    }
 
-        // Determine the namespace for this class
-        let namespace_name = if group_by_package {
-            if let Some(full_class_name) = get_full_class_name(classfile) {
-                let package = get_package_name(&full_class_name);
-                get_relative_namespace(&base_package, package)
-            } else {
-                mermaid_parser::types::DEFAULT_NAMESPACE.to_string()
+    // Hand authored relations are kept aside and written back verbatim
+    let mut diagram_sources = Vec::new();
+    let mut manual_relations = Vec::new();
+    for diagram_path in &args.diagram {
+        let diagram_source = match read_diagram(diagram_path) {
+            Ok(content) => content,
+            Err(why) => {
+                diagnostics::error(why);
+                std::process::exit(FAILED_TO_LOAD_DIAGRAM);
             }
-        } else {
-            mermaid_parser::types::DEFAULT_NAMESPACE.to_string()
         };
+        let (diagram_source, manual) = manual_relations::split_manual_relations(&diagram_source);
+        diagram_sources.push(diagram_source);
+        manual_relations.extend(manual);
+    }
 
-        // Add the class to the appropriate namespace
-        let namespace = diagram.namespaces.entry(namespace_name.into()).or_default();
-
-        namespace
-            .classes
-            .insert(class_name.clone().into(), mermaid_class);
-
-        // Process fields to find relationship annotations
-        let constant_pool = classfile.constant_pool();
-        for field in classfile.fields() {
-            let field_descriptor =
-                classfile_utils::get_utf8(constant_pool, field.descriptor_index()).unwrap_or("");
-
-            // Extract the target class from the field descriptor (if it's an object type)
-            if let Some(target_class) = extract_class_name_from_descriptor(field_descriptor) {
-                // Check for each relationship annotation type
-                let annotations = [
-                    (aggregate_annotation, RelationKind::Aggregation),
-                    (compose_annotation, RelationKind::Composition),
-                    (link_annotation, RelationKind::Association),
-                    (navigate_annotation, RelationKind::Association),
-                ];
-
-                for (annotation_name, relation_kind) in &annotations {
-                    if let Some((self_card, label, other_card)) =
-                        classfile_utils::get_annotation_params(
-                            constant_pool,
-                            field.attributes(),
-                            *annotation_name,
-                        )
-                    {
-                        // Create a relationship from the current class to the field's type
-                        let relation = mermaid_parser::types::Relation {
-                            tail: class_name.clone().into(),
-                            head: target_class.clone().into(),
-                            kind: *relation_kind,
-                            cardinality_tail: if self_card.is_empty() {
-                                None
-                            } else {
-                                Some(self_card.into())
-                            },
-                            cardinality_head: if other_card.is_empty() {
-                                None
-                            } else {
-                                Some(other_card.into())
-                            },
-                            label: if label.is_empty() {
-                                None
-                            } else {
-                                Some(label.into())
-                            },
-                        };
-                        diagram.relations.push(relation);
-                        break; // Only create one relation per field (first matching annotation)
-                    }
+    let diagrams = diagram_sources
+        .iter()
+        .filter(|diagram_source| !diagram_source.is_empty())
+        .map(
+            |diagram_source| match mermaid_parser::parserv2::parse_mermaid(diagram_source) {
+                Ok(diagram) => diagram.1.into(),
+                Err(why) => {
+                    diagnostics::error(why);
+                    std::process::exit(FAILED_TO_LOAD_DIAGRAM);
                 }
-            }
-        }
-
-        // Add inheritance relationship if the class extends another class
-        if let Some(superclass) = get_superclass_name(classfile) {
-            let relation = mermaid_parser::types::Relation {
-                tail: class_name.clone().into(),
-                head: superclass.into(),
-                kind: RelationKind::Inheritance,
-                cardinality_tail: None,
-                cardinality_head: None,
-                label: None,
-            };
-            diagram.relations.push(relation);
-        }
+            },
+        );
+    let mut diagram = umlink::merge_diagrams(diagrams);
+    umlink::apply_config_options(&mut diagram, &merged_config);
 
-        // Add realization relationships for implemented interfaces
-        for interface in get_interface_names(classfile) {
-            let relation = mermaid_parser::types::Relation {
-                tail: class_name.clone().into(),
-                head: interface.into(),
-                kind: RelationKind::Realization,
-                cardinality_tail: None,
-                cardinality_head: None,
-                label: None,
-            };
-            diagram.relations.push(relation);
-        }
+    if args.overwrite_relations {
+        diagram.relations.clear();
     }
 
-    // Serialize the diagram to Mermaid text
-    let output_text = serialize_diagram(&diagram);
+    // Key the classfiles by class name, which must be unique unless the diagram
+    // asks for classes sharing a name to be told apart
+    let classfiles =
+        match umlink::index_classfiles(loaded_classfiles, umlink::get_disambiguate(&diagram)) {
+            Ok(classfiles) => classfiles,
+            Err(why) => {
+                diagnostics::error(why);
+                std::process::exit(FAILED_TO_LOAD_CLASSFILES);
+            }
+        };
 
-    // Determine output file path based on whether output is a file or directory
-    let output_path = if args.output.exists() {
-        if args.output.is_dir() {
-            // Output path exists and is a directory - use default filename
-            let default_name = || std::ffi::OsStr::new("output.mmd");
-            let output_filename = args
-                .diagram
-                .as_ref()
-                .map(|path| path.file_name().unwrap_or_else(default_name));
-            args.output
-                .join(output_filename.unwrap_or_else(default_name))
-        } else {
-            // Output path exists and is a file - abort to avoid overwriting
-            eprintln!(
-                "ERROR: Output path {} already exists as a file. Refusing to overwrite.",
-                args.output.display()
-            );
-            std::process::exit(FAILED_TO_WRITE_OUTPUT);
-        }
-    } else {
-        // Output path doesn't exist - check if parent directory exists
-        if let Some(parent) = args.output.parent() {
-            // Check if parent is empty (e.g., just a filename like "sample.mmd")
-            if parent.as_os_str().is_empty() {
-                // No parent directory specified - use current directory
-                args.output.clone()
-            } else if parent.exists() && parent.is_dir() {
-                // Parent directory exists - use the given path as the output filename
-                args.output.clone()
-            } else {
-                // Parent directory doesn't exist
-                eprintln!(
-                    "ERROR: Parent directory {} does not exist",
-                    parent.display()
-                );
-                std::process::exit(FAILED_TO_WRITE_OUTPUT);
+    // Validate the relations file before its relations join the diagram's own
+    if let Some(relations_path) = &args.relations_file {
+        match relations_file::load_relations(relations_path) {
+            Ok(relations) => relations_file::merge_relations(&mut diagram, relations, &classfiles),
+            Err(why) => {
+                diagnostics::error(format_args!(
+                    "Invalid relations file {}: {}",
+                    relations_path.display(),
+                    why
+                ));
+                std::process::exit(FAILED_TO_LOAD_RELATIONS);
             }
-        } else {
-            // No parent (shouldn't normally happen, but handle it)
-            args.output.clone()
         }
-    };
-
-    // Write to file
-    if let Err(why) = fs::write(&output_path, output_text) {
-        eprintln!(
-            "ERROR: Failed to write output file {}: {}",
-            output_path.display(),
-            why
-        );
-        std::process::exit(FAILED_TO_WRITE_OUTPUT);
     }
 
-    println!(
-        "Successfully wrote linked diagram to {}",
-        output_path.display()
+    // Watching regenerates from the same seed diagram every time
+    let seed = args.watch.then(|| diagram.clone());
+
+    // Add the loaded classes and their relations to the diagram
+    let LinkedDiagram {
+        diagram,
+        serialize_options,
+        excluded_classes,
+        skip_annotations,
+        relationship_annotations,
+        show_constructors,
+        ..
+    } = link_diagram(
+        &args,
+        &merged_config,
+        &classfiles,
+        diagram,
+        manual_relations.clone(),
     );
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::find_common_base_package;
+    // Report on a single class instead of writing the diagram
+    if let Some(explain_name) = &args.explain {
+        let context = explain::ExplainContext {
+            skip_annotations: &skip_annotations,
+            relationship_annotations: &relationship_annotations,
+            show_constructors,
+            visibility: merged_config.visibility,
+            serialize_options: &serialize_options,
+        };
+        let exclusion = excluded_classes.get(explain_name.as_str()).copied();
+        match explain::explain_class(explain_name, &classfiles, &diagram, exclusion, &context) {
+            Some(report) => print!("{}", report),
+            None => {
+                diagnostics::error(format_args!(
+                    "No classfile was loaded for `{}`",
+                    explain_name
+                ));
+                std::process::exit(UNKNOWN_CLASS);
+            }
+        }
+        return;
+    }
 
-    #[test]
-    fn test_find_common_base_package() {
-        let prefix = find_common_base_package(&[
-            "com/MainActivity",
-            "com/example/example/Helper",
-            "com/example/Second",
-        ]);
+    // Stop short of writing anything when only checking the inputs, counting
+    // what would have been written
+    if args.check {
+        let classes: usize = diagram
+            .namespaces
+            .values()
+            .map(|namespace| namespace.classes.len())
+            .sum();
+        diagnostics::status(format_args!(
+            "Checked {} classes and {} relations, {} classes skipped",
+            classes,
+            diagram.relations.len() + serialize_options.manual_relations.len(),
+            excluded_classes.len()
+        ));
+        return;
+    }
 
-        assert_eq!("com", prefix);
+    // Serialize the diagram in the requested format
+    let output_text = normalize_output(&args.format.serialize(&diagram, &serialize_options));
 
-        let prefix = find_common_base_package(&[
-            "com/example/example/Helper",
-            "com/example/Second",
-            "com/example/Third",
-        ]);
+    let output_path = write_output(&args, output_text);
 
-        assert_eq!("com/example", prefix);
+    diagnostics::status(format_args!(
+        "Successfully wrote linked diagram to {}",
+        output_path.display()
+    ));
+
+    if let Some(stats_path) = &args.stats_json {
+        let stats = stats::collect_stats(&diagram, &classfiles);
+        let result = serde_json::to_string_pretty(&stats)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(fs::write(stats_path, json)?));
+        if let Err(why) = result {
+            diagnostics::error(format_args!(
+                "Failed to write stats file {}: {}",
+                stats_path.display(),
+                why
+            ));
+            std::process::exit(FAILED_TO_WRITE_OUTPUT);
+        }
+    }
 
-        let prefix = find_common_base_package(&[
-            "other/example/example/Helper",
-            "com/example/Second",
-            "com/example/Third",
-        ]);
+    if let Some(report_path) = &args.report {
+        let report =
+            report::collect_report(&diagram, &classfiles, &serialize_options.manual_relations);
+        let result = serde_json::to_string_pretty(&report)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(fs::write(report_path, json)?));
+        if let Err(why) = result {
+            diagnostics::error(format_args!(
+                "Failed to write report {}: {}",
+                report_path.display(),
+                why
+            ));
+            std::process::exit(FAILED_TO_WRITE_OUTPUT);
+        }
+    }
 
-        assert_eq!("", prefix);
+    if let Some(seed) = seed {
+        watch(
+            &args,
+            &merged_config,
+            &seed,
+            &manual_relations,
+            jobs,
+            &output_path,
+        );
     }
 }
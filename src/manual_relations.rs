@@ -0,0 +1,93 @@
+//! Hand authored relations pinned with a trailing `%% @manual` comment
+//!
+//! Marked relations are taken out of the source before it's parsed and
+//! written back verbatim (with their marker), so they survive regeneration
+//! even when the rest of the input's relations are replaced:
+//!
+//! ```text
+//! Shop ..> User : notifies %% @manual
+//! ```
+
+use crate::diagram::{Diagram, Relation};
+use mermaid_parser::types::RelationKind;
+use std::borrow::Cow;
+
+/// Comment marking a relation as hand authored
+pub const MANUAL_MARKER: &str = "%% @manual";
+
+/// Split marked relations out of a Mermaid source. Returns the source without
+/// the marked lines and each marked relation's text without its marker.
+pub fn split_manual_relations(source: &str) -> (String, Vec<String>) {
+    let mut remaining = String::with_capacity(source.len());
+    let mut manual = Vec::new();
+    for line in source.split_inclusive('\n') {
+        match line.trim_end().strip_suffix(MANUAL_MARKER) {
+            Some(relation) if !relation.trim().is_empty() => {
+                manual.push(relation.trim().to_string());
+            }
+            _ => remaining.push_str(line),
+        }
+    }
+    (remaining, manual)
+}
+
+/// Parse the text of a marked relation (without its marker). Gives `None`
+/// for lines that aren't a relation Mermaid understands.
+pub fn parse_manual_relation(line: &str) -> Option<Relation<'static>> {
+    let source = format!("classDiagram\n{}\n", line);
+    let (_, diagram) = mermaid_parser::parserv2::parse_mermaid(&source).ok()?;
+    let relation = diagram.relations.into_iter().next()?;
+    let owned = |text: Cow<str>| Cow::Owned(text.into_owned());
+    Some(Relation {
+        tail: owned(relation.tail),
+        head: owned(relation.head),
+        kind: relation.kind,
+        cardinality_tail: relation.cardinality_tail.map(owned),
+        cardinality_head: relation.cardinality_head.map(owned),
+        label: relation.label.map(owned),
+        arrow: None,
+    })
+}
+
+/// Classes and kind of each marked relation, used to compare them with
+/// generated relations. Lines that aren't a relation Mermaid understands are
+/// left out.
+pub fn pinned_relations(manual: &[String]) -> Vec<(String, String, RelationKind)> {
+    manual
+        .iter()
+        .filter_map(|line| parse_manual_relation(line))
+        .map(|relation| {
+            (
+                relation.tail.into_owned(),
+                relation.head.into_owned(),
+                relation.kind,
+            )
+        })
+        .collect()
+}
+
+/// Drop relations which a marked relation already draws between the same
+/// classes with the same kind, so the hand authored version is the only one
+pub fn remove_pinned_relations(diagram: &mut Diagram, pinned: &[(String, String, RelationKind)]) {
+    diagram.relations.retain(|relation| {
+        !pinned.iter().any(|(tail, head, kind)| {
+            relation.tail == tail.as_str()
+                && relation.head == head.as_str()
+                && relation.kind == *kind
+        })
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_manual_relations() {
+        let (remaining, manual) = split_manual_relations(
+            "classDiagram\nShop ..> User : notifies %% @manual\nShop --> Order\n%% @manual\n",
+        );
+        assert_eq!(remaining, "classDiagram\nShop --> Order\n%% @manual\n");
+        assert_eq!(manual, vec!["Shop ..> User : notifies".to_string()]);
+    }
+}
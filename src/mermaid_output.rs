@@ -0,0 +1,1089 @@
+//! Serialization of a diagram into Mermaid `classDiagram` text
+//!
+//! This is the only Mermaid serializer umlink uses. It produces the same
+//! format as `mermaid_parser::serializer`, but lets umlink control rendering
+//! details which the parser crate doesn't expose, and reads whatever
+//! `mermaid_parser::parserv2` parses back into the same diagram.
+//!
+//! Relations are always `diagram::Relation`: directed from their `tail` to
+//! their `head`, with a `RelationKind`, optional cardinalities on either end,
+//! an optional label and the arrow they're drawn with when it isn't their
+//! kind's. The PlantUML and DOT serializers read the same shape.
+
+use crate::diagram::{Class, Diagram, Member, Namespace, Relation};
+use crate::graph::has_class;
+use crate::manual_relations::MANUAL_MARKER;
+use mermaid_parser::types::{DEFAULT_NAMESPACE, RelationKind, TypeNotation, Visibility};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Order in which namespaces are emitted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NamespaceOrder {
+    /// Lexicographic by namespace name
+    #[default]
+    Name,
+    /// Shallower packages first, then lexicographic by name
+    Depth,
+}
+
+/// Separator written between the names of an outer class and its inner
+/// classes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InnerSeparator {
+    /// `Outer.Inner`
+    #[default]
+    Dot,
+    /// `Outer_Inner`
+    Underscore,
+    /// `Outer$Inner`, as in the classfile's name
+    Dollar,
+}
+
+/// Options controlling how a diagram is rendered
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions {
+    /// Render every class without members as `class Foo`, including those
+    /// whose members were all filtered out
+    pub compact_empty_classes: bool,
+    /// Classes which had members that were all filtered out. Unless
+    /// `compact_empty_classes` is set these keep an empty `{ }` body as a hint
+    /// that members are hidden.
+    pub filtered_classes: BTreeSet<String>,
+    /// Emit relations clustered by their tail class (separated by blank
+    /// lines and sorted by head then kind) instead of one flat list
+    pub group_relations_by_source: bool,
+    pub namespace_order: NamespaceOrder,
+    /// How inner class names are written, in both class declarations and
+    /// relation ends. Class names in the diagram always use `.`.
+    pub inner_separator: InnerSeparator,
+    /// Declare supertypes before their subtypes (within each namespace) and
+    /// emit relations in the same order, which guides Mermaid's layout to
+    /// keep inheritance edges pointing the same way
+    pub layout_hints: bool,
+    /// Mark relation heads which aren't classes in the diagram (library types
+    /// such as `List` or `Activity`) with an `<<external>>` stereotype
+    pub mark_external: bool,
+    /// Declare a bare `class Foo` for every relation end which isn't a class
+    /// in the diagram, so it renders as a labelled node that can be styled
+    pub stub_referenced_types: bool,
+    /// Label relations which don't have a label with a description of their
+    /// kind (e.g. `«extends»`), so the diagram reads without knowing UML arrows
+    pub synthetic_relation_labels: bool,
+    /// Serialized methods each class inherits, grouped by the superclass they
+    /// come from. These are listed after the class's own members under an
+    /// `«inherited from Base»` divider.
+    pub inherited_members: BTreeMap<String, Vec<(String, Vec<String>)>>,
+    /// Source locations of members keyed by class, as pairs of a member (e.g.
+    /// `area()`) and its target (e.g. `com/example/Circle.java#L6`). Mermaid
+    /// only links whole classes, so these are written as `%% link` comments
+    /// for renderers and tools which support member links.
+    pub member_links: BTreeMap<String, Vec<(String, String)>>,
+    /// URLs of the source files classes are declared in, keyed by class and
+    /// written as a `click Foo href "url"` directive so the class links there
+    pub source_links: BTreeMap<String, String>,
+    /// Approximate complexity of classes (see `complexity::class_complexity`),
+    /// written as a `complexity: 42` note on each class in the diagram
+    pub complexity_badges: BTreeMap<String, usize>,
+    /// Text of classes' note annotations, written as a note on each class in
+    /// the diagram
+    pub notes: BTreeMap<String, String>,
+    /// Hand authored relations written verbatim before the generated ones,
+    /// each followed by the `%% @manual` marker (see `manual_relations`)
+    pub manual_relations: Vec<String>,
+    /// Fully qualified names of the classes loaded from classfiles (e.g.
+    /// `com.example.util.Helper`), keyed by class. Classes are emitted in
+    /// the order of these within each namespace, so they don't move around
+    /// as the names of other classes change.
+    pub full_names: BTreeMap<String, String>,
+    /// Numbers of attributes and methods left out of classes by `maxMembers`,
+    /// each summarized by a `... K more` line at the end of its section
+    pub truncated_members: BTreeMap<String, (usize, usize)>,
+}
+
+/// Serialize a whole diagram, including the YAML frontmatter
+pub fn serialize_diagram(diagram: &Diagram, options: &SerializeOptions) -> String {
+    let mut out = String::new();
+
+    if let Some(yaml) = &diagram.yaml {
+        out.push_str("---\n");
+        out.push_str(&serde_yml::to_string(yaml).unwrap_or_default());
+        out.push_str("---\n");
+    }
+
+    out.push_str("classDiagram\n");
+
+    let mut namespaces: Vec<_> = diagram.namespaces.iter().collect();
+    if options.namespace_order == NamespaceOrder::Depth {
+        // Stable sort, so namespaces of equal depth keep their name order
+        namespaces.sort_by_key(|(namespace_name, _)| namespace_depth(namespace_name));
+    }
+
+    let depths = options.layout_hints.then(|| hierarchy_depths(diagram));
+
+    for (namespace_name, namespace) in namespaces {
+        let is_default = namespace_name == DEFAULT_NAMESPACE;
+        if !is_default {
+            out.push_str(&format!("namespace {} {{\n", namespace_name));
+        }
+
+        let mut classes = ordered_classes(namespace, options);
+        if let Some(depths) = &depths {
+            classes.sort_by_key(|class| hierarchy_depth(depths, &class.name));
+        }
+        for class in classes {
+            serialize_class(&mut out, class, options);
+        }
+
+        if !is_default {
+            out.push_str("}\n");
+        }
+    }
+
+    if options.stub_referenced_types {
+        for name in undefined_endpoints(diagram) {
+            out.push_str(&format!("class {}\n", mermaid_node_name(name, options)));
+        }
+    }
+
+    if options.mark_external {
+        for name in external_classes(diagram) {
+            out.push_str(&format!(
+                "<<external>> {}\n",
+                mermaid_node_name(name, options)
+            ));
+        }
+    }
+
+    for (class_name, links) in &options.member_links {
+        for (member, target) in links {
+            out.push_str(&format!(
+                "%% link {}.{} \"{}\"\n",
+                node_name(class_name, options),
+                member,
+                target
+            ));
+        }
+    }
+
+    for (class_name, url) in &options.source_links {
+        if has_class(diagram, class_name) {
+            out.push_str(&format!(
+                "click {} href \"{}\"\n",
+                mermaid_node_name(class_name, options),
+                url
+            ));
+        }
+    }
+
+    for (class_name, complexity) in &options.complexity_badges {
+        if has_class(diagram, class_name) {
+            out.push_str(&format!(
+                "note for {} \"complexity: {}\"\n",
+                mermaid_node_name(class_name, options),
+                complexity
+            ));
+        }
+    }
+
+    for (class_name, text) in &options.notes {
+        if has_class(diagram, class_name) {
+            out.push_str(&format!(
+                "note for {} \"{}\"\n",
+                mermaid_node_name(class_name, options),
+                escape_note(text)
+            ));
+        }
+    }
+
+    for relation in &options.manual_relations {
+        out.push_str(&format!("{} {}\n", relation, MANUAL_MARKER));
+    }
+
+    if options.group_relations_by_source {
+        serialize_grouped_relations(&mut out, &diagram.relations, options);
+    } else {
+        let mut relations: Vec<&Relation> = diagram.relations.iter().collect();
+        if let Some(depths) = &depths {
+            relations.sort_by_key(|relation| hierarchy_depth(depths, &relation.head));
+        }
+        for relation in relations {
+            serialize_relation(&mut out, relation, options);
+        }
+    }
+
+    out
+}
+
+/// Name of a class as it's written out, with the chosen separator between
+/// outer and inner class names
+pub fn node_name<'a>(name: &'a str, options: &SerializeOptions) -> Cow<'a, str> {
+    match options.inner_separator {
+        InnerSeparator::Dot => Cow::Borrowed(name),
+        InnerSeparator::Underscore => Cow::Owned(name.replace('.', "_")),
+        InnerSeparator::Dollar => Cow::Owned(name.replace('.', "$")),
+    }
+}
+
+/// Write a name so Mermaid reads it as a single class name. Names with
+/// anything besides letters, digits, `_` and `-` (e.g. the `.` of inner
+/// classes, or a `$`) are wrapped in backticks.
+pub fn mermaid_safe_name(name: &str) -> Cow<'_, str> {
+    if !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    {
+        Cow::Borrowed(name)
+    } else {
+        Cow::Owned(format!("`{}`", name))
+    }
+}
+
+/// Name of a class as it's written in Mermaid: the `node_name`, escaped by
+/// `mermaid_safe_name` where needed
+fn mermaid_node_name(name: &str, options: &SerializeOptions) -> String {
+    mermaid_safe_name(&node_name(name, options)).into_owned()
+}
+
+/// Normalize serialized text before it's written out: no UTF-8 byte order
+/// mark and exactly one trailing newline
+pub fn normalize_output(text: &str) -> String {
+    let mut text = text
+        .trim_start_matches('\u{feff}')
+        .trim_end_matches(['\n', '\r'])
+        .to_string();
+    text.push('\n');
+    text
+}
+
+/// Names of the classes defined in any namespace of the diagram
+fn defined_classes<'a>(diagram: &'a Diagram) -> BTreeSet<&'a str> {
+    diagram
+        .namespaces
+        .values()
+        .flat_map(|namespace| namespace.classes.keys())
+        .map(|name| name.as_ref())
+        .collect()
+}
+
+/// Names of relation heads which aren't defined as classes in the diagram
+pub fn external_classes<'a>(diagram: &'a Diagram) -> BTreeSet<&'a str> {
+    let defined = defined_classes(diagram);
+    diagram
+        .relations
+        .iter()
+        .map(|relation| relation.head.as_ref())
+        .filter(|head| !defined.contains(head))
+        .collect()
+}
+
+/// Names of relation tails and heads which aren't defined as classes in the
+/// diagram
+pub fn undefined_endpoints<'a>(diagram: &'a Diagram) -> BTreeSet<&'a str> {
+    let defined = defined_classes(diagram);
+    diagram
+        .relations
+        .iter()
+        .flat_map(|relation| [relation.tail.as_ref(), relation.head.as_ref()])
+        .filter(|name| !defined.contains(name))
+        .collect()
+}
+
+/// The fully qualified name of a class when it's known, otherwise its name
+pub fn full_name<'a>(name: &'a str, options: &'a SerializeOptions) -> &'a str {
+    options.full_names.get(name).map_or(name, String::as_str)
+}
+
+/// Classes of a namespace ordered by their `full_name`
+pub fn ordered_classes<'a>(
+    namespace: &'a Namespace,
+    options: &SerializeOptions,
+) -> Vec<&'a Class<'a>> {
+    let mut classes: Vec<&Class> = namespace.classes.values().collect();
+    classes.sort_by(|a, b| full_name(&a.name, options).cmp(full_name(&b.name, options)));
+    classes
+}
+
+/// Number of package segments in a namespace name. The default namespace has
+/// depth zero.
+pub fn namespace_depth(namespace_name: &str) -> usize {
+    if namespace_name == DEFAULT_NAMESPACE {
+        0
+    } else {
+        namespace_name.split('.').count()
+    }
+}
+
+/// Depth of every class in the inheritance hierarchy, where classes without a
+/// supertype have depth zero and a subtype is deeper than all its supertypes.
+/// Classes in a cycle are left out.
+fn hierarchy_depths<'a>(diagram: &'a Diagram) -> BTreeMap<&'a str, usize> {
+    let edges: Vec<(&str, &str)> = diagram
+        .relations
+        .iter()
+        .filter(|relation| {
+            matches!(
+                relation.kind,
+                RelationKind::Inheritance | RelationKind::Realization
+            ) && relation.tail != relation.head
+        })
+        .map(|relation| (relation.head.as_ref(), relation.tail.as_ref()))
+        .collect();
+
+    // Count the supertypes of every class, visiting classes once all their
+    // supertypes have been visited
+    let mut supertype_count: BTreeMap<&str, usize> = BTreeMap::new();
+    for namespace in diagram.namespaces.values() {
+        for name in namespace.classes.keys() {
+            supertype_count.entry(name).or_default();
+        }
+    }
+    for (supertype, subtype) in &edges {
+        supertype_count.entry(supertype).or_default();
+        *supertype_count.entry(subtype).or_default() += 1;
+    }
+
+    let mut depths: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut queue: VecDeque<&str> = supertype_count
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(name, _)| *name)
+        .collect();
+    while let Some(current) = queue.pop_front() {
+        let depth = *depths.entry(current).or_default();
+        for (_, subtype) in edges.iter().filter(|(supertype, _)| *supertype == current) {
+            let subtype_depth = depths.entry(subtype).or_default();
+            *subtype_depth = (*subtype_depth).max(depth + 1);
+
+            let count = supertype_count.entry(subtype).or_default();
+            *count -= 1;
+            if *count == 0 {
+                queue.push_back(subtype);
+            }
+        }
+    }
+
+    // Classes in a cycle may have been given a depth by their non-cyclic
+    // supertypes, remove them so they fall back to name order
+    depths.retain(|name, _| supertype_count.get(name) == Some(&0));
+    depths
+}
+
+/// Sort key for a class given its hierarchy depths. Classes in a cycle sort
+/// after every other class.
+fn hierarchy_depth(depths: &BTreeMap<&str, usize>, name: &str) -> usize {
+    depths.get(name).copied().unwrap_or(usize::MAX)
+}
+
+/// Serialize relations grouped by their tail class, with a blank line between
+/// groups. Within a group relations are ordered by head and then kind.
+fn serialize_grouped_relations(
+    out: &mut String,
+    relations: &[Relation],
+    options: &SerializeOptions,
+) {
+    let mut groups: BTreeMap<&str, Vec<&Relation>> = BTreeMap::new();
+    for relation in relations {
+        groups.entry(&relation.tail).or_default().push(relation);
+    }
+
+    for (index, group) in groups.values_mut().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+
+        group.sort_by(|a, b| {
+            a.head
+                .cmp(&b.head)
+                .then_with(|| relation_kind_rank(a.kind).cmp(&relation_kind_rank(b.kind)))
+        });
+        for relation in group.iter() {
+            serialize_relation(out, relation, options);
+        }
+    }
+}
+
+/// Stable ordering of relation kinds, used when sorting relations
+pub fn relation_kind_rank(kind: RelationKind) -> u8 {
+    match kind {
+        RelationKind::Inheritance => 0,
+        RelationKind::Realization => 1,
+        RelationKind::Composition => 2,
+        RelationKind::Aggregation => 3,
+        RelationKind::Association => 4,
+        RelationKind::Dependency => 5,
+    }
+}
+
+/// Serialize a class with its members followed by its stereotypes (if any)
+pub fn serialize_class(out: &mut String, class: &Class, options: &SerializeOptions) {
+    let keep_body =
+        !options.compact_empty_classes && options.filtered_classes.contains(class.name.as_ref());
+    let inherited = options
+        .inherited_members
+        .get(class.name.as_ref())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+
+    let (hidden_attributes, hidden_methods) = hidden_member_counts(class, options);
+    let truncated = hidden_attributes > 0 || hidden_methods > 0;
+
+    if class.members.is_empty() && inherited.is_empty() && !keep_body && !truncated {
+        out.push_str(&format!(
+            "class {}\n",
+            mermaid_node_name(&class.name, options)
+        ));
+    } else {
+        out.push_str(&format!(
+            "class {} {{\n",
+            mermaid_node_name(&class.name, options)
+        ));
+        // Mermaid reads lines ending in `)` as methods, so the attribute
+        // summary is written without parentheses
+        let attributes_summary = format!("  ... {} more\n", hidden_attributes);
+        let mut summarized_attributes = hidden_attributes == 0;
+        for member in &class.members {
+            if !summarized_attributes && matches!(member, Member::Method(_)) {
+                out.push_str(&attributes_summary);
+                summarized_attributes = true;
+            }
+            out.push_str("  ");
+            out.push_str(&serialize_member(member));
+            out.push('\n');
+        }
+        if !summarized_attributes {
+            out.push_str(&attributes_summary);
+        }
+        if hidden_methods > 0 {
+            out.push_str(&format!("  ... ({} more)\n", hidden_methods));
+        }
+        for (superclass, members) in inherited {
+            out.push_str(&format!(
+                "  «inherited from {}»\n",
+                node_name(superclass, options)
+            ));
+            for member in members {
+                out.push_str(&format!("  {}\n", member));
+            }
+        }
+        out.push_str("}\n");
+    }
+
+    if let Some(stereotypes) = class.stereotype_text() {
+        out.push_str(&format!(
+            "<<{}>> {}\n",
+            stereotypes,
+            mermaid_node_name(&class.name, options)
+        ));
+    }
+}
+
+/// Numbers of attributes and methods `maxMembers` left out of a class
+pub fn hidden_member_counts(class: &Class, options: &SerializeOptions) -> (usize, usize) {
+    options
+        .truncated_members
+        .get(class.name.as_ref())
+        .copied()
+        .unwrap_or_default()
+}
+
+/// Mermaid visibility prefix
+pub fn visibility_symbol(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "+",
+        Visibility::Private => "-",
+        Visibility::Protected => "#",
+        Visibility::Package => "~",
+        Visibility::Unspecified => "",
+    }
+}
+
+/// Write a type in Mermaid's notation, where generic type arguments are
+/// enclosed in `~` rather than `<>` (e.g. `List<Item>` as `List~Item~`). Types
+/// are kept in Java's notation until they're serialized.
+pub fn escape_type(data_type: &str) -> String {
+    data_type.replace(['<', '>'], "~")
+}
+
+/// Escape the text of a note for its quoted string: quotes and backslashes
+/// become entity codes, so only line breaks are written as `\n`
+fn escape_note(text: &str) -> String {
+    text.replace('"', "#quot;")
+        .replace('\\', "#92;")
+        .replace("\r\n", "\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+/// Render a name with an optional type in the given notation
+fn typed_name(name: &str, data_type: Option<&str>, notation: TypeNotation) -> String {
+    match (data_type, notation) {
+        (Some(data_type), TypeNotation::Postfix) => {
+            format!("{}: {}", name, escape_type(data_type))
+        }
+        (Some(data_type), TypeNotation::Prefix) => format!("{} {}", escape_type(data_type), name),
+        _ => name.to_string(),
+    }
+}
+
+/// Text written after a member's type: the value of a constant or the
+/// default of an annotation element, the exceptions a method throws and the
+/// `«default»` and `(deprecated)` markers
+pub fn member_modifiers(member: &Member) -> String {
+    let mut modifiers = String::new();
+    match member {
+        Member::Attribute(attribute) => {
+            if let Some(value) = &attribute.value {
+                modifiers.push_str(&format!(" = {}", value));
+            }
+            if attribute.is_deprecated {
+                modifiers.push_str(" (deprecated)");
+            }
+        }
+        Member::Method(method) => {
+            if let Some(default_value) = &method.default_value {
+                modifiers.push_str(&format!(" = {}", default_value));
+            }
+            if !method.throws.is_empty() {
+                modifiers.push_str(&format!(" throws {}", method.throws.join(", ")));
+            }
+            if method.is_default {
+                modifiers.push_str(" «default»");
+            }
+            if method.is_deprecated {
+                modifiers.push_str(" (deprecated)");
+            }
+        }
+    }
+    modifiers
+}
+
+/// Serialize a single attribute or method line (without indentation)
+pub fn serialize_member(member: &Member) -> String {
+    match member {
+        Member::Attribute(attribute) => {
+            let mut line = visibility_symbol(attribute.visibility).to_string();
+            line.push_str(&typed_name(
+                &attribute.name,
+                attribute.data_type.as_deref(),
+                attribute.type_notation,
+            ));
+            line.push_str(&member_modifiers(member));
+            if attribute.is_static {
+                line.push('$');
+            }
+            line
+        }
+        Member::Method(method) => {
+            let parameters: Vec<String> = method
+                .parameters
+                .iter()
+                .map(|parameter| {
+                    typed_name(
+                        &parameter.name,
+                        parameter.data_type.as_deref(),
+                        parameter.type_notation,
+                    )
+                })
+                .collect();
+
+            let mut line = visibility_symbol(method.visibility).to_string();
+            if let (Some(return_type), TypeNotation::Prefix) =
+                (&method.return_type, method.return_type_notation)
+            {
+                line.push_str(&format!("{} ", escape_type(return_type)));
+            }
+            line.push_str(&format!("{}({})", method.name, parameters.join(", ")));
+            if method.is_abstract {
+                line.push('*');
+            }
+            if method.is_static {
+                line.push('$');
+            }
+            if let (Some(return_type), TypeNotation::Postfix) =
+                (&method.return_type, method.return_type_notation)
+            {
+                line.push_str(&format!(" {}", escape_type(return_type)));
+            }
+            line.push_str(&member_modifiers(member));
+            line
+        }
+    }
+}
+
+/// Mermaid arrow for a relation drawn from its tail to its head
+pub fn relation_arrow(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Inheritance => "--|>",
+        RelationKind::Realization => "..|>",
+        RelationKind::Composition => "--*",
+        RelationKind::Aggregation => "--o",
+        RelationKind::Association => "-->",
+        RelationKind::Dependency => "..>",
+    }
+}
+
+/// Label describing a relation kind in words
+pub fn synthetic_relation_label(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Inheritance => "«extends»",
+        RelationKind::Realization => "«implements»",
+        RelationKind::Composition => "«has»",
+        RelationKind::Aggregation => "«contains»",
+        RelationKind::Association => "«references»",
+        RelationKind::Dependency => "«depends on»",
+    }
+}
+
+/// Check a Mermaid arrow token of the form `[marker]line[marker]`, where the
+/// line is `--` (solid) or `..` (dashed), and return the standard relation kind
+/// it most closely resembles
+pub fn parse_arrow_token(token: &str) -> Option<RelationKind> {
+    let (line_start, line) = token
+        .find("--")
+        .map(|index| (index, "--"))
+        .or_else(|| token.find("..").map(|index| (index, "..")))?;
+    let tail_marker = &token[..line_start];
+    let head_marker = &token[line_start + line.len()..];
+
+    if !["", "<|", "<", "*", "o"].contains(&tail_marker)
+        || !["", "|>", ">", "*", "o"].contains(&head_marker)
+    {
+        return None;
+    }
+
+    let dashed = line == "..";
+    Some(match head_marker {
+        "|>" if dashed => RelationKind::Realization,
+        "|>" => RelationKind::Inheritance,
+        "*" => RelationKind::Composition,
+        "o" => RelationKind::Aggregation,
+        _ if dashed => RelationKind::Dependency,
+        _ => RelationKind::Association,
+    })
+}
+
+/// Serialize a relation with its cardinalities and label
+pub fn serialize_relation(out: &mut String, relation: &Relation, options: &SerializeOptions) {
+    let arrow = relation
+        .arrow
+        .as_deref()
+        .unwrap_or_else(|| relation_arrow(relation.kind));
+
+    out.push_str(&mermaid_node_name(&relation.tail, options));
+    if let Some(cardinality) = &relation.cardinality_tail {
+        out.push_str(&format!(" \"{}\"", cardinality));
+    }
+    out.push_str(&format!(" {} ", arrow));
+    if let Some(cardinality) = &relation.cardinality_head {
+        out.push_str(&format!("\"{}\" ", cardinality));
+    }
+    out.push_str(&mermaid_node_name(&relation.head, options));
+    if let Some(label) = &relation.label {
+        out.push_str(&format!(" : {}", label));
+    } else if options.synthetic_relation_labels {
+        out.push_str(&format!(" : {}", synthetic_relation_label(relation.kind)));
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::{Attribute, Method};
+    use mermaid_parser::types::Parameter;
+
+    fn class(name: &str, members: Vec<Member<'static>>) -> Class<'static> {
+        Class {
+            name: name.to_string().into(),
+            annotation: None,
+            stereotypes: Vec::new(),
+            members,
+        }
+    }
+
+    fn field(name: &str) -> Member<'static> {
+        Member::Attribute(Attribute {
+            visibility: Visibility::Private,
+            name: name.to_string().into(),
+            data_type: Some("int".into()),
+            is_static: false,
+            type_notation: TypeNotation::Postfix,
+            value: None,
+            is_deprecated: false,
+        })
+    }
+
+    #[test]
+    fn test_empty_class_is_compact() {
+        let mut out = String::new();
+        serialize_class(
+            &mut out,
+            &class("Empty", vec![]),
+            &SerializeOptions::default(),
+        );
+        assert_eq!(out, "class Empty\n");
+
+        let mut out = String::new();
+        serialize_class(
+            &mut out,
+            &class("Full", vec![field("count")]),
+            &SerializeOptions::default(),
+        );
+        assert_eq!(out, "class Full {\n  -count: int\n}\n");
+    }
+
+    #[test]
+    fn test_mermaid_safe_name() {
+        assert_eq!(mermaid_safe_name("Shape"), "Shape");
+        assert_eq!(mermaid_safe_name("Config_ui"), "Config_ui");
+        assert_eq!(mermaid_safe_name("Café"), "Café");
+        assert_eq!(mermaid_safe_name("Tree.Node"), "`Tree.Node`");
+        assert_eq!(mermaid_safe_name("Tree$Node"), "`Tree$Node`");
+        assert_eq!(mermaid_safe_name("My Class"), "`My Class`");
+
+        let relation = relation("Tree", "Tree.Node", RelationKind::Composition);
+        let mut out = String::new();
+        serialize_relation(&mut out, &relation, &SerializeOptions::default());
+        assert_eq!(out, "Tree --* `Tree.Node`\n");
+    }
+
+    #[test]
+    fn test_truncated_members() {
+        let method = Member::Method(Method {
+            visibility: Visibility::Public,
+            name: "reset".into(),
+            parameters: Vec::new(),
+            return_type: None,
+            is_static: false,
+            is_abstract: false,
+            return_type_notation: TypeNotation::Postfix,
+            default_value: None,
+            throws: Vec::new(),
+            is_default: false,
+            is_deprecated: false,
+        });
+        let options = SerializeOptions {
+            truncated_members: BTreeMap::from([("Big".to_string(), (3, 5))]),
+            ..Default::default()
+        };
+
+        let mut out = String::new();
+        serialize_class(
+            &mut out,
+            &class("Big", vec![field("a"), field("b"), method]),
+            &options,
+        );
+        assert_eq!(
+            out,
+            "class Big {\n  -a: int\n  -b: int\n  ... 3 more\n  +reset()\n  ... (5 more)\n}\n"
+        );
+
+        // A class with every member left out still lists the summaries
+        let mut out = String::new();
+        serialize_class(&mut out, &class("Big", vec![]), &options);
+        assert_eq!(out, "class Big {\n  ... 3 more\n  ... (5 more)\n}\n");
+    }
+
+    fn relation(tail: &str, head: &str, kind: RelationKind) -> Relation<'static> {
+        Relation {
+            tail: tail.to_string().into(),
+            head: head.to_string().into(),
+            kind,
+            cardinality_tail: None,
+            cardinality_head: None,
+            label: None,
+            arrow: None,
+        }
+    }
+
+    #[test]
+    fn test_group_relations_by_source() {
+        let diagram = Diagram {
+            relations: vec![
+                relation("B", "Z", RelationKind::Association),
+                relation("A", "Y", RelationKind::Dependency),
+                relation("B", "X", RelationKind::Inheritance),
+                relation("A", "Y", RelationKind::Inheritance),
+                relation("A", "X", RelationKind::Association),
+            ],
+            ..Default::default()
+        };
+
+        let options = SerializeOptions {
+            group_relations_by_source: true,
+            ..Default::default()
+        };
+        let out = serialize_diagram(&diagram, &options);
+        assert_eq!(
+            out,
+            "classDiagram\n\
+             A --> X\n\
+             A --|> Y\n\
+             A ..> Y\n\
+             \n\
+             B --|> X\n\
+             B --> Z\n"
+        );
+    }
+
+    #[test]
+    fn test_namespace_order_by_depth() {
+        let mut diagram = Diagram::default();
+        for name in ["com.example.ui", "com", "com.example", "org"] {
+            let mut namespace = Namespace::default();
+            namespace.classes.insert(
+                format!("{}Class", name.replace('.', "")).into(),
+                class(&format!("{}Class", name.replace('.', "")), vec![]),
+            );
+            diagram
+                .namespaces
+                .insert(name.to_string().into(), namespace);
+        }
+
+        let namespace_lines = |options: &SerializeOptions| -> Vec<String> {
+            serialize_diagram(&diagram, options)
+                .lines()
+                .filter(|line| line.starts_with("namespace "))
+                .map(str::to_string)
+                .collect()
+        };
+
+        assert_eq!(
+            namespace_lines(&SerializeOptions::default()),
+            [
+                "namespace com {",
+                "namespace com.example {",
+                "namespace com.example.ui {",
+                "namespace org {",
+            ]
+        );
+
+        let options = SerializeOptions {
+            namespace_order: NamespaceOrder::Depth,
+            ..Default::default()
+        };
+        assert_eq!(
+            namespace_lines(&options),
+            [
+                "namespace com {",
+                "namespace org {",
+                "namespace com.example {",
+                "namespace com.example.ui {",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_arrow_token() {
+        assert_eq!(parse_arrow_token("..>"), Some(RelationKind::Dependency));
+        assert_eq!(parse_arrow_token("--"), Some(RelationKind::Association));
+        assert_eq!(parse_arrow_token("<..>"), Some(RelationKind::Dependency));
+        assert_eq!(parse_arrow_token("..|>"), Some(RelationKind::Realization));
+        assert_eq!(parse_arrow_token("*--o"), Some(RelationKind::Aggregation));
+        assert_eq!(parse_arrow_token("==>"), None);
+        assert_eq!(parse_arrow_token("-->>"), None);
+    }
+
+    #[test]
+    fn test_custom_arrow() {
+        let options = SerializeOptions::default();
+        let custom = Relation {
+            arrow: Some("<..>".into()),
+            ..relation("A", "B", RelationKind::Dependency)
+        };
+
+        let mut out = String::new();
+        serialize_relation(&mut out, &custom, &options);
+        serialize_relation(
+            &mut out,
+            &relation("A", "B", RelationKind::Association),
+            &options,
+        );
+        assert_eq!(out, "A <..> B\nA --> B\n");
+    }
+
+    #[test]
+    fn test_synthetic_relation_labels() {
+        let options = SerializeOptions {
+            synthetic_relation_labels: true,
+            ..Default::default()
+        };
+
+        let mut out = String::new();
+        serialize_relation(
+            &mut out,
+            &relation("Cat", "Animal", RelationKind::Inheritance),
+            &options,
+        );
+        let mut composition = relation("Car", "Engine", RelationKind::Composition);
+        composition.cardinality_head = Some("1".into());
+        serialize_relation(&mut out, &composition, &options);
+        serialize_relation(
+            &mut out,
+            &relation("Driver", "Car", RelationKind::Association),
+            &options,
+        );
+        let mut labelled = relation("Car", "Wheel", RelationKind::Aggregation);
+        labelled.label = Some("wheels".into());
+        serialize_relation(&mut out, &labelled, &options);
+
+        assert_eq!(
+            out,
+            "Cat --|> Animal : «extends»\n\
+             Car --* \"1\" Engine : «has»\n\
+             Driver --> Car : «references»\n\
+             Car --o Wheel : wheels\n"
+        );
+    }
+
+    #[test]
+    fn test_layout_hints_declare_supertypes_first() {
+        let mut namespace = Namespace::default();
+        for name in ["Animal", "Cat", "Lion", "Pet"] {
+            namespace
+                .classes
+                .insert(name.to_string().into(), class(name, vec![]));
+        }
+        let mut diagram = Diagram {
+            relations: vec![
+                relation("Lion", "Cat", RelationKind::Inheritance),
+                relation("Cat", "Pet", RelationKind::Realization),
+                relation("Cat", "Animal", RelationKind::Inheritance),
+            ],
+            ..Default::default()
+        };
+        diagram
+            .namespaces
+            .insert(DEFAULT_NAMESPACE.into(), namespace);
+
+        let options = SerializeOptions {
+            layout_hints: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            serialize_diagram(&diagram, &options),
+            "classDiagram\n\
+             class Animal\n\
+             class Pet\n\
+             class Cat\n\
+             class Lion\n\
+             Cat ..|> Pet\n\
+             Cat --|> Animal\n\
+             Lion --|> Cat\n"
+        );
+    }
+
+    #[test]
+    fn test_layout_hints_cycle_falls_back_to_name_order() {
+        let mut namespace = Namespace::default();
+        for name in ["A", "B", "C"] {
+            namespace
+                .classes
+                .insert(name.to_string().into(), class(name, vec![]));
+        }
+        let mut diagram = Diagram {
+            relations: vec![
+                relation("A", "B", RelationKind::Inheritance),
+                relation("B", "A", RelationKind::Inheritance),
+            ],
+            ..Default::default()
+        };
+        diagram
+            .namespaces
+            .insert(DEFAULT_NAMESPACE.into(), namespace);
+
+        let options = SerializeOptions {
+            layout_hints: true,
+            ..Default::default()
+        };
+        let out = serialize_diagram(&diagram, &options);
+        assert!(out.starts_with("classDiagram\nclass C\nclass A\nclass B\n"));
+    }
+
+    #[test]
+    fn test_escape_generic_types() {
+        assert_eq!(
+            escape_type("Map<String, List<Item>>"),
+            "Map~String, List~Item~~"
+        );
+        assert_eq!(escape_type("int[]"), "int[]");
+
+        let member = Member::Method(Method {
+            visibility: Visibility::Public,
+            name: "items".into(),
+            parameters: vec![Parameter {
+                name: "filter".into(),
+                data_type: Some("Predicate<+Item>".into()),
+                type_notation: TypeNotation::Postfix,
+            }],
+            return_type: Some("List<Item>[]".into()),
+            is_static: false,
+            is_abstract: false,
+            return_type_notation: TypeNotation::Postfix,
+            default_value: None,
+            throws: Vec::new(),
+            is_default: false,
+            is_deprecated: false,
+        });
+        assert_eq!(
+            serialize_member(&member),
+            "+items(filter: Predicate~+Item~) List~Item~[]"
+        );
+    }
+
+    #[test]
+    fn test_escape_note() {
+        assert_eq!(
+            escape_note("Handles \"auth\"\r\nin C:\\auth\nand more"),
+            "Handles #quot;auth#quot;\\nin C:#92;auth\\nand more"
+        );
+    }
+
+    #[test]
+    fn test_normalize_output() {
+        assert_eq!(normalize_output("classDiagram"), "classDiagram\n");
+        assert_eq!(normalize_output("classDiagram\n"), "classDiagram\n");
+        assert_eq!(normalize_output("classDiagram\n\n\r\n"), "classDiagram\n");
+        assert_eq!(normalize_output("\u{feff}classDiagram\n"), "classDiagram\n");
+    }
+
+    #[test]
+    fn test_filtered_class_keeps_body_unless_compact() {
+        let mut options = SerializeOptions::default();
+        options.filtered_classes.insert("Hidden".to_string());
+
+        let mut out = String::new();
+        serialize_class(&mut out, &class("Hidden", vec![]), &options);
+        assert_eq!(out, "class Hidden {\n}\n");
+
+        options.compact_empty_classes = true;
+        let mut out = String::new();
+        serialize_class(&mut out, &class("Hidden", vec![]), &options);
+        assert_eq!(out, "class Hidden\n");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source = "classDiagram\n\
+                      class Car {\n  -speed: int\n  +drive(distance: int) void\n}\n\
+                      class Engine {\n}\n\
+                      Car \"1\" --* \"1\" Engine : powers\n\
+                      Car --> \"0..*\" Wheel\n\
+                      Truck --|> Car\n";
+        let diagram = Diagram::from(mermaid_parser::parserv2::parse_mermaid(source).unwrap().1);
+        let options = SerializeOptions::default();
+        let serialized = serialize_diagram(&diagram, &options);
+
+        // Parsing the output gives back the same relations, and so the same text
+        let reparsed = Diagram::from(
+            mermaid_parser::parserv2::parse_mermaid(&serialized)
+                .unwrap()
+                .1,
+        );
+        assert_eq!(reparsed.relations, diagram.relations);
+        assert_eq!(serialize_diagram(&reparsed, &options), serialized);
+        assert!(serialized.contains("Car \"1\" --* \"1\" Engine : powers\n"));
+        assert!(serialized.contains("Car --> \"0..*\" Wheel\n"));
+    }
+}
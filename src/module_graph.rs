@@ -0,0 +1,158 @@
+//! Diagram of Java modules and their dependencies read from `module-info.class`
+
+use crate::classfile_utils::get_utf8;
+use crate::diagram::{Class, Diagram, Relation};
+use crate::mermaid_output::{SerializeOptions, serialize_diagram};
+use jclassfile::{attributes::Attribute, class_file::ClassFile, constant_pool::ConstantPool};
+use mermaid_parser::types::{DEFAULT_NAMESPACE, RelationKind};
+
+/// `requires` flag of the implicit dependency every module has on `java.base`
+const ACC_MANDATED: u16 = 0x8000;
+
+/// What a module declares in its `module-info.java`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModuleInfo {
+    /// Dotted module name (e.g. "com.example.app")
+    pub name: String,
+    /// Modules this module requires, leaving out the implicit `java.base`
+    pub requires: Vec<String>,
+    /// Dotted names of the packages this module exports
+    pub exports: Vec<String>,
+    /// Dotted names of the packages this module opens for reflection
+    pub opens: Vec<String>,
+}
+
+/// Resolve a `CONSTANT_Module` or `CONSTANT_Package` entry to its dotted name
+fn get_module_or_package_name(constant_pool: &[ConstantPool], index: u16) -> Option<String> {
+    let name_index = match constant_pool.get(index as usize)? {
+        ConstantPool::Module { name_index } | ConstantPool::Package { name_index } => *name_index,
+        _ => return None,
+    };
+    get_utf8(constant_pool, name_index).map(|name| name.replace('/', "."))
+}
+
+/// Read the `Module` attribute of a `module-info.class`. Returns `None` for
+/// any other classfile.
+pub fn read_module_info(class_file: &ClassFile) -> Option<ModuleInfo> {
+    let constant_pool = class_file.constant_pool();
+    class_file.attributes().iter().find_map(|attr| match attr {
+        Attribute::Module {
+            module_name_index,
+            requires,
+            exports,
+            opens,
+            ..
+        } => Some(ModuleInfo {
+            name: get_module_or_package_name(constant_pool, *module_name_index)?,
+            requires: requires
+                .iter()
+                .filter(|record| record.requires_flags() & ACC_MANDATED == 0)
+                .filter_map(|record| {
+                    get_module_or_package_name(constant_pool, record.requires_index())
+                })
+                .filter(|name| name != "java.base")
+                .collect(),
+            exports: exports
+                .iter()
+                .filter_map(|record| {
+                    get_module_or_package_name(constant_pool, record.exports_index())
+                })
+                .collect(),
+            opens: opens
+                .iter()
+                .filter_map(|record| {
+                    get_module_or_package_name(constant_pool, record.opens_index())
+                })
+                .collect(),
+        }),
+        _ => None,
+    })
+}
+
+/// Mermaid safe class name for a module, as dots separate namespaces
+fn module_node_name(module_name: &str) -> String {
+    module_name.replace('.', "_")
+}
+
+/// Serialize a diagram with a `<<module>>` node for each module, a dependency
+/// arrow for each `requires`, and a note listing exported and opened packages
+pub fn serialize_module_graph(modules: &[ModuleInfo]) -> String {
+    let mut diagram = Diagram::default();
+    let namespace = diagram
+        .namespaces
+        .entry(DEFAULT_NAMESPACE.into())
+        .or_default();
+    for module in modules {
+        let name = module_node_name(&module.name);
+        namespace.classes.insert(
+            name.clone().into(),
+            Class {
+                name: name.into(),
+                annotation: Some("module".into()),
+                stereotypes: Vec::new(),
+                members: Vec::new(),
+            },
+        );
+    }
+
+    for module in modules {
+        for required in &module.requires {
+            diagram.relations.push(Relation {
+                tail: module_node_name(&module.name).into(),
+                head: module_node_name(required).into(),
+                kind: RelationKind::Dependency,
+                cardinality_tail: None,
+                cardinality_head: None,
+                label: Some("requires".into()),
+                arrow: None,
+            });
+        }
+    }
+
+    let mut out = serialize_diagram(&diagram, &SerializeOptions::default());
+    for module in modules {
+        let lines: Vec<String> = module
+            .exports
+            .iter()
+            .map(|package| format!("exports {}", package))
+            .chain(
+                module
+                    .opens
+                    .iter()
+                    .map(|package| format!("opens {}", package)),
+            )
+            .collect();
+        if !lines.is_empty() {
+            out.push_str(&format!(
+                "note for {} \"{}\"\n",
+                module_node_name(&module.name),
+                lines.join("\\n")
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_module_graph() {
+        let modules = [ModuleInfo {
+            name: "com.example.app".to_string(),
+            requires: vec!["java.sql".to_string()],
+            exports: vec!["com.example.app.api".to_string()],
+            opens: vec!["com.example.app.internal".to_string()],
+        }];
+
+        assert_eq!(
+            serialize_module_graph(&modules),
+            "classDiagram\n\
+             class com_example_app\n\
+             <<module>> com_example_app\n\
+             com_example_app ..> java_sql : requires\n\
+             note for com_example_app \"exports com.example.app.api\\nopens com.example.app.internal\"\n"
+        );
+    }
+}
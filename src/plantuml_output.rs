@@ -0,0 +1,370 @@
+//! Serialization of a diagram into PlantUML class diagram text
+//!
+//! Mirrors `mermaid_output` for tooling which consumes PlantUML. The same
+//! `SerializeOptions` apply where PlantUML has an equivalent, and relations
+//! use the same arrows, which PlantUML reads the same way Mermaid does.
+
+use crate::diagram::{Class, Diagram, Member, Relation};
+use crate::graph::has_class;
+use crate::mermaid_output::{
+    NamespaceOrder, SerializeOptions, external_classes, hidden_member_counts, member_modifiers,
+    namespace_depth, node_name, ordered_classes, relation_arrow, relation_kind_rank,
+    synthetic_relation_label, undefined_endpoints, visibility_symbol,
+};
+use mermaid_parser::types::DEFAULT_NAMESPACE;
+use std::collections::BTreeMap;
+
+/// Serialize a whole diagram as `@startuml ... @enduml`. Namespaces become
+/// `package` blocks. The frontmatter has no PlantUML equivalent and is left
+/// out.
+pub fn serialize_plantuml(diagram: &Diagram, options: &SerializeOptions) -> String {
+    let mut out = String::from("@startuml\n");
+
+    // Class names like `Outer.Inner` shouldn't be read as packages
+    out.push_str("set separator none\n");
+
+    let mut namespaces: Vec<_> = diagram.namespaces.iter().collect();
+    if options.namespace_order == NamespaceOrder::Depth {
+        namespaces.sort_by_key(|(namespace_name, _)| namespace_depth(namespace_name));
+    }
+
+    for (namespace_name, namespace) in namespaces {
+        let is_default = namespace_name == DEFAULT_NAMESPACE;
+        if !is_default {
+            out.push_str(&format!("package {} {{\n", namespace_name));
+        }
+        for class in ordered_classes(namespace, options) {
+            serialize_class(&mut out, class, options);
+        }
+        if !is_default {
+            out.push_str("}\n");
+        }
+    }
+
+    if options.stub_referenced_types {
+        for name in undefined_endpoints(diagram) {
+            out.push_str(&format!("class {}\n", node_name(name, options)));
+        }
+    }
+
+    if options.mark_external {
+        for name in external_classes(diagram) {
+            out.push_str(&format!(
+                "class {} <<external>>\n",
+                node_name(name, options)
+            ));
+        }
+    }
+
+    for (class_name, links) in &options.member_links {
+        for (member, target) in links {
+            out.push_str(&format!(
+                "' link {}.{} \"{}\"\n",
+                node_name(class_name, options),
+                member,
+                target
+            ));
+        }
+    }
+
+    for (class_name, url) in &options.source_links {
+        if has_class(diagram, class_name) {
+            out.push_str(&format!(
+                "url of {} is [[{}]]\n",
+                node_name(class_name, options),
+                url
+            ));
+        }
+    }
+
+    for (class_name, complexity) in &options.complexity_badges {
+        if has_class(diagram, class_name) {
+            out.push_str(&format!(
+                "note right of {} : complexity: {}\n",
+                node_name(class_name, options),
+                complexity
+            ));
+        }
+    }
+
+    for (class_name, text) in &options.notes {
+        if has_class(diagram, class_name) {
+            out.push_str(&format!(
+                "note top of {} : {}\n",
+                node_name(class_name, options),
+                escape_note(text)
+            ));
+        }
+    }
+
+    // Hand authored relations use arrows PlantUML shares with Mermaid
+    for relation in &options.manual_relations {
+        out.push_str(&format!("{}\n", relation));
+    }
+
+    if options.group_relations_by_source {
+        let mut groups: BTreeMap<&str, Vec<&Relation>> = BTreeMap::new();
+        for relation in &diagram.relations {
+            groups.entry(&relation.tail).or_default().push(relation);
+        }
+        for group in groups.values_mut() {
+            group.sort_by(|a, b| {
+                a.head
+                    .cmp(&b.head)
+                    .then_with(|| relation_kind_rank(a.kind).cmp(&relation_kind_rank(b.kind)))
+            });
+            for relation in group.iter() {
+                serialize_relation(&mut out, relation, options);
+            }
+        }
+    } else {
+        for relation in &diagram.relations {
+            serialize_relation(&mut out, relation, options);
+        }
+    }
+
+    out.push_str("@enduml\n");
+    out
+}
+
+/// Escape the text of a single line note, where PlantUML reads `\n` as a
+/// line break and `\\` as a backslash
+fn escape_note(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace("\r\n", "\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+/// PlantUML keyword declaring a class of the given kind. Kinds PlantUML draws
+/// natively (e.g. `interface`) are declared with their keyword rather than as
+/// a stereotype, which is `None` then.
+fn class_keyword(annotation: Option<&str>) -> (&'static str, Option<&str>) {
+    match annotation {
+        Some("interface") => ("interface", None),
+        Some("enumeration") => ("enum", None),
+        Some("abstract") => ("abstract class", None),
+        Some("annotation") => ("annotation", None),
+        annotation => ("class", annotation),
+    }
+}
+
+/// Serialize a class with its members, declared with the keyword for its kind
+pub fn serialize_class(out: &mut String, class: &Class, options: &SerializeOptions) {
+    let (keyword, annotation) = class_keyword(class.annotation.as_deref());
+    out.push_str(&format!("{} {}", keyword, node_name(&class.name, options)));
+    for stereotype in annotation
+        .into_iter()
+        .chain(class.stereotypes.iter().map(AsRef::as_ref))
+    {
+        out.push_str(&format!(" <<{}>>", stereotype));
+    }
+
+    let keep_body =
+        !options.compact_empty_classes && options.filtered_classes.contains(class.name.as_ref());
+    let inherited = options
+        .inherited_members
+        .get(class.name.as_ref())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let (hidden_attributes, hidden_methods) = hidden_member_counts(class, options);
+    let truncated = hidden_attributes > 0 || hidden_methods > 0;
+    if class.members.is_empty() && inherited.is_empty() && !keep_body && !truncated {
+        out.push('\n');
+        return;
+    }
+
+    out.push_str(" {\n");
+    // Lines with parentheses are read as methods, so the attribute summary is
+    // written without them
+    let attributes_summary = format!("  ... {} more\n", hidden_attributes);
+    let mut summarized_attributes = hidden_attributes == 0;
+    for member in &class.members {
+        if !summarized_attributes && matches!(member, Member::Method(_)) {
+            out.push_str(&attributes_summary);
+            summarized_attributes = true;
+        }
+        out.push_str(&format!("  {}\n", serialize_member(member)));
+    }
+    if !summarized_attributes {
+        out.push_str(&attributes_summary);
+    }
+    if hidden_methods > 0 {
+        out.push_str(&format!("  ... ({} more)\n", hidden_methods));
+    }
+    for (superclass, members) in inherited {
+        out.push_str(&format!(
+            "  -- inherited from {} --\n",
+            node_name(superclass, options)
+        ));
+        for member in members {
+            out.push_str(&format!("  {}\n", member));
+        }
+    }
+    out.push_str("}\n");
+}
+
+/// Render a name with an optional type as `name : Type`
+fn typed_name(name: &str, data_type: Option<&str>) -> String {
+    match data_type {
+        Some(data_type) => format!("{} : {}", name, data_type),
+        None => name.to_string(),
+    }
+}
+
+/// Serialize a single attribute or method line (without indentation). Static
+/// and abstract members get PlantUML's `{static}` and `{abstract}` modifiers,
+/// the others follow the type as in Mermaid.
+pub fn serialize_member(member: &Member) -> String {
+    match member {
+        Member::Attribute(attribute) => {
+            let mut line = String::new();
+            if attribute.is_static {
+                line.push_str("{static} ");
+            }
+            line.push_str(visibility_symbol(attribute.visibility));
+            line.push_str(&typed_name(&attribute.name, attribute.data_type.as_deref()));
+            line.push_str(&member_modifiers(member));
+            line
+        }
+        Member::Method(method) => {
+            let parameters: Vec<String> = method
+                .parameters
+                .iter()
+                .map(|parameter| typed_name(&parameter.name, parameter.data_type.as_deref()))
+                .collect();
+
+            let mut line = String::new();
+            if method.is_static {
+                line.push_str("{static} ");
+            }
+            if method.is_abstract {
+                line.push_str("{abstract} ");
+            }
+            line.push_str(visibility_symbol(method.visibility));
+            line.push_str(&format!("{}({})", method.name, parameters.join(", ")));
+            if let Some(return_type) = &method.return_type {
+                line.push_str(&format!(" : {}", return_type));
+            }
+            line.push_str(&member_modifiers(member));
+            line
+        }
+    }
+}
+
+/// Serialize a relation with its cardinalities and label
+pub fn serialize_relation(out: &mut String, relation: &Relation, options: &SerializeOptions) {
+    let arrow = relation
+        .arrow
+        .as_deref()
+        .unwrap_or_else(|| relation_arrow(relation.kind));
+
+    out.push_str(&node_name(&relation.tail, options));
+    if let Some(cardinality) = &relation.cardinality_tail {
+        out.push_str(&format!(" \"{}\"", cardinality));
+    }
+    out.push_str(&format!(" {} ", arrow));
+    if let Some(cardinality) = &relation.cardinality_head {
+        out.push_str(&format!("\"{}\" ", cardinality));
+    }
+    out.push_str(&node_name(&relation.head, options));
+    if let Some(label) = &relation.label {
+        out.push_str(&format!(" : {}", label));
+    } else if options.synthetic_relation_labels {
+        out.push_str(&format!(" : {}", synthetic_relation_label(relation.kind)));
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::{Attribute, Method, Namespace};
+    use mermaid_parser::types::{RelationKind, TypeNotation, Visibility};
+
+    #[test]
+    fn test_serialize_plantuml() {
+        let mut namespace = Namespace::default();
+        namespace.classes.insert(
+            "Shape".into(),
+            Class {
+                name: "Shape".into(),
+                annotation: Some("abstract".into()),
+                stereotypes: Vec::new(),
+                members: vec![
+                    Member::Attribute(Attribute {
+                        visibility: Visibility::Protected,
+                        name: "count".into(),
+                        data_type: Some("int".into()),
+                        is_static: true,
+                        type_notation: TypeNotation::Postfix,
+                        value: None,
+                        is_deprecated: false,
+                    }),
+                    Member::Method(Method {
+                        visibility: Visibility::Public,
+                        name: "area".into(),
+                        parameters: Vec::new(),
+                        return_type: Some("double".into()),
+                        is_static: false,
+                        is_abstract: true,
+                        return_type_notation: TypeNotation::Postfix,
+                        default_value: None,
+                        throws: Vec::new(),
+                        is_default: false,
+                        is_deprecated: false,
+                    }),
+                ],
+            },
+        );
+        let mut diagram = Diagram::default();
+        diagram.namespaces.insert("com.example".into(), namespace);
+        diagram.relations.push(Relation {
+            tail: "Circle".into(),
+            head: "Shape".into(),
+            kind: RelationKind::Inheritance,
+            cardinality_tail: None,
+            cardinality_head: None,
+            label: None,
+            arrow: None,
+        });
+
+        assert_eq!(
+            serialize_plantuml(&diagram, &SerializeOptions::default()),
+            "@startuml\n\
+             set separator none\n\
+             package com.example {\n\
+             abstract class Shape {\n  \
+             {static} #count : int\n  \
+             {abstract} +area() : double\n\
+             }\n\
+             }\n\
+             Circle --|> Shape\n\
+             @enduml\n"
+        );
+    }
+
+    #[test]
+    fn test_escape_note() {
+        assert_eq!(
+            escape_note("Reads C:\\auth\r\nthen writes"),
+            "Reads C:\\\\auth\\nthen writes"
+        );
+    }
+
+    #[test]
+    fn test_class_keyword() {
+        assert_eq!(class_keyword(Some("interface")), ("interface", None));
+        assert_eq!(class_keyword(Some("record")), ("class", Some("record")));
+        assert_eq!(class_keyword(None), ("class", None));
+
+        let mut out = String::new();
+        let class = Class {
+            name: "Legacy".into(),
+            annotation: Some("interface".into()),
+            stereotypes: vec!["sealed".into(), "deprecated".into()],
+            members: Vec::new(),
+        };
+        serialize_class(&mut out, &class, &SerializeOptions::default());
+        assert_eq!(out, "interface Legacy <<sealed>> <<deprecated>>\n");
+    }
+}
@@ -0,0 +1,225 @@
+//! Extra relations read from a hand maintained sidecar file
+//!
+//! The file is a YAML list of relations, each with a `from` and `to` class
+//! name, an optional `kind` (defaults to `association`) and an optional
+//! `label`:
+//!
+//! ```yaml
+//! - from: Shop
+//!   to: User
+//!   kind: dependency
+//!   label: notifies
+//! ```
+
+use crate::diagnostics;
+use crate::diagram::{Diagram, Relation};
+use jclassfile::class_file::ClassFile;
+use mermaid_parser::types::RelationKind;
+use serde_yml::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const FIELDS: [&str; 4] = ["from", "to", "kind", "label"];
+
+/// A relation given in the sidecar file
+#[derive(Debug, Clone, PartialEq)]
+pub struct SidecarRelation {
+    pub from: String,
+    pub to: String,
+    pub kind: RelationKind,
+    pub label: Option<String>,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RelationsFileError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Parse(#[from] serde_yml::Error),
+    #[error("expected a list of relations")]
+    NotAList,
+    #[error("entry {index}: expected a mapping of fields")]
+    NotAMapping { index: usize },
+    #[error("entry {index}: missing `{field}`")]
+    MissingField { index: usize, field: &'static str },
+    #[error("entry {index}: `{field}` must be a string")]
+    NotAString { index: usize, field: &'static str },
+    #[error("entry {index}: unknown field `{field}`, expected one of {}", FIELDS.join(", "))]
+    UnknownField { index: usize, field: String },
+    #[error(
+        "entry {index}: unknown `kind` value `{kind}`, expected one of inheritance, realization, composition, aggregation, association, dependency"
+    )]
+    UnknownKind { index: usize, kind: String },
+}
+
+/// Read and validate a relations file
+pub fn load_relations(path: &Path) -> Result<Vec<SidecarRelation>, RelationsFileError> {
+    parse_relations(&std::fs::read_to_string(path)?)
+}
+
+/// Parse and validate the contents of a relations file. Every entry is
+/// checked and the first problem is reported with the entry's index.
+pub fn parse_relations(source: &str) -> Result<Vec<SidecarRelation>, RelationsFileError> {
+    let value: Value = serde_yml::from_str(source)?;
+    let entries = match value {
+        Value::Null => return Ok(Vec::new()),
+        Value::Sequence(entries) => entries,
+        _ => return Err(RelationsFileError::NotAList),
+    };
+
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| parse_entry(index, entry))
+        .collect()
+}
+
+fn parse_entry(index: usize, entry: &Value) -> Result<SidecarRelation, RelationsFileError> {
+    let Value::Mapping(mapping) = entry else {
+        return Err(RelationsFileError::NotAMapping { index });
+    };
+
+    for key in mapping.keys() {
+        let field = key.as_str().unwrap_or_default();
+        if !FIELDS.contains(&field) {
+            return Err(RelationsFileError::UnknownField {
+                index,
+                field: serde_yml::to_string(key)
+                    .unwrap_or_default()
+                    .trim_end()
+                    .to_string(),
+            });
+        }
+    }
+
+    let string_field = |field: &'static str| match mapping.get(field) {
+        None => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value.clone())),
+        Some(_) => Err(RelationsFileError::NotAString { index, field }),
+    };
+
+    let from = string_field("from")?.ok_or(RelationsFileError::MissingField {
+        index,
+        field: "from",
+    })?;
+    let to = string_field("to")?.ok_or(RelationsFileError::MissingField { index, field: "to" })?;
+    let kind = match string_field("kind")?.as_deref() {
+        None | Some("association") => RelationKind::Association,
+        Some("inheritance") => RelationKind::Inheritance,
+        Some("realization") => RelationKind::Realization,
+        Some("composition") => RelationKind::Composition,
+        Some("aggregation") => RelationKind::Aggregation,
+        Some("dependency") => RelationKind::Dependency,
+        Some(other) => {
+            return Err(RelationsFileError::UnknownKind {
+                index,
+                kind: other.to_string(),
+            });
+        }
+    };
+    let label = string_field("label")?;
+
+    Ok(SidecarRelation {
+        from,
+        to,
+        kind,
+        label,
+    })
+}
+
+/// Add validated relations to the diagram. Relations whose ends aren't loaded
+/// classes are still added but warned about, as they're likely typos.
+pub fn merge_relations(
+    diagram: &mut Diagram,
+    relations: Vec<SidecarRelation>,
+    classfiles: &BTreeMap<String, ClassFile>,
+) {
+    for (index, relation) in relations.into_iter().enumerate() {
+        for (field, name) in [("from", &relation.from), ("to", &relation.to)] {
+            if !classfiles.contains_key(name) {
+                diagnostics::warn(format_args!(
+                    "Relations file entry {}: `{}` class `{}` is not loaded",
+                    index, field, name
+                ));
+            }
+        }
+
+        diagram.relations.push(Relation {
+            tail: relation.from.into(),
+            head: relation.to.into(),
+            kind: relation.kind,
+            cardinality_tail: None,
+            cardinality_head: None,
+            label: relation.label.map(Into::into),
+            arrow: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        parse_relations(source)
+            .expect_err("Relations should be rejected")
+            .to_string()
+    }
+
+    #[test]
+    fn test_parse_relations() {
+        let relations = parse_relations(
+            "- from: Shop\n  to: User\n  kind: dependency\n  label: notifies\n- from: Shop\n  to: Order\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            relations,
+            vec![
+                SidecarRelation {
+                    from: "Shop".to_string(),
+                    to: "User".to_string(),
+                    kind: RelationKind::Dependency,
+                    label: Some("notifies".to_string()),
+                },
+                SidecarRelation {
+                    from: "Shop".to_string(),
+                    to: "Order".to_string(),
+                    kind: RelationKind::Association,
+                    label: None,
+                },
+            ]
+        );
+        assert_eq!(parse_relations("").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_unknown_kind() {
+        assert!(
+            error("- from: A\n  to: B\n- from: A\n  to: B\n  kind: extends\n")
+                .starts_with("entry 1: unknown `kind` value `extends`")
+        );
+    }
+
+    #[test]
+    fn test_missing_endpoints() {
+        assert_eq!(error("- to: B\n"), "entry 0: missing `from`");
+        assert_eq!(error("- from: A\n"), "entry 0: missing `to`");
+        assert_eq!(
+            error("- from: A\n  to: [B]\n"),
+            "entry 0: `to` must be a string"
+        );
+    }
+
+    #[test]
+    fn test_malformed_entries() {
+        assert!(
+            error("- from: A\n  to: B\n  lable: x\n").starts_with("entry 0: unknown field `lable`")
+        );
+        assert_eq!(
+            error("- A --> B\n"),
+            "entry 0: expected a mapping of fields"
+        );
+        assert_eq!(error("from: A\nto: B\n"), "expected a list of relations");
+    }
+}
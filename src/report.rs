@@ -0,0 +1,119 @@
+//! Machine readable summary of the classes and relations of a diagram
+
+use crate::classfile_utils::{get_full_class_name, get_package_name};
+use crate::diagram::{Diagram, Member, Relation};
+use crate::manual_relations::parse_manual_relation;
+use jclassfile::class_file::ClassFile;
+use mermaid_parser::types::RelationKind;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Everything in the final (post-filtering) diagram
+#[derive(Debug, Default, Serialize)]
+pub struct DiagramReport {
+    pub classes: Vec<ClassReport>,
+    pub relations: Vec<RelationReport>,
+}
+
+/// A class and how many members it shows
+#[derive(Debug, Serialize)]
+pub struct ClassReport {
+    pub name: String,
+    /// Dotted package name, empty for the default package and absent for
+    /// classes without a classfile (e.g. ones written in the seed diagram)
+    pub package: Option<String>,
+    pub attributes: usize,
+    pub methods: usize,
+}
+
+/// A relation as drawn from its tail to its head
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelationReport {
+    pub tail: String,
+    pub head: String,
+    /// Relation kind (e.g. "composition", see `kind_name`)
+    pub kind: &'static str,
+    /// "undirected" for relations drawn without a head marker (e.g. links),
+    /// otherwise "directed"
+    pub direction: &'static str,
+    pub cardinality_tail: Option<String>,
+    pub cardinality_head: Option<String>,
+    pub label: Option<String>,
+    /// Hand authored with a `%% @manual` marker rather than generated
+    pub manual: bool,
+}
+
+impl RelationReport {
+    fn new(relation: &Relation, manual: bool) -> Self {
+        RelationReport {
+            tail: relation.tail.to_string(),
+            head: relation.head.to_string(),
+            kind: kind_name(relation.kind),
+            direction: if relation.is_undirected() {
+                "undirected"
+            } else {
+                "directed"
+            },
+            cardinality_tail: relation.cardinality_tail.as_ref().map(|c| c.to_string()),
+            cardinality_head: relation.cardinality_head.as_ref().map(|c| c.to_string()),
+            label: relation.label.as_ref().map(|label| label.to_string()),
+            manual,
+        }
+    }
+}
+
+/// Name a relation kind is reported by
+fn kind_name(kind: RelationKind) -> &'static str {
+    match kind {
+        RelationKind::Inheritance => "inheritance",
+        RelationKind::Realization => "realization",
+        RelationKind::Composition => "composition",
+        RelationKind::Aggregation => "aggregation",
+        RelationKind::Association => "association",
+        RelationKind::Dependency => "dependency",
+    }
+}
+
+/// Build the report for a diagram given the classfiles keyed by class name
+/// and the hand authored relations written along with it. Classes are listed
+/// in namespace order, and relations in the order they're written: manual
+/// ones first, then the diagram's.
+pub fn collect_report(
+    diagram: &Diagram,
+    classfiles: &BTreeMap<String, ClassFile>,
+    manual_relations: &[String],
+) -> DiagramReport {
+    let mut report = DiagramReport::default();
+
+    for namespace in diagram.namespaces.values() {
+        for (name, class) in &namespace.classes {
+            let package = classfiles
+                .get(name.as_ref())
+                .and_then(get_full_class_name)
+                .map(|full_name| get_package_name(&full_name).replace('/', "."));
+            let attributes = class
+                .members
+                .iter()
+                .filter(|member| matches!(member, Member::Attribute(_)))
+                .count();
+            report.classes.push(ClassReport {
+                name: name.to_string(),
+                package,
+                attributes,
+                methods: class.members.len() - attributes,
+            });
+        }
+    }
+
+    for line in manual_relations {
+        if let Some(relation) = parse_manual_relation(line) {
+            report.relations.push(RelationReport::new(&relation, true));
+        }
+    }
+    for relation in &diagram.relations {
+        report.relations.push(RelationReport::new(relation, false));
+    }
+
+    report
+}
@@ -0,0 +1,409 @@
+//! `select` filters from a diagram's frontmatter, which limit the classes
+//! included in the diagram
+//!
+//! ```yaml
+//! umlink:
+//!   select:
+//!     - field: package
+//!       pattern: com.example
+//!     - field: name
+//!       pattern: "*Repository"
+//! ```
+//!
+//! A class is included when it matches any filter. Patterns containing `*`,
+//! `?` or `[...]` are globs matched against the whole field, otherwise a
+//! `package` pattern also matches subpackages unless `matchMode: exact` is
+//! given, and a `name` pattern must equal the class name.
+//!
+//! An `exclude` directive takes filters of the same shape and removes any
+//! class matching one of them, even if `select` included it.
+//!
+//! A `skipPattern` directive takes globs matched against fully qualified
+//! class names, e.g. `*.generated.*` or `*Test`, and removes any class
+//! matching one of them.
+
+use crate::classfile_utils::{get_full_class_name, get_package_name};
+use crate::diagnostics;
+use crate::diagram::Diagram;
+use jclassfile::class_file::ClassFile;
+use serde_yml::Value;
+
+/// How a literal `package` pattern is matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// The package must equal the pattern
+    Exact,
+    /// The package must equal the pattern or be one of its subpackages
+    Prefix,
+}
+
+/// The part of a class a filter matches against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectField {
+    /// The dotted package name, e.g. `com.example.ui`
+    Package,
+    /// The simple class name as shown in the diagram, e.g. `Tree.Node`
+    Name,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum GlobToken {
+    Literal(char),
+    /// `?`, any single character
+    AnyChar,
+    /// `*`, any run of characters
+    AnyRun,
+    /// `[...]` with its inclusive ranges, or `[!...]` when negated
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// A `*`/`?`/`[...]` wildcard pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    tokens: Vec<GlobToken>,
+}
+
+impl Glob {
+    /// Whether a pattern uses any wildcards
+    pub fn is_glob(pattern: &str) -> bool {
+        pattern.contains(['*', '?', '['])
+    }
+
+    /// Parse a glob, returning `None` if a `[` is never closed or encloses
+    /// nothing
+    pub fn parse(pattern: &str) -> Option<Glob> {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '*' => GlobToken::AnyRun,
+                '?' => GlobToken::AnyChar,
+                '[' => {
+                    let negated = chars.next_if_eq(&'!').is_some();
+                    let mut ranges = Vec::new();
+                    loop {
+                        match chars.next()? {
+                            ']' if !ranges.is_empty() => break,
+                            start => {
+                                if chars.next_if_eq(&'-').is_some() {
+                                    let end = chars.next()?;
+                                    ranges.push((start, end));
+                                } else {
+                                    ranges.push((start, start));
+                                }
+                            }
+                        }
+                    }
+                    GlobToken::Class { negated, ranges }
+                }
+                c => GlobToken::Literal(c),
+            };
+            tokens.push(token);
+        }
+        Some(Glob { tokens })
+    }
+
+    /// Whether the glob matches the whole of `text`
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        // matched[j] is whether the tokens so far match the first j characters
+        let mut matched = vec![false; text.len() + 1];
+        matched[0] = true;
+        for token in &self.tokens {
+            let mut next = vec![false; text.len() + 1];
+            for j in 0..=text.len() {
+                next[j] = match token {
+                    GlobToken::AnyRun => matched[j] || (j > 0 && next[j - 1]),
+                    _ if j == 0 => false,
+                    GlobToken::AnyChar => matched[j - 1],
+                    GlobToken::Literal(c) => matched[j - 1] && text[j - 1] == *c,
+                    GlobToken::Class { negated, ranges } => {
+                        let c = text[j - 1];
+                        let in_class = ranges.iter().any(|&(start, end)| start <= c && c <= end);
+                        matched[j - 1] && in_class != *negated
+                    }
+                };
+            }
+            matched = next;
+        }
+        matched[text.len()]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SelectPattern {
+    Literal(String, MatchMode),
+    Glob(Glob),
+}
+
+/// A single `select` filter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectFilter {
+    field: SelectField,
+    pattern: SelectPattern,
+}
+
+impl SelectFilter {
+    /// Read a filter from its frontmatter mapping. Filters with an invalid
+    /// pattern are reported and left out, as they can't match anything.
+    fn from_yaml(filter: &Value) -> Option<SelectFilter> {
+        let field = match filter.get("field")?.as_str()? {
+            "package" => SelectField::Package,
+            "name" => SelectField::Name,
+            other => {
+                diagnostics::warn(format_args!(
+                    "Unknown select field `{}`, expected `package` or `name`",
+                    other
+                ));
+                return None;
+            }
+        };
+        let pattern = filter.get("pattern")?.as_str()?;
+
+        if Glob::is_glob(pattern) {
+            let Some(glob) = Glob::parse(pattern) else {
+                diagnostics::warn(format_args!(
+                    "Invalid select pattern `{}`, it will not match anything",
+                    pattern
+                ));
+                return None;
+            };
+            return Some(SelectFilter {
+                field,
+                pattern: SelectPattern::Glob(glob),
+            });
+        }
+
+        let match_mode = match filter.get("matchMode").and_then(Value::as_str) {
+            None | Some("prefix") => MatchMode::Prefix,
+            Some("exact") => MatchMode::Exact,
+            Some(other) => {
+                diagnostics::warn(format_args!(
+                    "Unknown select matchMode `{}`, expected `prefix` or `exact`",
+                    other
+                ));
+                MatchMode::Prefix
+            }
+        };
+        Some(SelectFilter {
+            field,
+            pattern: SelectPattern::Literal(pattern.to_string(), match_mode),
+        })
+    }
+
+    /// Whether a class with the given dotted package and simple name matches
+    pub fn matches(&self, package: &str, name: &str) -> bool {
+        match (&self.pattern, self.field) {
+            (SelectPattern::Glob(glob), SelectField::Package) => glob.matches(package),
+            (SelectPattern::Glob(glob), SelectField::Name) => glob.matches(name),
+            (SelectPattern::Literal(pattern, mode), SelectField::Package) => {
+                package_matches(package, pattern, *mode)
+            }
+            (SelectPattern::Literal(pattern, _), SelectField::Name) => name == pattern,
+        }
+    }
+}
+
+/// Check if a dotted package name matches a `select` pattern. Prefixes only
+/// match whole package segments, so `com.example` doesn't match `com.examples`.
+pub fn package_matches(package: &str, pattern: &str, mode: MatchMode) -> bool {
+    match mode {
+        MatchMode::Exact => package == pattern,
+        MatchMode::Prefix => {
+            package == pattern
+                || package
+                    .strip_prefix(pattern)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        }
+    }
+}
+
+/// Read the `select` filters from the diagram's frontmatter. Returns `None`
+/// when there is no `select` directive, meaning every class is included. A
+/// `select` which isn't a sequence, or is empty, includes nothing.
+pub fn select_filters(diagram: &Diagram) -> Option<Vec<SelectFilter>> {
+    let select = diagram.yaml.as_ref()?.get("umlink")?.get("select")?;
+    let Some(filters) = select.as_sequence() else {
+        return Some(Vec::new());
+    };
+    Some(filters.iter().filter_map(SelectFilter::from_yaml).collect())
+}
+
+/// Read the `exclude` filters from the diagram's frontmatter, empty when
+/// there is no `exclude` directive
+pub fn exclude_filters(diagram: &Diagram) -> Vec<SelectFilter> {
+    diagram
+        .yaml
+        .as_ref()
+        .and_then(|yaml| yaml.get("umlink")?.get("exclude")?.as_sequence())
+        .map(|filters| filters.iter().filter_map(SelectFilter::from_yaml).collect())
+        .unwrap_or_default()
+}
+
+/// Read the `skipPattern` globs from the diagram's frontmatter, given as a
+/// single pattern or a list of them. Invalid patterns are reported and left
+/// out.
+pub fn skip_patterns(diagram: &Diagram) -> Vec<Glob> {
+    let Some(patterns) = diagram
+        .yaml
+        .as_ref()
+        .and_then(|yaml| yaml.get("umlink")?.get("skipPattern"))
+    else {
+        return Vec::new();
+    };
+    let patterns: Vec<&str> = match patterns {
+        Value::String(pattern) => vec![pattern],
+        Value::Sequence(patterns) => patterns.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+
+    patterns
+        .into_iter()
+        .filter_map(|pattern| {
+            let glob = Glob::parse(pattern);
+            if glob.is_none() {
+                diagnostics::warn(format_args!(
+                    "Invalid skipPattern `{}`, it will not match anything",
+                    pattern
+                ));
+            }
+            glob
+        })
+        .collect()
+}
+
+/// Check if a classfile's fully qualified name (e.g.
+/// `com.example.generated.Parser`, with `.` between outer and inner classes)
+/// matches any of the skip patterns
+pub fn matches_skip_pattern(patterns: &[Glob], classfile: &ClassFile) -> bool {
+    if patterns.is_empty() {
+        return false;
+    }
+
+    let full_name = get_full_class_name(classfile)
+        .unwrap_or_default()
+        .replace(['/', '$'], ".");
+    patterns.iter().any(|pattern| pattern.matches(&full_name))
+}
+
+/// Dotted package and simple name of a classfile's class, as matched by
+/// filters
+fn package_and_name(classfile: &ClassFile) -> (String, String) {
+    let full_name = get_full_class_name(classfile).unwrap_or_default();
+    let package = get_package_name(&full_name).replace('/', ".");
+    let name = full_name
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .replace('$', ".");
+    (package, name)
+}
+
+/// Check if a classfile should be included based on the select filters
+/// Returns true if the classfile should be included, false otherwise.
+///
+/// Behavior:
+/// - If no "select" directive is present (`None`), include all classfiles
+/// - If "select" is present but has no filters, include no classfiles
+/// - If "select" has filters, include classfile if it matches ANY filter
+pub fn should_include_classfile(filters: Option<&[SelectFilter]>, classfile: &ClassFile) -> bool {
+    let Some(filters) = filters else {
+        return true;
+    };
+
+    let (package, name) = package_and_name(classfile);
+    filters.iter().any(|filter| filter.matches(&package, &name))
+}
+
+/// Check if a classfile matches any of the exclude filters
+pub fn is_excluded(filters: &[SelectFilter], classfile: &ClassFile) -> bool {
+    if filters.is_empty() {
+        return false;
+    }
+
+    let (package, name) = package_and_name(classfile);
+    filters.iter().any(|filter| filter.matches(&package, &name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_matches() {
+        assert!(package_matches(
+            "com.example",
+            "com.example",
+            MatchMode::Prefix
+        ));
+        assert!(package_matches(
+            "com.example.ui",
+            "com.example",
+            MatchMode::Prefix
+        ));
+        assert!(!package_matches(
+            "com.examples",
+            "com.example",
+            MatchMode::Prefix
+        ));
+        assert!(!package_matches("com", "com.example", MatchMode::Prefix));
+
+        assert!(package_matches(
+            "com.example",
+            "com.example",
+            MatchMode::Exact
+        ));
+        assert!(!package_matches(
+            "com.example.ui",
+            "com.example",
+            MatchMode::Exact
+        ));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        let glob = Glob::parse("*Repository").unwrap();
+        assert!(glob.matches("UserRepository"));
+        assert!(glob.matches("Repository"));
+        assert!(!glob.matches("RepositoryFactory"));
+
+        let glob = Glob::parse("com.*.ui").unwrap();
+        assert!(glob.matches("com.example.ui"));
+        assert!(glob.matches("com.example.app.ui"));
+        assert!(!glob.matches("com.example.ui.dialog"));
+
+        let glob = Glob::parse("Shape?").unwrap();
+        assert!(glob.matches("Shape2"));
+        assert!(!glob.matches("Shape"));
+
+        let glob = Glob::parse("[A-C]ircle[!s]").unwrap();
+        assert!(glob.matches("Circle1"));
+        assert!(!glob.matches("Dircle1"));
+        assert!(!glob.matches("Circles"));
+    }
+
+    #[test]
+    fn test_skip_patterns() {
+        let diagram = Diagram {
+            yaml: serde_yml::from_str(
+                "umlink:\n  skipPattern: [\"*.generated.*\", \"*Test\", \"Bad[\"]\n",
+            )
+            .ok(),
+            ..Default::default()
+        };
+        let patterns = skip_patterns(&diagram);
+        assert_eq!(patterns.len(), 2);
+        assert!(patterns[0].matches("com.example.generated.Parser"));
+        assert!(!patterns[0].matches("com.example.Parser"));
+        assert!(patterns[1].matches("com.example.ParserTest"));
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        assert_eq!(Glob::parse("Shape[A-"), None);
+        assert_eq!(Glob::parse("Shape[]"), None);
+    }
+}
@@ -0,0 +1,69 @@
+//! Machine readable metrics about a generated diagram
+
+use crate::classfile_utils::{get_full_class_name, get_package_name};
+use crate::diagram::Diagram;
+use jclassfile::class_file::ClassFile;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Counts describing the final (post-filtering) diagram
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagramStats {
+    /// Total number of classes in the diagram, including interfaces and enums
+    pub classes: usize,
+    pub interfaces: usize,
+    pub enums: usize,
+    pub abstract_classes: usize,
+    pub records: usize,
+    /// Number of relations keyed by relation kind (e.g. "inheritance")
+    pub relations: BTreeMap<String, usize>,
+    /// Average number of attributes and methods per class
+    pub average_members: f64,
+    /// Number of classes keyed by dotted package name
+    pub packages: BTreeMap<String, usize>,
+}
+
+/// Collect stats for the classes and relations of a diagram. Classes are
+/// counted by their stereotype, so classes which only exist in the diagram
+/// (without a classfile) count too, and by the package of their classfile in
+/// `classfiles` (keyed by class name) when they have one.
+pub fn collect_stats(diagram: &Diagram, classfiles: &BTreeMap<String, ClassFile>) -> DiagramStats {
+    let mut stats = DiagramStats::default();
+
+    let mut total_members = 0;
+    for class in diagram
+        .namespaces
+        .values()
+        .flat_map(|namespace| namespace.classes.values())
+    {
+        stats.classes += 1;
+        total_members += class.members.len();
+
+        match class.annotation.as_deref() {
+            Some("interface") => stats.interfaces += 1,
+            Some("enumeration") => stats.enums += 1,
+            Some("record") => stats.records += 1,
+            Some("abstract") => stats.abstract_classes += 1,
+            _ => {}
+        }
+
+        if let Some(full_name) = classfiles
+            .get(class.name.as_ref())
+            .and_then(get_full_class_name)
+        {
+            let package = get_package_name(&full_name).replace('/', ".");
+            *stats.packages.entry(package).or_default() += 1;
+        }
+    }
+    if stats.classes > 0 {
+        stats.average_members = total_members as f64 / stats.classes as f64;
+    }
+
+    for relation in &diagram.relations {
+        let kind = format!("{:?}", relation.kind).to_lowercase();
+        *stats.relations.entry(kind).or_default() += 1;
+    }
+
+    stats
+}
@@ -17,8 +17,8 @@ echo ""
 # Create output directory if it doesn't exist
 mkdir -p "$CLASS_DIR"
 
-# Find all .java files in the com.example package 
-EXAMPLE_FILES=$(find "$JAVA_DIR/com/example" -name "*.java" 2>/dev/null || true)
+# Find all .java files in the com.example package (and the lombok stand-in it uses)
+EXAMPLE_FILES=$(find "$JAVA_DIR/com/example" "$JAVA_DIR/lombok" -name "*.java" 2>/dev/null || true)
 
 if [ -n "$EXAMPLE_FILES" ]; then
     echo "Compiling com.example package files..."
@@ -27,6 +27,49 @@ if [ -n "$EXAMPLE_FILES" ]; then
     echo ""
 fi
 
+# Classes sharing a simple name are kept apart from the rest, since loading
+# them together needs `disambiguate: suffix`
+DUPLICATE_DIR="$SCRIPT_DIR/duplicates"
+DUPLICATE_FILES=$(find "$DUPLICATE_DIR/java" -name "*.java" 2>/dev/null || true)
+
+if [ -n "$DUPLICATE_FILES" ]; then
+    echo "Compiling duplicate name files..."
+    mkdir -p "$DUPLICATE_DIR/class"
+    javac -implicit:none -sourcepath "$JAVA_DIR" -d "$DUPLICATE_DIR/class" $DUPLICATE_FILES
+    echo "✓ Successfully compiled duplicate name files"
+    echo ""
+fi
+
+# The module fixture is compiled as a named module, so it lives apart from the
+# classpath classes too
+MODULE_DIR="$SCRIPT_DIR/modules"
+MODULE_FILES=$(find "$MODULE_DIR/java" -name "*.java" 2>/dev/null || true)
+
+if [ -n "$MODULE_FILES" ]; then
+    echo "Compiling module files..."
+    mkdir -p "$MODULE_DIR/class"
+    javac -d "$MODULE_DIR/class" $MODULE_FILES
+    echo "✓ Successfully compiled module files"
+    echo ""
+fi
+
+# The archive fixture is packaged as an uncompressed jar along with an entry
+# that isn't a valid classfile
+JAR_DIR="$SCRIPT_DIR/jars"
+JAR_FILES=$(find "$JAR_DIR/java" -name "*.java" 2>/dev/null || true)
+
+if [ -n "$JAR_FILES" ]; then
+    echo "Packaging jar files..."
+    JAR_BUILD=$(mktemp -d)
+    javac -d "$JAR_BUILD" $JAR_FILES
+    cp "$JAR_DIR/Broken.txt" "$JAR_BUILD/com/example/jarred/Broken.class"
+    rm -f "$JAR_DIR/jarred.jar"
+    jar --create --no-compress --file "$JAR_DIR/jarred.jar" -C "$JAR_BUILD" .
+    rm -rf "$JAR_BUILD"
+    echo "✓ Successfully packaged jar files"
+    echo ""
+fi
+
 echo "Compilation complete!"
 echo ""
 echo "To verify compiled files:"
@@ -0,0 +1,5 @@
+package com.example.dup.core;
+
+public class Config {
+    private int threads;
+}
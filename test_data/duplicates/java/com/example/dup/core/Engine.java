@@ -0,0 +1,8 @@
+package com.example.dup.core;
+
+import com.example.UmlAssociate;
+
+public class Engine {
+    @UmlAssociate
+    private Config config;
+}
@@ -0,0 +1,5 @@
+package com.example.dup.ui;
+
+public class Config {
+    private String theme;
+}
@@ -0,0 +1,8 @@
+package com.example.dup.ui;
+
+import com.example.UmlAssociate;
+
+public class Screen {
+    @UmlAssociate
+    private Config config;
+}
@@ -0,0 +1,14 @@
+package com.example.jarred;
+
+public class Ticker {
+    private int ticks;
+
+    public Runnable tickLater() {
+        return new Runnable() {
+            @Override
+            public void run() {
+                ticks++;
+            }
+        };
+    }
+}
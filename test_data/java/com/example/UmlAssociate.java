@@ -7,9 +7,9 @@ import java.lang.annotation.ElementType;
 import java.lang.annotation.Target;
 
 @Retention(RetentionPolicy.CLASS)
-@Target(ElementType.FIELD)
+@Target({ElementType.FIELD, ElementType.METHOD})
 public @interface UmlAssociate {
-    String selfCard() default "1";
+    String selfCard() default "";
     String label() default "";
-    String otherCard() default "1";
+    String otherCard() default "";
 }
@@ -0,0 +1,5 @@
+package com.example.access;
+
+public class Database {
+    private String url;
+}
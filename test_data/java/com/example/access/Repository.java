@@ -0,0 +1,7 @@
+package com.example.access;
+
+@Status(Stage.DRAFT)
+public class Repository {
+    @Uses(label = {"reads", "writes"})
+    private Database database;
+}
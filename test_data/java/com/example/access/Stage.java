@@ -0,0 +1,6 @@
+package com.example.access;
+
+public enum Stage {
+    DRAFT,
+    STABLE
+}
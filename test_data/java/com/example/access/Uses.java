@@ -0,0 +1,16 @@
+package com.example.access;
+
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+
+@Retention(RetentionPolicy.RUNTIME)
+@Target(ElementType.FIELD)
+public @interface Uses {
+    String[] label() default {};
+
+    String selfCard() default "";
+
+    String otherCard() default "";
+}
@@ -0,0 +1,28 @@
+package com.example.bank;
+
+import com.example.UmlAssociate;
+
+public class Account {
+    @UmlAssociate(label = "owned by")
+    public Owner owner;
+
+    @UmlAssociate(label = "records")
+    private Ledger ledger;
+
+    protected int balance;
+
+    String branch;
+
+    public int getBalance() {
+        return balance;
+    }
+
+    protected void audit() {
+    }
+
+    void reconcile() {
+    }
+
+    private void log(String message) {
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.bank;
+
+public class Ledger {
+    private int entries;
+}
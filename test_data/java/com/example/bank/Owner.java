@@ -0,0 +1,5 @@
+package com.example.bank;
+
+public class Owner {
+    public String name;
+}
@@ -0,0 +1,36 @@
+package com.example.booking;
+
+public class Booking {
+    private final String guest;
+    private final String note;
+
+    private Booking(Builder builder) {
+        this.guest = builder.guest;
+        this.note = builder.note;
+    }
+
+    public String getGuest() {
+        return guest;
+    }
+
+    @Deprecated
+    public static class Builder {
+        private String guest;
+        private String note;
+
+        public Builder withGuest(String guest) throws IllegalArgumentException {
+            this.guest = guest;
+            return this;
+        }
+
+        @Deprecated
+        public Builder withNote(String note) {
+            this.note = note;
+            return this;
+        }
+
+        public Booking build() {
+            return new Booking(this);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+package com.example.bridges;
+
+public class Box<T> {
+    private T value;
+
+    public T get() {
+        return value;
+    }
+
+    public void set(T value) {
+        this.value = value;
+    }
+}
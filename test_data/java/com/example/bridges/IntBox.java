@@ -0,0 +1,12 @@
+package com.example.bridges;
+
+public class IntBox extends Box<Integer> {
+    @Override
+    public Integer get() {
+        return 0;
+    }
+
+    @Override
+    public void set(Integer value) {
+    }
+}
@@ -0,0 +1,4 @@
+package com.example.channels;
+
+public interface ByteChannel extends ReadableChannel, WritableChannel {
+}
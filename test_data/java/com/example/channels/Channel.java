@@ -0,0 +1,5 @@
+package com.example.channels;
+
+public interface Channel {
+    boolean isOpen();
+}
@@ -0,0 +1,15 @@
+package com.example.channels;
+
+public class FileChannel implements ByteChannel {
+    public boolean isOpen() {
+        return true;
+    }
+
+    public int read(byte[] buffer) {
+        return 0;
+    }
+
+    public int write(byte[] buffer) {
+        return 0;
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.channels;
+
+public interface ReadableChannel extends Channel {
+    int read(byte[] buffer);
+}
@@ -0,0 +1,5 @@
+package com.example.channels;
+
+public interface WritableChannel extends Channel {
+    int write(byte[] buffer);
+}
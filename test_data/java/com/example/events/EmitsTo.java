@@ -0,0 +1,14 @@
+package com.example.events;
+
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+
+@Retention(RetentionPolicy.CLASS)
+@Target(ElementType.FIELD)
+public @interface EmitsTo {
+    String selfCard() default "";
+    String label() default "";
+    String otherCard() default "";
+}
@@ -0,0 +1,6 @@
+package com.example.events;
+
+public class EventBus {
+    public void publish(String event) {
+    }
+}
@@ -0,0 +1,6 @@
+package com.example.events;
+
+public class OrderService {
+    @EmitsTo(selfCard = "", label = "order events", otherCard = "")
+    private EventBus bus;
+}
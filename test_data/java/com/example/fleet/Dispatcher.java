@@ -0,0 +1,12 @@
+package com.example.fleet;
+
+import com.example.UmlAssociate;
+import com.example.UmlNavigate;
+
+public class Dispatcher {
+    @UmlAssociate
+    private Truck assigned;
+
+    @UmlNavigate
+    private Truck standby;
+}
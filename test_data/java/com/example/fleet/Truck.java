@@ -0,0 +1,5 @@
+package com.example.fleet;
+
+public class Truck {
+    private String plate;
+}
@@ -0,0 +1,17 @@
+package com.example.functions;
+
+public interface Formatter {
+    String format(String value);
+
+    default String formatAll(String first, String second) {
+        return join(format(first), format(second));
+    }
+
+    static Formatter plain() {
+        return value -> value;
+    }
+
+    private String join(String first, String second) {
+        return first + second;
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.functions;
+
+public interface Listener {
+    void onEvent(String event);
+}
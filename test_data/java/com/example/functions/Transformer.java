@@ -0,0 +1,6 @@
+package com.example.functions;
+
+@FunctionalInterface
+public interface Transformer {
+    String apply(String input);
+}
@@ -0,0 +1,5 @@
+package com.example.garage;
+
+public class Engine {
+    private int horsepower;
+}
@@ -0,0 +1,10 @@
+package com.example.garage;
+
+import com.example.UmlAggregate;
+import com.example.UmlCompose;
+
+public class Garage {
+    @UmlAggregate
+    @UmlCompose
+    private Engine engine;
+}
@@ -0,0 +1,17 @@
+package com.example.geometry;
+
+import java.util.List;
+
+public record Point(int x, int y, List<String> labels) {
+    public static final Point ORIGIN = new Point(0, 0, List.of());
+
+    public Point(int x, int y) {
+        this(x, y, List.of());
+    }
+
+    public double distanceTo(Point other) {
+        int dx = x - other.x;
+        int dy = y - other.y;
+        return Math.sqrt(dx * dx + dy * dy);
+    }
+}
@@ -0,0 +1,20 @@
+package com.example.grading;
+
+public class Grader {
+    private String course;
+
+    public String getCourse() {
+        return course;
+    }
+
+    public String grade(int score) {
+        if (score >= 90) {
+            return "A";
+        } else if (score >= 80) {
+            return "B";
+        } else if (score >= 70) {
+            return "C";
+        }
+        return "F";
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.ledger;
+
+public class Invoice extends Record {
+    private String payee;
+}
@@ -0,0 +1,5 @@
+package com.example.ledger;
+
+public class Record {
+    protected long amount;
+}
@@ -0,0 +1,11 @@
+package com.example.legacy;
+
+public class Client {
+    @Deprecated
+    public String token;
+
+    public String name;
+
+    @Deprecated
+    public void connect() {}
+}
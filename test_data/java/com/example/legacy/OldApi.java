@@ -0,0 +1,12 @@
+package com.example.legacy;
+
+@Deprecated
+public interface OldApi {
+    @Deprecated
+    int LIMIT = 10;
+
+    @Deprecated
+    void reset();
+
+    void run();
+}
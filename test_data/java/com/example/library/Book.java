@@ -0,0 +1,5 @@
+package com.example.library;
+
+public class Book {
+    private String title;
+}
@@ -0,0 +1,25 @@
+package com.example.library;
+
+import com.example.UmlAssociate;
+import java.util.List;
+import java.util.Set;
+
+public class Library {
+    @UmlAssociate(label = "lends")
+    private Book featured;
+
+    @UmlAssociate(selfCard = "1", label = "latest", otherCard = "1")
+    private Book latest;
+
+    @UmlAssociate(label = "shelf")
+    private Book[] shelf;
+
+    @UmlAssociate(label = "reserved")
+    private List<Book> reserved;
+
+    @UmlAssociate(label = "holds", otherCard = "0..5")
+    private Set<Book> holds;
+
+    @UmlAssociate(label = "counts")
+    private int[] counts;
+}
@@ -0,0 +1,5 @@
+package com.example.literals;
+
+public @interface Caption {
+    String value() default "\"untitled\"";
+}
@@ -0,0 +1,9 @@
+package com.example.literals;
+
+public class Escapes {
+    public static final String GREETING = "say \"hi\"\n";
+    public static final String PATH = "C:\\tmp\tdir";
+    public static final char QUOTE = '\'';
+    public static final char NEWLINE = '\n';
+    public static final char BACKSLASH = '\\';
+}
@@ -0,0 +1,12 @@
+package com.example.logging;
+
+public class Logger {
+    public void log(String format, Object... args) {
+    }
+
+    public void logAll(String[] messages) {
+    }
+
+    public void table(int[]... rows) {
+    }
+}
@@ -0,0 +1,30 @@
+package com.example.lombok;
+
+import lombok.Generated;
+
+/**
+ * What a Lombok `@Data` class looks like after compilation: the declared
+ * fields plus generated accessors marked with `@lombok.Generated`.
+ */
+public class Person {
+    private String name;
+
+    public void greet() {
+        System.out.println("Hello " + name);
+    }
+
+    @Generated
+    public String getName() {
+        return name;
+    }
+
+    @Generated
+    public void setName(String name) {
+        this.name = name;
+    }
+
+    @Generated
+    public int hashCode() {
+        return name == null ? 0 : name.hashCode();
+    }
+}
@@ -0,0 +1,15 @@
+package com.example.names;
+
+public class Mangled {
+    private int foo$bar;
+    private int $val$x;
+    private String total$;
+
+    public void $reset() {
+    }
+
+    // Asserting makes the compiler add a synthetic `$assertionsDisabled` field
+    public void check(int amount) {
+        assert amount > 0;
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.network;
+
+public class Port {
+    private int number;
+}
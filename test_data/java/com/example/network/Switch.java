@@ -0,0 +1,11 @@
+package com.example.network;
+
+import com.example.UmlAssociate;
+
+public class Switch {
+    @UmlAssociate
+    private Port uplink;
+
+    @UmlAssociate
+    private Port downlink;
+}
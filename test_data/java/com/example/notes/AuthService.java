@@ -0,0 +1,6 @@
+package com.example.notes;
+
+@DiagramNote("Handles \"auth\" for every request")
+public class AuthService {
+    private Session current;
+}
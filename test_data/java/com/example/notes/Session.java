@@ -0,0 +1,5 @@
+package com.example.notes;
+
+public class Session {
+    private String token;
+}
@@ -0,0 +1,9 @@
+package com.example.payments;
+
+public final class Card implements Payment {
+    private int amount;
+
+    public int amount() {
+        return amount;
+    }
+}
@@ -0,0 +1,9 @@
+package com.example.payments;
+
+public non-sealed class Cash implements Payment {
+    private int amount;
+
+    public int amount() {
+        return amount;
+    }
+}
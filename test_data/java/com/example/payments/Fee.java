@@ -0,0 +1,5 @@
+package com.example.payments;
+
+public abstract sealed class Fee permits FlatFee {
+    public abstract int charge(Payment payment);
+}
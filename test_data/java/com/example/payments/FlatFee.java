@@ -0,0 +1,9 @@
+package com.example.payments;
+
+public final class FlatFee extends Fee {
+    private int charge;
+
+    public int charge(Payment payment) {
+        return charge;
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.payments;
+
+public sealed interface Payment permits Card, Cash {
+    int amount();
+}
@@ -0,0 +1,42 @@
+package com.example.pizza;
+
+public class Pizza {
+    private final String size;
+    private final boolean cheese;
+    private final int slices;
+
+    private Pizza(Builder builder) {
+        this.size = builder.size;
+        this.cheese = builder.cheese;
+        this.slices = builder.slices;
+    }
+
+    public String getSize() {
+        return size;
+    }
+
+    public static class Builder {
+        private String size;
+        private boolean cheese;
+        private int slices;
+
+        public Builder size(String size) {
+            this.size = size;
+            return this;
+        }
+
+        public Builder withCheese(boolean cheese) {
+            this.cheese = cheese;
+            return this;
+        }
+
+        public Builder setSlices(int slices) {
+            this.slices = slices;
+            return this;
+        }
+
+        public Pizza build() {
+            return new Pizza(this);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.reports;
+
+public class Chart {
+    private String title;
+}
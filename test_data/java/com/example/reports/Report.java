@@ -0,0 +1,12 @@
+package com.example.reports;
+
+import com.example.UmlAssociate;
+
+public class Report {
+    @UmlAssociate
+    private Chart summary;
+    @UmlAssociate
+    private Chart detail;
+    @UmlAssociate
+    private Chart trend;
+}
@@ -0,0 +1,5 @@
+package com.example.scanner;
+
+public class Camera {
+    private int resolution;
+}
@@ -0,0 +1,7 @@
+package com.example.scanner;
+
+public class QRGenerator {
+    public String generate(String text) {
+        return text;
+    }
+}
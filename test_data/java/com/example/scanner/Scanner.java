@@ -0,0 +1,29 @@
+package com.example.scanner;
+
+import com.example.UmlAssociate;
+import java.util.List;
+
+public class Scanner {
+    @UmlAssociate
+    private Camera camera;
+
+    public Scanner(Camera camera) {
+        this.camera = camera;
+    }
+
+    public Code scan(QRGenerator generator) {
+        return null;
+    }
+
+    public Code rescan(QRGenerator generator, String hint) {
+        return null;
+    }
+
+    public List<Code> history() {
+        return null;
+    }
+
+    public Scanner copy() {
+        return this;
+    }
+}
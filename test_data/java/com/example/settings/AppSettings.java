@@ -0,0 +1,6 @@
+package com.example.settings;
+
+public class AppSettings {
+    @Setting(key = "app.theme")
+    private String theme;
+}
@@ -0,0 +1,11 @@
+package com.example.settings;
+
+public class Limits {
+    public static final int MAX = 100;
+    public static final String NAME = "rocket";
+    public static final boolean ENABLED = true;
+    public static final char SEPARATOR = ':';
+    public static final long TIMEOUT = 30000L;
+    public static final Integer BOXED = 5;
+    public static int counter = 0;
+}
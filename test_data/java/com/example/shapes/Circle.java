@@ -0,0 +1,9 @@
+package com.example.shapes;
+
+public class Circle extends Shape {
+    private double radius;
+
+    public double area() {
+        return Math.PI * radius * radius;
+    }
+}
@@ -0,0 +1,5 @@
+package com.example.shapes;
+
+public abstract class Shape {
+    public abstract double area();
+}
@@ -0,0 +1,5 @@
+package com.example.shop;
+
+public class Order {
+    private int total;
+}
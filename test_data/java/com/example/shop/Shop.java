@@ -0,0 +1,11 @@
+package com.example.shop;
+
+import java.util.HashMap;
+import java.util.Map;
+
+import com.example.UmlAssociate;
+
+public class Shop {
+    @UmlAssociate
+    private Map<User, Order> ordersByUser = new HashMap<>();
+}
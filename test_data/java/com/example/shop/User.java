@@ -0,0 +1,5 @@
+package com.example.shop;
+
+public class User {
+    private String name;
+}
@@ -0,0 +1,14 @@
+package com.example.storage;
+
+import java.io.IOException;
+import java.util.concurrent.TimeoutException;
+
+public class FileStore {
+    public void save(String path) throws IOException {}
+
+    public String load(String path) throws IOException, TimeoutException {
+        return "";
+    }
+
+    public void clear() {}
+}
@@ -0,0 +1,6 @@
+package com.example.tagged;
+
+@Audited
+public class AuditedService {
+    public void run() { }
+}
@@ -0,0 +1,5 @@
+package com.example.tagged;
+
+public class PlainService {
+    public void run() { }
+}
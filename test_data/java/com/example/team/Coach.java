@@ -0,0 +1,5 @@
+package com.example.team;
+
+public class Coach {
+    private String name;
+}
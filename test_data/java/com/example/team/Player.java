@@ -0,0 +1,5 @@
+package com.example.team;
+
+public class Player {
+    private String name;
+}
@@ -0,0 +1,25 @@
+package com.example.team;
+
+import com.example.UmlAggregate;
+import com.example.UmlAssociate;
+import java.util.List;
+
+public class Team {
+    private List<Player> players;
+    private Coach coach;
+    private String name;
+
+    @UmlAggregate(label = "fields")
+    public List<Player> getPlayers() {
+        return players;
+    }
+
+    @UmlAssociate(label = "coached by", otherCard = "1")
+    public Coach getCoach() {
+        return coach;
+    }
+
+    public String getName() {
+        return name;
+    }
+}
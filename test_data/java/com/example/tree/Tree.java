@@ -0,0 +1,19 @@
+package com.example.tree;
+
+public class Tree {
+    private Node root;
+
+    public Runnable visitor() {
+        return new Runnable() {
+            public void run() {}
+        };
+    }
+
+    public static class Node {
+        protected int depth;
+    }
+
+    public static class Leaf extends Node {
+        private String value;
+    }
+}
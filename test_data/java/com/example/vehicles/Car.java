@@ -0,0 +1,8 @@
+package com.example.vehicles;
+
+public class Car extends Vehicle {
+    @Override
+    public int wheels() {
+        return 4;
+    }
+}
@@ -0,0 +1,6 @@
+package com.example.vehicles;
+
+public class SportsCar extends Car {
+    public void boost() {
+    }
+}
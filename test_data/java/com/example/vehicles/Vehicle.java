@@ -0,0 +1,21 @@
+package com.example.vehicles;
+
+public class Vehicle {
+    public String describe() {
+        return "vehicle";
+    }
+
+    public int wheels() {
+        return 0;
+    }
+
+    protected void honk() {
+    }
+
+    private void service() {
+    }
+
+    public static Vehicle create() {
+        return new Vehicle();
+    }
+}
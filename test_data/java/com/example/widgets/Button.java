@@ -0,0 +1,12 @@
+package com.example.widgets;
+
+public final class Button extends Widget {
+    private String text;
+
+    public void click() {
+    }
+
+    @Override
+    protected void layout() {
+    }
+}
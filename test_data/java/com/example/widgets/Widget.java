@@ -0,0 +1,19 @@
+package com.example.widgets;
+
+public abstract class Widget {
+    protected int width;
+    private String id;
+    int revision;
+
+    public void draw() {
+    }
+
+    protected void layout() {
+    }
+
+    void invalidate() {
+    }
+
+    private void cache() {
+    }
+}
@@ -0,0 +1,15 @@
+package com.example.wildcards;
+
+import java.util.Collection;
+import java.util.Comparator;
+import java.util.List;
+import java.util.Map;
+
+public class Registry<T> {
+    private List<? extends Number> readings;
+    private Comparator<? super Integer> order;
+    private Map<String, ?> extras;
+
+    public void copy(List<? super T> into, Collection<? extends T> from) {
+    }
+}
@@ -0,0 +1,6 @@
+package com.example.app.api;
+
+public class App {
+    public void run() {
+    }
+}
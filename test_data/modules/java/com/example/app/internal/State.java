@@ -0,0 +1,5 @@
+package com.example.app.internal;
+
+public class State {
+    private int value;
+}
@@ -0,0 +1,7 @@
+module com.example.app {
+    requires java.logging;
+    requires transitive java.sql;
+
+    exports com.example.app.api;
+    opens com.example.app.internal;
+}
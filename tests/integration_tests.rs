@@ -17,6 +17,14 @@ fn run_umlink(args: &[&str]) -> Result<std::process::Output, std::io::Error> {
         .output()
 }
 
+/// Helper function to run the umlink binary from a specific working directory
+fn run_umlink_in(dir: &Path, args: &[&str]) -> Result<std::process::Output, std::io::Error> {
+    Command::new(env!("CARGO_BIN_EXE_umlink"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+}
+
 #[test]
 fn test_basic_diagram_generation() {
     setup_test_output_dir().expect("Failed to create test output directory");
@@ -186,11 +194,74 @@ fn test_class_retention_annotation() {
 fn test_cardinality_preservation() {
     setup_test_output_dir().expect("Failed to create test output directory");
 
+    let output_file = "test_output/test_cardinality.mmd";
+    let relinked_file = "test_output/test_cardinality_relinked.mmd";
+    let _ = fs::remove_file(output_file);
+    let _ = fs::remove_file(relinked_file);
+
     let output = run_umlink(&[
         "test_data/input/test_cardinality.mmd",
-        "-i",
+        "-c",
         "test_data/class",
         "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output_file_path = Path::new(output_file);
+    assert!(output_file_path.exists(), "Output file was not created");
+
+    let assert_cardinalities = |content: &str| {
+        assert!(
+            content.contains("Student \"1\" --> \"1..*\" Course\n"),
+            "Cardinalities on both ends should be kept:\n{}",
+            content
+        );
+        assert!(
+            content.contains("Galaxy --> \"many\" Star : Contains\n"),
+            "A single cardinality should be kept next to the label:\n{}",
+            content
+        );
+        let customer = content
+            .lines()
+            .find(|line| line.contains("Customer") && line.contains("Ticket"))
+            .unwrap_or_else(|| panic!("The Customer relation is missing:\n{}", content));
+        assert!(
+            customer.contains("\"1\"") && customer.contains("\"*\""),
+            "Cardinalities of a reversed arrow should be kept: {}",
+            customer
+        );
+    };
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert_cardinalities(&content);
+
+    // Linking the output again keeps them too
+    let output = run_umlink(&[output_file, "-c", "test_data/class", "-o", relinked_file])
+        .expect("Failed to execute umlink");
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let relinked = fs::read_to_string(relinked_file).expect("Failed to read output file");
+    assert_cardinalities(&relinked);
+}
+
+#[test]
+fn test_abstract_as_realization() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_abstract_realization.mmd",
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
         "test_output",
     ])
     .expect("Failed to execute umlink");
@@ -201,9 +272,3914 @@ fn test_cardinality_preservation() {
         String::from_utf8_lossy(&output.stderr)
     );
 
-    let output_file = Path::new("test_output/test_cardinality.mmd");
-    assert!(output_file.exists(), "Output file was not created");
+    let output_file = Path::new("test_output/test_abstract_realization.mmd");
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+
+    // Circle extends the abstract Shape, so it should be drawn as a realization
+    assert!(
+        content.contains("Circle ..|> Shape"),
+        "Subclass of an abstract class should use a realization edge"
+    );
+    assert!(
+        !content.contains("Circle --|> Shape"),
+        "Subclass of an abstract class should not use an inheritance edge"
+    );
+}
+
+#[test]
+fn test_config_search_from_nested_directory() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let root = Path::new(manifest_dir).join("test_output/config_search");
+    let nested = root.join("nested/deeper");
+    fs::create_dir_all(&nested).expect("Failed to create nested directory");
+    fs::write(root.join("umlink.yml"), "skip: com.example.Skip\n")
+        .expect("Failed to write config file");
+
+    let classfiles = format!("{manifest_dir}/test_data/class/com/example");
+    let output_file = nested.join("output.mmd");
+
+    // The config lives two directories up and should still be picked up
+    let _ = fs::remove_file(&output_file);
+    let output = run_umlink_in(&nested, &["-c", &classfiles, "-o", "output.mmd"])
+        .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let config_path = root.join("umlink.yml");
+    assert!(
+        stderr.contains(&format!(
+            "Loaded configuration from {}",
+            config_path.display()
+        )),
+        "Should report the config file found in a parent directory, got: {stderr}"
+    );
+
+    let content = fs::read_to_string(&output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("hiddenField: String"),
+        "Skip annotation from the parent config should be applied"
+    );
+
+    // With the search disabled the parent config should be ignored
+    let _ = fs::remove_file(&output_file);
+    let output = run_umlink_in(
+        &nested,
+        &["-c", &classfiles, "-o", "output.mmd", "--no-config-search"],
+    )
+    .expect("Failed to execute umlink");
+
+    assert!(output.status.success());
+    assert!(
+        !String::from_utf8_lossy(&output.stderr).contains("Loaded configuration"),
+        "No config should be loaded when searching is disabled"
+    );
+
+    let content = fs::read_to_string(&output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("hiddenField: String"),
+        "Skip annotation should not be applied without a config"
+    );
+}
+
+#[test]
+fn test_env_config_overrides() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = Path::new("test_output/test_env_overrides.mmd");
+
+    // UMLINK_SKIP should take effect when no flag is given
+    let _ = fs::remove_file(output_file);
+    let output = Command::new(env!("CARGO_BIN_EXE_umlink"))
+        .env("UMLINK_SKIP", "com.example.Skip")
+        .args(["-c", "test_data/class/com/example", "-o"])
+        .arg(output_file)
+        .output()
+        .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("hiddenField: String"),
+        "UMLINK_SKIP should hide members marked with @Skip"
+    );
+
+    // An explicit --skip flag should be used along with the environment variable
+    let _ = fs::remove_file(output_file);
+    let output = Command::new(env!("CARGO_BIN_EXE_umlink"))
+        .env("UMLINK_SKIP", "com.example.Skip")
+        .args([
+            "-c",
+            "test_data/class/com/example",
+            "--skip",
+            "com.example.SkipClass",
+        ])
+        .arg("-o")
+        .arg(output_file)
+        .output()
+        .expect("Failed to execute umlink");
+
+    assert!(output.status.success());
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("hiddenField: String"),
+        "UMLINK_SKIP should still apply alongside --skip"
+    );
+    assert!(
+        !content.contains("hiddenFieldWithClassRetention"),
+        "--skip annotation should be applied"
+    );
+
+    // A single annotation given both ways is taken from the flag
+    let _ = fs::remove_file(output_file);
+    let output = Command::new(env!("CARGO_BIN_EXE_umlink"))
+        .env("UMLINK_AGGREGATE", "com.example.UmlUnused")
+        .args([
+            "-c",
+            "test_data/class/com/example/Computer.class",
+            "-c",
+            "test_data/class/com/example/io",
+            "--no-config-search",
+            "--aggregate",
+            "com.example.UmlAggregate",
+        ])
+        .arg("-o")
+        .arg(output_file)
+        .output()
+        .expect("Failed to execute umlink");
+
+    assert!(output.status.success());
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Computer --o Keyboard\n"),
+        "--aggregate should override UMLINK_AGGREGATE:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_multiple_skip_annotations() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = Path::new("test_output/test_multiple_skip.mmd");
+
+    // Repeated --skip flags
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example",
+        "--no-config-search",
+        "--skip",
+        "com.example.Skip",
+        "--skip",
+        "com.example.SkipClass",
+        "-o",
+        "test_output/test_multiple_skip.mmd",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("hiddenField: String")
+            && !content.contains("hiddenFieldWithClassRetention"),
+        "Members marked with either skip annotation should be hidden:\n{}",
+        content
+    );
+
+    // A list in the config file, combined with a --skip flag
+    let config_file = Path::new("test_output/test_multiple_skip.yml");
+    fs::write(config_file, "skip:\n  - com.example.SkipClass\n").expect("Failed to write config");
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example",
+        "--config",
+        "test_output/test_multiple_skip.yml",
+        "--skip",
+        "com.example.Skip",
+        "-o",
+        "test_output/test_multiple_skip.mmd",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(output.status.success());
 
-    // Just verify it runs successfully and produces output
-    // Cardinality-specific assertions would require parsing the mermaid output
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("hiddenField: String")
+            && !content.contains("hiddenFieldWithClassRetention"),
+        "Skip annotations from the config and the command line should both apply:\n{}",
+        content
+    );
+
+    // Entries of UMLINK_SKIP are trimmed, and blank ones are ignored
+    let _ = fs::remove_file(output_file);
+    let output = Command::new(env!("CARGO_BIN_EXE_umlink"))
+        .env("UMLINK_SKIP", " com.example.Skip, ,com.example.SkipClass ,")
+        .args([
+            "-c",
+            "test_data/class/com/example",
+            "--no-config-search",
+            "-o",
+        ])
+        .arg(output_file)
+        .output()
+        .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("hiddenField: String")
+            && !content.contains("hiddenFieldWithClassRetention"),
+        "Skip annotations listed with spaces around them should apply:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_map_field_relations() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Default: only relate to the value type of the map
+    let output = run_umlink(&[
+        "test_data/input/test_map_value_only.mmd",
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_map_value_only.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains("Shop -- \"*\" Order"),
+        "Map field should relate to its value type"
+    );
+    assert!(
+        !content.contains("-- User"),
+        "Map field should not relate to its key type by default"
+    );
+    assert!(
+        !content.contains("-- Map"),
+        "Map field should not relate to the map itself"
+    );
+
+    // mapRelations: both relates to the key and value types with labels
+    let output = run_umlink(&[
+        "test_data/input/test_map_both.mmd",
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(output.status.success());
+
+    let content =
+        fs::read_to_string("test_output/test_map_both.mmd").expect("Failed to read output file");
+    assert!(
+        content.contains("Shop -- \"*\" User : key"),
+        "Map field should relate to its key type"
+    );
+    assert!(
+        content.contains("Shop -- \"*\" Order : value"),
+        "Map field should relate to its value type"
+    );
+}
+
+#[test]
+fn test_stats_json() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_stats.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        "test_output/test_stats.mmd",
+        "--stats-json",
+        "test_output/test_stats.json",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_stats.json").expect("Failed to read stats file");
+    let stats: serde_json::Value = serde_json::from_str(&content).expect("Stats should be JSON");
+
+    // Shape (abstract, 1 method) and Circle (1 field, 1 method)
+    assert_eq!(stats["classes"], 2);
+    assert_eq!(stats["interfaces"], 0);
+    assert_eq!(stats["enums"], 0);
+    assert_eq!(stats["abstractClasses"], 1);
+    assert_eq!(stats["records"], 0);
+    assert_eq!(stats["relations"]["inheritance"], 1);
+    assert_eq!(stats["averageMembers"], 1.5);
+    assert_eq!(stats["packages"]["com.example.shapes"], 2);
+
+    // Classes pruned by --roots aren't counted
+    let _ = fs::remove_file("test_output/test_stats.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-c",
+        "test_data/class/com/example/io",
+        "-o",
+        "test_output/test_stats.mmd",
+        "--roots",
+        "Circle",
+        "--stats-json",
+        "test_output/test_stats.json",
+    ])
+    .expect("Failed to execute umlink");
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let content =
+        fs::read_to_string("test_output/test_stats.json").expect("Failed to read stats file");
+    let stats: serde_json::Value = serde_json::from_str(&content).expect("Stats should be JSON");
+    assert_eq!(stats["classes"], 2);
+    assert_eq!(stats["enums"], 0);
+    assert_eq!(
+        stats["packages"],
+        serde_json::json!({ "com.example.shapes": 2 })
+    );
+}
+
+#[test]
+fn test_report_json() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_report.mmd");
+    fs::write(
+        "test_output/test_report_seed.mmd",
+        "classDiagram\nLibrary ..> Book : catalogues %% @manual\n",
+    )
+    .expect("Failed to write seed diagram");
+    let output = run_umlink(&[
+        "test_output/test_report_seed.mmd",
+        "-c",
+        "test_data/class/com/example/library",
+        "-o",
+        "test_output/test_report.mmd",
+        "--link",
+        "com.example.UmlAssociate",
+        "--report",
+        "test_output/test_report.json",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_report.json").expect("Failed to read report");
+    let report: serde_json::Value = serde_json::from_str(&content).expect("Report should be JSON");
+
+    let book = report["classes"]
+        .as_array()
+        .expect("Classes should be a list")
+        .iter()
+        .find(|class| class["name"] == "Book")
+        .expect("Book should be reported");
+    assert_eq!(book["package"], "com.example.library");
+    assert_eq!(book["attributes"], 1);
+    assert_eq!(book["methods"], 0);
+
+    let relations = report["relations"]
+        .as_array()
+        .expect("Relations should be a list");
+    assert_eq!(relations.len(), 6, "{}", content);
+    let reserved = relations
+        .iter()
+        .find(|relation| relation["label"] == "reserved")
+        .expect("The reserved relation should be reported");
+    assert_eq!(reserved["tail"], "Library");
+    assert_eq!(reserved["head"], "Book");
+    assert_eq!(reserved["kind"], "association");
+    assert_eq!(reserved["direction"], "undirected");
+    assert_eq!(reserved["cardinalityTail"], serde_json::Value::Null);
+    assert_eq!(reserved["cardinalityHead"], "*");
+    assert_eq!(reserved["manual"], false);
+
+    // Hand authored relations are reported too
+    let catalogues = relations
+        .iter()
+        .find(|relation| relation["label"] == "catalogues")
+        .expect("The manual relation should be reported");
+    assert_eq!(catalogues["kind"], "dependency");
+    assert_eq!(catalogues["direction"], "directed");
+    assert_eq!(catalogues["manual"], true);
+}
+
+#[test]
+fn test_roots_prune_unreachable_classes() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_roots.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        "test_output/test_roots.mmd",
+        "--link",
+        "com.example.UmlAssociate",
+        "--roots",
+        "Shop",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_roots.mmd").expect("Failed to read output");
+
+    // Shop links to Order (the map's value type), User is never reached
+    assert!(content.contains("class Shop"), "Root should be kept");
+    assert!(
+        content.contains("class Order"),
+        "Reachable class should be kept"
+    );
+    assert!(
+        !content.contains("class User"),
+        "Unreachable class should be pruned"
+    );
+}
+
+#[test]
+fn test_annotation_dependencies() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_annotation_dependencies.mmd",
+        "-c",
+        "test_data/class/com/example/tagged",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_annotation_dependencies.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("AuditedService ..> Audited"),
+        "Annotated class should depend on the loaded annotation type"
+    );
+    assert!(
+        !content.contains("PlainService ..>"),
+        "Class without annotations should have no dependencies"
+    );
+    assert_eq!(
+        content.matches("..> Audited").count(),
+        1,
+        "Annotation dependencies should not be duplicated"
+    );
+}
+
+#[test]
+fn test_infer_dependencies() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_infer_dependencies.mmd",
+        "-c",
+        "test_data/class/com/example/scanner",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_infer_dependencies.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("Scanner ..> QRGenerator\n"),
+        "A parameter type should become a dependency:\n{}",
+        content
+    );
+    assert_eq!(
+        content.matches("Scanner ..> Code\n").count(),
+        1,
+        "A return type used by several methods should be one dependency:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Scanner -- Camera\n") && !content.contains("Scanner ..> Camera"),
+        "A type the class already relates to should not also be a dependency:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("..> String") && !content.contains("..> List"),
+        "Types that weren't loaded should not become dependencies:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("Scanner ..> Scanner"),
+        "A class should not depend on itself:\n{}",
+        content
+    );
+
+    // Without the option, method signatures add no relations
+    let output_file = "test_output/test_no_inferred_dependencies.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/scanner",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+    assert!(output.status.success());
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("..>"),
+        "Dependencies should only be inferred when asked for:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_keep_seed_classes() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_keep_seed_classes.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "test_data/input/test_keep_seed_classes.mmd",
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("class PaymentGateway"),
+        "A seed class without a classfile should be kept:\n{}",
+        content
+    );
+    assert!(
+        content.contains("+area() double") && !content.contains("bogus"),
+        "A seed class with a classfile should be regenerated from it:\n{}",
+        content
+    );
+    assert_eq!(
+        content.matches("class Circle").count(),
+        1,
+        "A regenerated class should not be declared twice:\n{}",
+        content
+    );
+    assert!(content.contains("Circle ..> PaymentGateway : bills"));
+
+    // Without the option the seed's classes are discarded
+    let seed = fs::read_to_string("test_data/input/test_keep_seed_classes.mmd")
+        .expect("Failed to read input file")
+        .replace("keepSeedClasses: true", "keepSeedClasses: false");
+    let seed_file = "test_output/test_discard_seed_classes_input.mmd";
+    fs::write(seed_file, seed).expect("Failed to write input file");
+    let output_file = "test_output/test_discard_seed_classes.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        seed_file,
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+    assert!(output.status.success());
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("class PaymentGateway"),
+        "Seed classes should be discarded by default:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_hide_lombok_generated_members() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_hide_lombok.mmd",
+        "-c",
+        "test_data/class/com/example/lombok",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_hide_lombok.mmd").expect("Failed to read output");
+
+    assert!(
+        content.contains("-name: String"),
+        "Declared field should remain"
+    );
+    assert!(
+        content.contains("+greet()"),
+        "Declared method should remain"
+    );
+    assert!(
+        !content.contains("getName") && !content.contains("setName"),
+        "Lombok generated accessors should be hidden"
+    );
+    assert!(
+        !content.contains("hashCode"),
+        "Lombok generated hashCode should be hidden"
+    );
+}
+
+#[test]
+fn test_show_annotations_with_defaults() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_show_annotations.mmd",
+        "-c",
+        "test_data/class/com/example/settings",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_show_annotations.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("<<annotation>> Setting"),
+        "Annotation type should be shown with the annotation stereotype"
+    );
+    assert!(
+        content.contains("+priority() int = 5"),
+        "Defaulted element should show its default value"
+    );
+    assert!(
+        content.contains("+description() String = \"none\""),
+        "String defaults should be quoted"
+    );
+    assert!(
+        content.contains("+key() String\n"),
+        "Element without a default should render without one"
+    );
+    assert!(
+        !content.contains("..|> Annotation"),
+        "The implicit Annotation interface should not be drawn"
+    );
+}
+
+#[test]
+fn test_constant_field_values() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_constant_values.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/settings/Limits.class",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    for expected in [
+        "+MAX: int = 100$",
+        "+NAME: String = \"rocket\"$",
+        "+ENABLED: boolean = true$",
+        "+SEPARATOR: char = ':'$",
+        "+TIMEOUT: long = 30000$",
+    ] {
+        assert!(
+            content.contains(expected),
+            "Expected constant `{}`:\n{}",
+            expected,
+            content
+        );
+    }
+    assert!(
+        content.contains("+BOXED: Integer$") && content.contains("+counter: int$"),
+        "Fields without a ConstantValue attribute should render as before:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_deprecated_markers() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_deprecated.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/legacy",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    for expected in [
+        "<<interface, deprecated>> OldApi",
+        "+LIMIT: int = 10 (deprecated)$",
+        "+reset()* void (deprecated)",
+        "+run()* void\n",
+        "+token: String (deprecated)",
+        "+name: String\n",
+        "+connect() void (deprecated)",
+    ] {
+        assert!(
+            content.contains(expected),
+            "Expected `{}`:\n{}",
+            expected,
+            content
+        );
+    }
+    assert!(
+        !content.contains("<<deprecated>> Client"),
+        "Classes which aren't deprecated shouldn't be marked:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_show_constructors() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_show_constructors.mmd",
+        "-c",
+        "test_data/class/com/example/pizza",
+        "-c",
+        "test_data/class/com/example/storage",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_show_constructors.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains("-Pizza(") && content.contains(": Pizza.Builder)\n"),
+        "Constructors should be named after the class, without a return type:\n{}",
+        content
+    );
+    assert!(
+        content.contains("+Builder()\n") && content.contains("+FileStore()\n"),
+        "Implicit constructors should be shown, nested ones by their simple name:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("<init>") && !content.contains("<clinit>"),
+        "Only constructors should be added:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_label_only_annotation_has_no_cardinalities() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_label_only.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/library",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Library -- Book : lends\n"),
+        "A relation with only a label should have no cardinalities:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Library \"1\" -- \"1\" Book : latest\n"),
+        "Explicit cardinalities should be kept:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_collection_fields_have_many_cardinality() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_collection_cardinality.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/library",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Library -- \"*\" Book : shelf\n"),
+        "An array field should relate to its element type with many cardinality:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Library -- \"*\" Book : reserved\n"),
+        "A List field should relate to its element type with many cardinality:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Library -- \"0..5\" Book : holds\n"),
+        "An explicit otherCard should override the inferred cardinality:\n{}",
+        content
+    );
+    assert!(
+        !content.contains(": counts"),
+        "A primitive array should not create a relation:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_skeleton_keeps_only_hierarchy() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_skeleton.mmd",
+        "-c",
+        "test_data/class/com/example/io",
+        "-o",
+        "test_output",
+        "--aggregate",
+        "com.example.UmlAggregate",
+        "--skeleton",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_skeleton.mmd").expect("Failed to read output file");
+
+    assert!(
+        content.contains("Keyboard --|> IODevice"),
+        "Inheritance edges should be kept"
+    );
+    assert!(content.contains("..|>"), "Realization edges should be kept");
+    assert!(
+        !content.contains("-->") && !content.contains("--o"),
+        "Associations and aggregations should be dropped"
+    );
+    assert!(!content.contains("press("), "Members should be dropped");
+}
+
+#[test]
+fn test_custom_relations() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_custom_relations.mmd",
+        "-c",
+        "test_data/class/com/example/events",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_custom_relations.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("OrderService <..> EventBus : order events"),
+        "Custom relation should be drawn with its configured arrow"
+    );
+    assert!(
+        !content.contains("-bus: EventBus"),
+        "Field with a custom relation annotation should not be listed as a member"
+    );
+}
+
+#[test]
+fn test_explain_class() {
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/io",
+        "-o",
+        "test_output",
+        "--aggregate",
+        "com.example.UmlAggregate",
+        "--explain",
+        "Keyboard",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        report.contains("fully qualified name: com.example.io.Keyboard"),
+        "Report should include the resolved class name:\n{}",
+        report
+    );
+    assert!(
+        report.contains("keys: excluded (drawn as a relation by @com.example.UmlAggregate)"),
+        "Report should give the reason a member was filtered:\n{}",
+        report
+    );
+    assert!(
+        report.contains("Keyboard --|> IODevice (superclass)"),
+        "Report should give the source of each relation:\n{}",
+        report
+    );
+
+    // Members are reported as the diagram filters them
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/io",
+        "-o",
+        "test_output",
+        "--visibility",
+        "public",
+        "--explain",
+        "KeyCode",
+    ])
+    .expect("Failed to execute umlink");
+    let report = String::from_utf8_lossy(&output.stdout);
+    for decision in [
+        "$VALUES: excluded (generated by the compiler)",
+        "values: excluded (implicit enum method)",
+        "toAscii: included",
+        "A: included",
+    ] {
+        assert!(
+            report.contains(decision),
+            "Report should include `{}`:\n{}",
+            decision,
+            report
+        );
+    }
+
+    // Relations to a class renamed to tell it apart are traced to their field
+    let output = run_umlink(&[
+        "test_data/input/test_disambiguate.mmd",
+        "-c",
+        "test_data/duplicates/class",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+        "--explain",
+        "Screen",
+    ])
+    .expect("Failed to execute umlink");
+    let report = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        report.contains("Screen -- Config_ui (field `config` annotated @com.example.UmlAssociate)"),
+        "Report should trace relations to renamed classes:\n{}",
+        report
+    );
+}
+
+#[test]
+fn test_generate_from_bytes() {
+    let read_class = |name: &str| {
+        let path = format!("test_data/class/com/example/shapes/{name}.class");
+        (
+            name.to_string(),
+            fs::read(&path).expect("Failed to read fixture classfile"),
+        )
+    };
+
+    let output = umlink::generate_from_bytes(
+        vec![read_class("Shape"), read_class("Circle")],
+        Some("classDiagram\n"),
+        &umlink::MergedConfig::default(),
+    )
+    .expect("Failed to generate diagram from bytes");
+
+    assert!(output.starts_with("classDiagram\n"));
+    assert!(
+        output.contains("class Shape"),
+        "Classes should come from the in-memory bytes"
+    );
+    assert!(
+        output.contains("Circle --|> Shape"),
+        "Relations should be derived like for classfiles on disk"
+    );
+
+    let error = umlink::generate_from_bytes(
+        vec![("Broken".to_string(), vec![0xCA, 0xFE])],
+        None,
+        &umlink::MergedConfig::default(),
+    );
+    assert!(error.is_err(), "Unparseable bytes should be an error");
+}
+
+#[test]
+fn test_link_diagram() {
+    let classfiles: std::collections::BTreeMap<String, jclassfile::class_file::ClassFile> =
+        ["Shape", "Circle"]
+            .into_iter()
+            .map(|name| {
+                let bytes = fs::read(format!("test_data/class/com/example/shapes/{name}.class"))
+                    .expect("Failed to read fixture classfile");
+                let classfile =
+                    jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+                (name.to_string(), classfile)
+            })
+            .collect();
+
+    let output = umlink::link_diagram(
+        &classfiles,
+        Some("classDiagram\nShape ..> Circle : draws %% @manual\n"),
+        &umlink::MergedConfig::default(),
+    )
+    .expect("Failed to link diagram");
+
+    assert!(
+        output.contains("class Circle") && output.contains("Circle --|> Shape"),
+        "Classes and relations should come from the loaded classfiles:\n{}",
+        output
+    );
+    assert!(
+        output.contains("Shape ..> Circle : draws %% @manual"),
+        "Manual relations in the seed should be kept:\n{}",
+        output
+    );
+
+    let output = umlink::link_diagram(&classfiles, None, &umlink::MergedConfig::default())
+        .expect("Failed to link diagram without a seed");
+    assert!(output.starts_with("classDiagram\n"), "{}", output);
+
+    // The binary's reshaping flags are options of the library's linking
+    let options = umlink::LinkOptions {
+        skeleton: true,
+        roots: vec!["Shape".to_string()],
+        roots_direction: umlink::graph::Direction::Incoming,
+        ..Default::default()
+    };
+    let linked = umlink::link_classfiles(
+        &classfiles,
+        umlink::diagram::Diagram::default(),
+        Vec::new(),
+        &umlink::MergedConfig::default(),
+        &options,
+    );
+    assert!(umlink::graph::has_class(&linked.diagram, "Circle"));
+    assert!(
+        linked
+            .diagram
+            .namespaces
+            .values()
+            .flat_map(|namespace| namespace.classes.values())
+            .all(|class| class.members.is_empty()),
+        "Skeletons should have no members: {:?}",
+        linked.diagram
+    );
+}
+
+#[test]
+fn test_signature_wildcards() {
+    use umlink::classfile_utils::{get_signature, get_utf8};
+    use umlink::descriptor::parse_signature_type;
+
+    let bytes = fs::read("test_data/class/com/example/wildcards/Registry.class")
+        .expect("Failed to read fixture classfile");
+    let classfile = jclassfile::class_file::parse(&bytes).expect("Failed to parse classfile");
+    let constant_pool = classfile.constant_pool();
+
+    let field_types: Vec<String> = classfile
+        .fields()
+        .iter()
+        .filter_map(|field| get_signature(constant_pool, field.attributes()))
+        .map(|signature| parse_signature_type(signature, 0, false).0)
+        .collect();
+    assert_eq!(
+        field_types,
+        ["List<+Number>", "Comparator<-Integer>", "Map<String, *>"]
+    );
+
+    let copy = classfile
+        .methods()
+        .iter()
+        .find(|method| get_utf8(constant_pool, method.name_index()) == Some("copy"))
+        .expect("Fixture should have a copy method");
+    let signature =
+        get_signature(constant_pool, copy.attributes()).expect("copy should have a signature");
+    let (into, consumed) = parse_signature_type(signature, 1, false);
+    let (from, _) = parse_signature_type(signature, 1 + consumed, false);
+    assert_eq!(into, "List<-T>");
+    assert_eq!(from, "Collection<+T>");
+}
+
+#[test]
+fn test_generic_member_types() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_generic_member_types.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/wildcards",
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        "test_output/test_generic_member_types.mmd",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_generic_member_types.mmd")
+        .expect("Failed to read output file");
+
+    for member in [
+        "-ordersByUser: Map~User, Order~",
+        "-readings: List~+Number~",
+        "+copy(arg0: List~-T~, arg1: Collection~+T~) void",
+    ] {
+        assert!(
+            content.contains(member),
+            "Member types should keep their type arguments, expected `{}`:\n{}",
+            member,
+            content
+        );
+    }
+}
+
+#[test]
+fn test_load_classfiles_from_jar() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_jar.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/jars/jarred.jar",
+        "-o",
+        "test_output/test_jar.mmd",
+    ])
+    .expect("Failed to execute umlink");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains(
+            "failed to parse `test_data/jars/jarred.jar!com/example/jarred/Broken.class`"
+        ),
+        "Unparseable entries should be warned about:\n{}",
+        stderr
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_jar.mmd").expect("Failed to read output file");
+    assert!(
+        content.contains("class Ticker {"),
+        "Classes inside the jar should be loaded:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("Ticker.1") && !content.contains("Broken"),
+        "Anonymous classes and unparseable entries should be skipped:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_varargs_parameters() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_varargs.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/logging",
+        "-o",
+        "test_output/test_varargs.mmd",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_varargs.mmd").expect("Failed to read output file");
+    for method in [
+        "+log(arg0: String, arg1: Object...) void",
+        "+logAll(arg0: String[]) void",
+        "+table(arg0: int[]...) void",
+    ] {
+        assert!(
+            content.contains(method),
+            "Only varargs parameters should use `...`, expected `{}`:\n{}",
+            method,
+            content
+        );
+    }
+}
+
+#[test]
+fn test_record_components() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_record.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/geometry",
+        "-o",
+        "test_output/test_record.mmd",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_record.mmd").expect("Failed to read output file");
+    assert!(
+        content.contains(
+            "class Point {\n  x: int\n  y: int\n  labels: List~String~\n  +ORIGIN: Point$\n  +distanceTo(arg0: Point) double\n}\n<<record>> Point"
+        ),
+        "Record should list its components and only its own methods:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("--|> Record"),
+        "The implicit java.lang.Record supertype should be left out:\n{}",
+        content
+    );
+
+    // The canonical constructor only repeats the components, so only the
+    // other constructors are shown
+    let output_file = "test_output/test_record_constructors.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "test_data/input/test_show_constructors.mmd",
+        "-c",
+        "test_data/class/com/example/geometry",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("  +Point(arg0: int, arg1: int)\n")
+            && !content.contains("+Point(arg0: int, arg1: int, arg2"),
+        "Only the non-canonical constructor should be listed:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_select_match_modes() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    for (input, includes_subpackage) in [("test_select_prefix", true), ("test_select_exact", false)]
+    {
+        let output = run_umlink(&[
+            &format!("test_data/input/{}.mmd", input),
+            "-c",
+            "test_data/class/com/example/TestClass.class",
+            "-c",
+            "test_data/class/com/example/shapes",
+            "-o",
+            "test_output",
+        ])
+        .expect("Failed to execute umlink");
+
+        assert!(
+            output.status.success(),
+            "umlink exited with non-zero status: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let content = fs::read_to_string(format!("test_output/{}.mmd", input))
+            .expect("Failed to read output file");
+        assert!(
+            content.contains("class TestClass"),
+            "{}: classes in the selected package should be included:\n{}",
+            input,
+            content
+        );
+        assert_eq!(
+            content.contains("class Circle"),
+            includes_subpackage,
+            "{}: only prefix matching should include subpackages:\n{}",
+            input,
+            content
+        );
+    }
+}
+
+#[test]
+fn test_select_glob_patterns() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_select_glob.mmd",
+        "-c",
+        "test_data/class/com/example/TestClass.class",
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Invalid select pattern `com.*.shapes[A-`"),
+        "An invalid pattern should be reported:\n{}",
+        stderr
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_select_glob.mmd").expect("Failed to read output file");
+    assert!(
+        content.contains("class Circle"),
+        "Classes matching the name glob should be included:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("class Shape") && !content.contains("class TestClass"),
+        "Only classes matching the name glob should be included:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_select_and_exclude() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    for (input, expected) in [
+        ("test_select_prefix", [true, true, true]),
+        ("test_exclude", [true, false, false]),
+        ("test_select_exclude", [true, true, false]),
+    ] {
+        // Written to its own file as other tests share these inputs
+        let output_file = format!("test_output/{}_exclusion.mmd", input);
+        let _ = fs::remove_file(&output_file);
+        let output = run_umlink(&[
+            &format!("test_data/input/{}.mmd", input),
+            "-c",
+            "test_data/class/com/example/TestClass.class",
+            "-c",
+            "test_data/class/com/example/shapes",
+            "-o",
+            &output_file,
+        ])
+        .expect("Failed to execute umlink");
+
+        assert!(
+            output.status.success(),
+            "umlink exited with non-zero status: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let content = fs::read_to_string(&output_file).expect("Failed to read output file");
+        for (class, included) in ["TestClass", "Circle", "Shape"].iter().zip(expected) {
+            assert_eq!(
+                content.contains(&format!("class {} ", class)),
+                included,
+                "{}: unexpected inclusion of {}:\n{}",
+                input,
+                class,
+                content
+            );
+        }
+    }
+}
+
+#[test]
+fn test_mark_external_supertype() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Only load the subclass so its supertype is an external type
+    let output = run_umlink(&[
+        "test_data/input/test_mark_external.mmd",
+        "-c",
+        "test_data/class/com/example/shapes/Circle.class",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_mark_external.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("Circle --|> Shape"),
+        "Relation to the external supertype should be kept"
+    );
+    assert!(
+        content.contains("<<external>> Shape"),
+        "External supertype should be marked"
+    );
+    assert!(
+        !content.contains("<<external>> Circle"),
+        "Loaded classes should not be marked external"
+    );
+}
+
+#[test]
+fn test_stub_referenced_types() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Only load the subclass so its supertype is only referenced by a relation
+    let output = run_umlink(&[
+        "test_data/input/test_stub_referenced_types.mmd",
+        "-c",
+        "test_data/class/com/example/shapes/Circle.class",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_stub_referenced_types.mmd")
+        .expect("Failed to read output file");
+
+    assert_eq!(
+        content.matches("class Shape\n").count(),
+        1,
+        "External supertype should get exactly one stub:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("class Circle\n"),
+        "Loaded classes should keep their own definition:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_inner_separator() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_inner_separator.mmd",
+        "-c",
+        "test_data/class/com/example/tree",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_inner_separator.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("class Tree_Node {") && content.contains("class Tree_Leaf {"),
+        "Inner classes should be declared with the chosen separator:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Tree_Leaf --|> Tree_Node"),
+        "Relation ends should use the same separator:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("class Tree.") && !content.contains("Tree.Leaf --|>"),
+        "No class or relation end should keep the `.` separator:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_thrown_exceptions() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_thrown_exceptions.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/storage",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("String) void throws IOException"),
+        "A declared exception should follow the return type:\n{}",
+        content
+    );
+    assert!(
+        content.contains("String) String throws IOException, TimeoutException"),
+        "Every declared exception should be listed:\n{}",
+        content
+    );
+    assert!(
+        content.contains("+clear() void\n"),
+        "Methods without exceptions should render as before:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_nested_class_containment() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_nested_classes.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&["-c", "test_data/class/com/example/tree", "-o", output_file])
+        .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Tree --* `Tree.Node`") && content.contains("Tree --* `Tree.Leaf`"),
+        "The outer class should compose its nested classes:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("`Tree.Leaf` --* `Tree.Node`") && !content.contains("Tree.1"),
+        "Only directly nested, named classes should be contained:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_nest_in_namespace() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_nest_in_namespace.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "test_data/input/test_nest_in_namespace.mmd",
+        "-c",
+        "test_data/class/com/example/tree",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    let start = content
+        .find("namespace Tree {")
+        .unwrap_or_else(|| panic!("Nested classes should get a namespace:\n{}", content));
+    // The namespace closes right after its last class
+    let end = start + content[start..].find("}\n}\n").expect("Unclosed namespace");
+    let namespace = &content[start..end];
+    assert!(
+        namespace.contains("class `Tree.Leaf`") && namespace.contains("class `Tree.Node`"),
+        "Nested classes should be in their outer class's namespace:\n{}",
+        content
+    );
+    assert!(
+        !namespace.contains("class Tree {"),
+        "The outer class should stay in its own namespace:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_disambiguate_duplicate_names() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_disambiguate.mmd",
+        "-c",
+        "test_data/duplicates/class",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_disambiguate.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("class Config {\n  -threads: int"),
+        "First class by package keeps its name"
+    );
+    assert!(
+        content.contains("class Config_ui {\n  -theme: String"),
+        "Later class gets a package suffix"
+    );
+    assert!(
+        content.contains("Engine -- Config\n"),
+        "Relation to the unsuffixed class should be kept"
+    );
+    assert!(
+        content.contains("Screen -- Config_ui\n"),
+        "Relation to the suffixed class should be rewritten"
+    );
+}
+
+#[test]
+fn test_grouped_duplicate_names() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_group_duplicates.mmd",
+        "-c",
+        "test_data/duplicates/class",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "Grouped classes sharing a name should be told apart: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_group_duplicates.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains("namespace ui {\nclass Config_ui {"),
+        "The later class should be named after its namespace:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Config_ui ..> Config : overrides\n"),
+        "Namespace qualified relation ends should point at the right class:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Engine -- Config\n") && content.contains("Screen -- Config_ui\n"),
+        "Generated relations should point at the class in the right namespace:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_duplicate_names_are_an_error() {
+    let output = run_umlink(&["-c", "test_data/duplicates/class", "-o", "test_output"])
+        .expect("Failed to execute umlink");
+
+    assert!(!output.status.success(), "Duplicate names should fail");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Duplicate class name `Config`"),
+        "Error should name the duplicate class"
+    );
+}
+
+#[test]
+fn test_parallel_loading_matches_serial() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let mut outputs = Vec::new();
+    for jobs in ["1", "4"] {
+        let output_file = format!("test_output/test_jobs_{}.mmd", jobs);
+        let _ = fs::remove_file(&output_file);
+        let output = run_umlink(&[
+            "-c",
+            "test_data/class/com/example",
+            "-o",
+            &output_file,
+            "--jobs",
+            jobs,
+        ])
+        .expect("Failed to execute umlink");
+
+        assert!(
+            output.status.success(),
+            "umlink exited with non-zero status: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        outputs.push(fs::read_to_string(&output_file).expect("Failed to read output file"));
+    }
+    assert_eq!(
+        outputs[0], outputs[1],
+        "Loading on several threads should give the same diagram"
+    );
+
+    // Duplicate names found by different threads are still caught
+    let output = run_umlink(&[
+        "-c",
+        "test_data/duplicates/class",
+        "-o",
+        "test_output",
+        "--jobs",
+        "4",
+    ])
+    .expect("Failed to execute umlink");
+    assert!(!output.status.success(), "Duplicate names should fail");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Duplicate class name `Config`"),
+        "Error should name the duplicate class"
+    );
+}
+
+#[test]
+fn test_output_name() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    for (name, file) in [
+        ("architecture", "test_output/architecture.mmd"),
+        ("overview.md", "test_output/overview.md"),
+    ] {
+        let _ = fs::remove_file(file);
+        let output = run_umlink(&[
+            "-c",
+            "test_data/class/com/example/shapes",
+            "-o",
+            "test_output",
+            "--name",
+            name,
+        ])
+        .expect("Failed to execute umlink");
+
+        assert!(
+            output.status.success(),
+            "umlink exited with non-zero status: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let content = fs::read_to_string(file).expect("Output should be named by --name");
+        assert!(content.contains("class Circle"), "{}", content);
+    }
+
+    // A file path keeps its own name, and --name is warned about
+    let file = "test_output/test_output_name_file.mmd";
+    let _ = fs::remove_file(file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        file,
+        "--name",
+        "ignored",
+    ])
+    .expect("Failed to execute umlink");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}", stderr);
+    assert!(
+        stderr.contains("Ignoring --name `ignored`"),
+        "--name with a file path should be warned about:\n{}",
+        stderr
+    );
+    assert!(
+        Path::new(file).exists(),
+        "The --output path should be written"
+    );
+    assert!(!Path::new("test_output/ignored.mmd").exists());
+}
+
+#[test]
+fn test_plantuml_format() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_plantuml.puml";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        "test_output",
+        "--name",
+        "test_plantuml",
+        "--format",
+        "plantuml",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string(output_file).expect("PlantUML output should get a .puml extension");
+    assert!(
+        content.starts_with("@startuml\n") && content.ends_with("@enduml\n"),
+        "PlantUML output should be wrapped in @startuml/@enduml:\n{}",
+        content
+    );
+    assert!(
+        content.contains("abstract class Shape {\n  {abstract} +area() : double\n}"),
+        "Abstract classes and methods should use PlantUML's keywords:\n{}",
+        content
+    );
+    assert!(
+        content.contains("  -radius : double\n"),
+        "Fields should be written as `name : type`:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Circle --|> Shape\n"),
+        "Inheritance should use PlantUML's arrow:\n{}",
+        content
+    );
+    assert!(!content.contains("classDiagram"), "{}", content);
+}
+
+#[test]
+fn test_dot_format() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_dot.dot";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/library",
+        "-o",
+        "test_output",
+        "--name",
+        "test_dot",
+        "--format",
+        "dot",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("DOT output should get a .dot extension");
+    assert!(
+        content.starts_with("digraph classDiagram {\n") && content.ends_with("}\n"),
+        "DOT output should be a digraph:\n{}",
+        content
+    );
+    assert!(
+        content.contains("\"Book\" [label=\"{Book|"),
+        "Classes should be record nodes:\n{}",
+        content
+    );
+    assert!(
+        content.contains("|-title : String\\l|"),
+        "Members should be left justified lines of the record:\n{}",
+        content
+    );
+    assert!(
+        content.contains(
+            "\"Library\" -> \"Book\" [arrowhead=none, headlabel=\"*\", label=\"reserved\"];"
+        ),
+        "Relations should be styled edges with their cardinalities and labels:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_verbose_reports_skips() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_verbose.mmd";
+    let args = [
+        "-c",
+        "test_data/class/com/example/tree",
+        "-c",
+        "test_data/class/com/example/TestClass.class",
+        "-c",
+        "test_data/class/com/example/SkippedClass.class",
+        "-c",
+        "test_data/class/com/example/Skip.class",
+        "-o",
+        output_file,
+        "--skip",
+        "com.example.Skip",
+    ];
+
+    let _ = fs::remove_file(output_file);
+    let output =
+        run_umlink(&[&args[..], &["--verbose"]].concat()).expect("Failed to execute umlink");
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    for expected in [
+        "INFO: Ignored anonymous class `test_data/class/com/example/tree/Tree$1.class`",
+        "INFO: Skipped class `Skip`: annotation type",
+        "INFO: Skipped class `SkippedClass`: class has the skip annotation",
+        "INFO: Dropped member `TestClass.hiddenField`: skipped by @com.example.Skip",
+        "INFO: Dropped member `TestClass.hiddenMethod`: skipped by @com.example.Skip",
+    ] {
+        assert!(
+            stderr.contains(expected),
+            "Missing `{}` in:\n{}",
+            expected,
+            stderr
+        );
+    }
+
+    // Without the flag nothing is reported
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&args).expect("Failed to execute umlink");
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("INFO"), "{}", stderr);
+}
+
+#[test]
+fn test_color_never_has_no_escape_codes() {
+    let args = ["-c", "test_data/duplicates/class", "-o", "test_output"];
+
+    let output =
+        run_umlink(&[&args[..], &["--color", "never"]].concat()).expect("Failed to execute umlink");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ERROR: Duplicate class name"), "{}", stderr);
+    assert!(
+        !stderr.contains('\x1b'),
+        "Diagnostics should be plain with `--color never`:\n{}",
+        stderr
+    );
+
+    let output = run_umlink(&[&args[..], &["--color", "always"]].concat())
+        .expect("Failed to execute umlink");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("\x1b[31mERROR\x1b[0m: "),
+        "Diagnostics should be colored with `--color always`"
+    );
+}
+
+#[test]
+fn test_relations_file() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_relations_file.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        "test_output/test_relations_file.mmd",
+        "--relations-file",
+        "test_data/relations/shop.yml",
+    ])
+    .expect("Failed to execute umlink");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("WARN: Relations file entry 1: `to` class `Ledger` is not loaded"),
+        "Unknown classes should be warned about:\n{}",
+        stderr
+    );
+
+    let content = fs::read_to_string("test_output/test_relations_file.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains("Shop ..> User : notifies"),
+        "Relation from the file should be added:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_manual_relations_survive_overwrite() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_manual_relations.mmd",
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+        "--overwrite-relations",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_manual_relations.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains("Shop ..> User : notifies %% @manual\n"),
+        "Manual relation should be kept verbatim:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("Ledger"),
+        "Unmarked relations should be regenerated:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Shop -- \"*\" Order"),
+        "Generated relations should still be added:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_relations_file_rejects_unknown_kind() {
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        "test_output",
+        "--relations-file",
+        "test_data/relations/unknown_kind.yml",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("entry 0: unknown `kind` value `extends`"),
+        "Error should name the entry and the bad kind"
+    );
+}
+
+#[test]
+fn test_show_inherited_methods() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_show_inherited.mmd",
+        "-c",
+        "test_data/class/com/example/vehicles",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_show_inherited.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains(
+            "class SportsCar {\n  +boost() void\n  «inherited from Car»\n  +wheels() int\n  «inherited from Vehicle»\n  +describe() String\n  #honk() void\n}"
+        ),
+        "Inherited methods should be grouped by superclass without overridden, private or static methods:\n{}",
+        content
+    );
+
+    // Without the flag classes only list their own methods
+    let _ = fs::remove_file("test_output/test_show_inherited_off.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/vehicles",
+        "-o",
+        "test_output/test_show_inherited_off.mmd",
+    ])
+    .expect("Failed to execute umlink");
+    assert!(output.status.success());
+
+    let content = fs::read_to_string("test_output/test_show_inherited_off.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        !content.contains("«inherited"),
+        "Inherited methods should only be shown under the flag:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_config_profiles() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let root = Path::new(manifest_dir).join("test_output/config_profiles");
+    fs::create_dir_all(&root).expect("Failed to create config directory");
+    fs::write(
+        root.join("umlink.yml"),
+        "profiles:\n  default:\n    skip: com.example.Skip\n  retention:\n    skip: com.example.SkipClass\n",
+    )
+    .expect("Failed to write config file");
+
+    let classfiles = format!("{manifest_dir}/test_data/class/com/example");
+    let output_file = root.join("output.mmd");
+
+    // Without --profile the default profile is used
+    let _ = fs::remove_file(&output_file);
+    let output = run_umlink_in(&root, &["-c", &classfiles, "-o", "output.mmd"])
+        .expect("Failed to execute umlink");
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(&output_file).expect("Failed to read output file");
+    assert!(
+        !content.contains("hiddenField: String"),
+        "Default profile should skip members marked @Skip"
+    );
+    assert!(
+        content.contains("hiddenFieldWithClassRetention"),
+        "Default profile should keep members marked @SkipClass"
+    );
+
+    let _ = fs::remove_file(&output_file);
+    let output = run_umlink_in(
+        &root,
+        &[
+            "-c",
+            &classfiles,
+            "-o",
+            "output.mmd",
+            "--profile",
+            "retention",
+        ],
+    )
+    .expect("Failed to execute umlink");
+    assert!(output.status.success());
+
+    let content = fs::read_to_string(&output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("hiddenField: String"),
+        "Selected profile should replace the default one"
+    );
+    assert!(
+        !content.contains("hiddenFieldWithClassRetention"),
+        "Selected profile should skip members marked @SkipClass"
+    );
+
+    let output = run_umlink_in(
+        &root,
+        &[
+            "-c",
+            &classfiles,
+            "-o",
+            "output.mmd",
+            "--profile",
+            "missing",
+        ],
+    )
+    .expect("Failed to execute umlink");
+    assert_eq!(output.status.code(), Some(6));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Unknown profile `missing`"),
+        "Unknown profiles should be reported"
+    );
+}
+
+#[test]
+fn test_member_links() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_member_links.mmd",
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_member_links.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("%% link Circle.area() \"com/example/shapes/Circle.java#L7\"\n"),
+        "Method should link to its source line:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("Shape.area()"),
+        "Abstract methods have no line to link to:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_annotation_precedence() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Aggregate wins by default
+    let _ = fs::remove_file("test_output/test_annotation_precedence_default.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/garage",
+        "-o",
+        "test_output/test_annotation_precedence_default.mmd",
+        "--aggregate",
+        "com.example.UmlAggregate",
+        "--compose",
+        "com.example.UmlCompose",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_annotation_precedence_default.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains("Garage --o Engine"),
+        "Aggregate should take precedence by default:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("--*"),
+        "Only one relation should be drawn"
+    );
+
+    // Listing compose first makes it win
+    let output = run_umlink(&[
+        "test_data/input/test_annotation_precedence.mmd",
+        "-c",
+        "test_data/class/com/example/garage",
+        "-o",
+        "test_output",
+        "--aggregate",
+        "com.example.UmlAggregate",
+        "--compose",
+        "com.example.UmlCompose",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(output.status.success());
+
+    let content = fs::read_to_string("test_output/test_annotation_precedence.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains("Garage --* Engine"),
+        "Compose should take precedence when listed first:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("--o"),
+        "Only one relation should be drawn"
+    );
+}
+
+#[test]
+fn test_extension_api() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_extension_api.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/widgets",
+        "-o",
+        "test_output/test_extension_api.mmd",
+        "--extension-api",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_extension_api.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains(
+            "class Button {\n  +click() void\n  #layout() void\n  «inherited from Widget»\n  #width: int\n  +draw() void\n}"
+        ),
+        "Subclass should show its own and the inherited protected surface:\n{}",
+        content
+    );
+    assert!(
+        content.contains("class Widget {\n  #width: int\n  +draw() void\n  #layout() void\n}"),
+        "Private and package private members should be hidden:\n{}",
+        content
+    );
+    assert!(
+        content.contains("<<final>> Button"),
+        "Final classes should be marked:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_weighted_edges() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_weighted_edges.mmd",
+        "-c",
+        "test_data/class/com/example/reports",
+        "-o",
+        "test_output",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_weighted_edges.mmd")
+        .expect("Failed to read output file");
+
+    assert_eq!(
+        content.matches("Report -- ").count(),
+        1,
+        "Fields referencing the same class should collapse into one edge:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Report -- Chart : 3\n"),
+        "Edge should be labelled with the number of fields:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_module_graph() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let _ = fs::remove_file("test_output/test_module_graph.mmd");
+    let output = run_umlink(&[
+        "-c",
+        "test_data/modules/class",
+        "-o",
+        "test_output/test_module_graph.mmd",
+        "--module-graph",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_module_graph.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("<<module>> com_example_app"),
+        "Module should be a node:\n{}",
+        content
+    );
+    assert!(
+        content.contains("com_example_app ..> java_logging : requires\n"),
+        "Requires should be a dependency edge:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("java_base"),
+        "The implicit java.base dependency should be left out:\n{}",
+        content
+    );
+    assert!(
+        content.contains("exports com.example.app.api"),
+        "Exported packages should be noted:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("class App"),
+        "Classes should not be drawn in the module graph:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_collapse_builders() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_collapse_builders.mmd",
+        "-c",
+        "test_data/class/com/example/pizza",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_collapse_builders.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains(
+            "class `Pizza.Builder` {\n  size: String\n  cheese: boolean\n  slices: int\n  +build() Pizza\n}\n<<builder>> `Pizza.Builder`"
+        ),
+        "Fluent setters should collapse into the properties they set:\n{}",
+        content
+    );
+    assert!(
+        content.contains("+getSize() String"),
+        "Classes which aren't builders should be left alone:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("<<builder>> Pizza\n"),
+        "Only the builder should be marked:\n{}",
+        content
+    );
+
+    // Thrown exceptions and deprecation don't hide a fluent setter, and a
+    // deprecated builder keeps both stereotypes
+    let _ = fs::remove_file("test_output/test_collapse_deprecated_builders.mmd");
+    let output = run_umlink(&[
+        "test_data/input/test_collapse_builders.mmd",
+        "-c",
+        "test_data/class/com/example/booking",
+        "-o",
+        "test_output/test_collapse_deprecated_builders.mmd",
+    ])
+    .expect("Failed to execute umlink");
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let content = fs::read_to_string("test_output/test_collapse_deprecated_builders.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains(
+            "class `Booking.Builder` {\n  guest: String\n  note: String\n  +build() Booking\n}\n<<builder, deprecated>> `Booking.Builder`"
+        ),
+        "Deprecated builders should collapse too:\n{}",
+        content
+    );
+
+    // Qualified return types still name the builder itself
+    let output = run_umlink(&[
+        "test_data/input/test_collapse_qualified_builders.mmd",
+        "-c",
+        "test_data/class/com/example/pizza",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let content = fs::read_to_string("test_output/test_collapse_qualified_builders.mmd")
+        .expect("Failed to read output file");
+    assert!(
+        content.contains(
+            "  slices: int\n  +build() com.example.pizza.Pizza\n}\n<<builder>> `Pizza.Builder`"
+        ),
+        "Builders should collapse with qualifiedTypes:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_complexity_badges() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_complexity_badges.mmd",
+        "-c",
+        "test_data/class/com/example/grading",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_complexity_badges.mmd")
+        .expect("Failed to read output file");
+
+    // Two methods, one of which has three branches
+    assert!(
+        content.contains("note for Grader \"complexity: 5\"\n"),
+        "Grader should be badged with its method count plus its branches:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_record_inputs() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_record_inputs.mmd",
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        "test_output",
+        "--skip",
+        "com.example.Skip",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_record_inputs.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains(
+            "  generatedFrom:\n    classfiles:\n    - test_data/class/com/example/shapes\n    config:\n      skip: com.example.Skip\n    timestamp: "
+        ),
+        "Output should record how it was generated:\n{}",
+        content
+    );
+
+    // The output is valid input, and its provenance is replaced on the next run
+    fs::create_dir_all("test_output/record_inputs").expect("Failed to create output directory");
+    let output = run_umlink(&[
+        "test_output/test_record_inputs.mmd",
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        "test_output/record_inputs",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "Output with provenance should parse as input: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let rerun = fs::read_to_string("test_output/record_inputs/test_record_inputs.mmd")
+        .expect("Failed to read output file");
+    assert_eq!(rerun.matches("generatedFrom").count(), 1);
+    assert!(
+        !rerun.contains("skip: com.example.Skip"),
+        "Provenance should describe the latest run:\n{}",
+        rerun
+    );
+    assert!(
+        rerun.contains("class Circle {\n  -radius: double"),
+        "Classes should be emitted as usual:\n{}",
+        rerun
+    );
+}
+
+#[test]
+fn test_max_members() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_max_members.mmd",
+        "-c",
+        "test_data/class/com/example/settings",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content =
+        fs::read_to_string("test_output/test_max_members.mmd").expect("Failed to read output file");
+
+    // Limits declares seven constants, only the first three are listed
+    assert!(
+        content.contains("  ... 4 more\n}"),
+        "Limits should summarize its remaining fields:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("SEPARATOR"),
+        "Fields past the limit should be left out:\n{}",
+        content
+    );
+    assert!(
+        content.contains("class AppSettings {\n  -theme: String\n}"),
+        "Classes within the limit should be unchanged:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_note_annotation() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_note_annotation.mmd",
+        "-c",
+        "test_data/class/com/example/notes",
+        "-o",
+        "test_output",
+        "--note",
+        "com.example.notes.DiagramNote",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_note_annotation.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("note for AuthService \"Handles #quot;auth#quot; for every request\"\n"),
+        "AuthService should get the text of its note annotation:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("note for Session"),
+        "Classes without the annotation shouldn't get a note:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_visibility_threshold() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_visibility_threshold.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/bank",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+        "--visibility",
+        "protected",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+
+    assert!(
+        content
+            .contains("class Account {\n  #balance: int\n  +getBalance() int\n  #audit() void\n}"),
+        "Only public and protected members should be listed:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Account -- Owner : owned by"),
+        "Relations from public fields should be kept:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("Account -- Ledger"),
+        "Relations from private fields should be left out:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_check_does_not_write_output() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_check.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/library",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+        "--check",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Checked 2 classes and 5 relations, 0 classes skipped"),
+        "A summary should be printed:\n{}",
+        stdout
+    );
+    assert!(
+        !Path::new(output_file).exists(),
+        "No output should be written when checking"
+    );
+
+    // The summary counts what would be written, and is a status line
+    // `--quiet` silences
+    let check = |extra: &[&str]| {
+        let mut args = vec![
+            "-c",
+            "test_data/class/com/example/shapes",
+            "-c",
+            "test_data/class/com/example/io",
+            "-o",
+            output_file,
+            "--roots",
+            "Circle",
+            "--check",
+        ];
+        args.extend_from_slice(extra);
+        let output = run_umlink(&args).expect("Failed to execute umlink");
+        assert!(
+            output.status.success(),
+            "umlink exited with non-zero status: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    let stdout = check(&[]);
+    assert!(
+        stdout.contains("Checked 2 classes and 1 relations, 0 classes skipped"),
+        "Classes pruned by --roots shouldn't be counted:\n{}",
+        stdout
+    );
+    assert_eq!(check(&["--quiet"]), "");
+
+    // Module graphs are summarized the same way
+    let module_check = |extra: &[&str]| {
+        let mut args = vec![
+            "-c",
+            "test_data/modules/class",
+            "-o",
+            output_file,
+            "--module-graph",
+            "--check",
+        ];
+        args.extend_from_slice(extra);
+        let output = run_umlink(&args).expect("Failed to execute umlink");
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    };
+    assert_eq!(module_check(&[]), "Checked 1 modules\n");
+    assert_eq!(module_check(&["--quiet"]), "");
+
+    // Failures to load the inputs are still reported through the exit code
+    let output = run_umlink(&[
+        "test_data/input/missing.mmd",
+        "-c",
+        "test_data/class/com/example/library",
+        "-o",
+        output_file,
+        "--dry-run",
+    ])
+    .expect("Failed to execute umlink");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_default_namespace_name() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_default_namespace_name.mmd",
+        "-c",
+        "test_data/class/com/example/Computer.class",
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_default_namespace_name.mmd")
+        .expect("Failed to read output file");
+
+    // Computer is in the base package `com.example`
+    assert!(
+        content.contains("namespace root {\nclass Computer {"),
+        "Classes in the base package should be in the named namespace:\n{}",
+        content
+    );
+    assert!(
+        content.contains("namespace shapes {"),
+        "Other packages should keep their relative namespace:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_method_relationship_annotations() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_method_relations.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/team",
+        "-o",
+        output_file,
+        "--aggregate",
+        "com.example.UmlAggregate",
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+
+    assert!(
+        content.contains("Team --o \"*\" Player : fields"),
+        "An annotated getter should relate to the element type it returns:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Team -- \"1\" Coach : coached by"),
+        "An annotated getter should relate to the type it returns:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("getCoach") && content.contains("+getName() String"),
+        "Only annotated getters should be drawn as relations instead of members:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_skip_pattern() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_skip_pattern.mmd",
+        "-c",
+        "test_data/class/com/example/bank",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("Invalid skipPattern `[oops`"),
+        "Invalid patterns should be warned about:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_skip_pattern.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("class Account {"),
+        "Classes not matching a pattern should be kept:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("class Ledger") && !content.contains("class Owner"),
+        "Classes matching a pattern should be skipped:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_qualified_types() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_qualified_types.mmd",
+        "-c",
+        "test_data/class/com/example/shop",
+        "-c",
+        "test_data/class/com/example/Computer.class",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_qualified_types.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains("-name: java.lang.String\n"),
+        "Member types should keep their package:\n{}",
+        content
+    );
+    assert!(
+        content.contains(
+            "-ordersByUser: java.util.Map~com.example.shop.User, com.example.shop.Order~\n"
+        ),
+        "Type arguments should keep their package:\n{}",
+        content
+    );
+    assert!(
+        content.contains("-state: com.example.Computer.State\n"),
+        "Nested classes should be qualified by their outer class:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_merge_diagram_fragments() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_fragments.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "test_data/input/test_fragment_orders.mmd",
+        "test_data/input/test_fragment_users.mmd",
+        "-c",
+        "test_data/class/com/example/shop",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("`umlink.maxMembers` is set differently"),
+        "Conflicting frontmatter should be warned about:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("maxMembers: 10"),
+        "The last diagram's value should win:\n{}",
+        content
+    );
+    assert!(content.contains("Shop --> Order\n"), "{}", content);
+    assert!(content.contains("Shop --> User\n"), "{}", content);
+    assert_eq!(
+        content.matches("Order --> User\n").count(),
+        1,
+        "Relations declared in both diagrams should be kept once:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_identical_relations_are_drawn_once() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_dedup_relations.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/network",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert_eq!(
+        content.matches("Switch -- Port\n").count(),
+        1,
+        "Two identical annotated fields should produce a single edge:\n{}",
+        content
+    );
+
+    // The library links diagrams the same way
+    let classes = ["Port", "Switch"]
+        .into_iter()
+        .map(|name| {
+            let path = format!("test_data/class/com/example/network/{name}.class");
+            let bytes = fs::read(&path).expect("Failed to read fixture classfile");
+            (name.to_string(), bytes)
+        })
+        .collect();
+    let config = umlink::MergedConfig {
+        link: Some("com.example.UmlAssociate".to_string()),
+        ..Default::default()
+    };
+    let content = umlink::generate_from_bytes(classes, None, &config)
+        .expect("Failed to generate diagram from bytes");
+    assert_eq!(
+        content.matches("Switch -- Port\n").count(),
+        1,
+        "Library output should also draw a single edge:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_source_base_url() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output = run_umlink(&[
+        "test_data/input/test_source_base_url.mmd",
+        "-c",
+        "test_data/class/com/example/Computer.class",
+        "-c",
+        "test_data/class/com/example/Computer$State.class",
+        "-o",
+        "test_output",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string("test_output/test_source_base_url.mmd")
+        .expect("Failed to read output file");
+
+    assert!(
+        content.contains(
+            "click Computer href \"https://example.com/src/com/example/Computer.java\"\n"
+        ),
+        "Classes should link to their source file:\n{}",
+        content
+    );
+    assert!(
+        content.contains(
+            "click `Computer.State` href \"https://example.com/src/com/example/Computer.java\"\n"
+        ),
+        "Nested classes should link to the file of their outer class:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_quiet_prints_only_errors() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Would warn about an invalid pattern, report skipped classes and print
+    // where the output went
+    let output_file = "test_output/test_quiet.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "test_data/input/test_skip_pattern.mmd",
+        "-c",
+        "test_data/class/com/example/bank",
+        "-o",
+        output_file,
+        "--quiet",
+        "--verbose",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !stderr.contains("WARN: ") && !stderr.contains("INFO: "),
+        "Quiet should win over verbose and silence warnings:\n{}",
+        stderr
+    );
+    assert!(
+        !String::from_utf8_lossy(&output.stdout).contains("Successfully wrote"),
+        "Quiet should silence the success line:\n{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(
+        Path::new(output_file).exists(),
+        "The diagram should still be written"
+    );
+
+    let output = run_umlink(&["test_data/input/missing.mmd", "-o", "test_output", "-q"])
+        .expect("Failed to execute umlink");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("ERROR: "),
+        "Errors should still be printed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_sealed_classes() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Cash is permitted by Payment but not loaded
+    let output_file = "test_output/test_sealed.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/payments/Payment.class",
+        "-c",
+        "test_data/class/com/example/payments/Card.class",
+        "-c",
+        "test_data/class/com/example/payments/Fee.class",
+        "-c",
+        "test_data/class/com/example/payments/FlatFee.class",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("<<interface, sealed>> Payment\n"),
+        "Sealed interfaces should be marked:\n{}",
+        content
+    );
+    assert!(
+        content.contains("<<abstract, sealed>> Fee\n"),
+        "Sealed classes should be marked:\n{}",
+        content
+    );
+    assert_eq!(
+        content.matches("Card ..|> Payment\n").count(),
+        1,
+        "Permitted subclasses should be related once:\n{}",
+        content
+    );
+    assert_eq!(
+        content.matches("FlatFee --|> Fee\n").count(),
+        1,
+        "{}",
+        content
+    );
+    assert!(
+        !content.contains("Cash"),
+        "Permitted subclasses which weren't loaded should be left out:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_configured_annotation_kinds() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let config_file = Path::new("test_output/test_annotation_kinds.yml");
+    fs::write(
+        config_file,
+        "link: com.example.UmlAssociate\nkinds:\n  link: composition\n  bogus: dependency\n",
+    )
+    .expect("Failed to write config");
+
+    let output_file = "test_output/test_annotation_kinds.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/library",
+        "--config",
+        config_file.to_str().unwrap(),
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Unknown relationship annotation `bogus` in kinds"),
+        "Unknown annotations should be warned about:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Library --* \"*\" Book : reserved\n"),
+        "Link annotations should create compositions:\n{}",
+        content
+    );
+    assert!(
+        !content.contains("Library -- "),
+        "No link should keep its default kind:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_gzip_diagrams() {
+    use std::io::{Read, Write};
+
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Named without a .gz extension so only the magic bytes give it away
+    let input_file = "test_output/test_gzip_input.mmd";
+    let output_file = "test_output/test_gzip_output.mmd.gz";
+    let _ = fs::remove_file(output_file);
+
+    let source =
+        fs::read("test_data/input/test_source_base_url.mmd").expect("Failed to read input diagram");
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&source).unwrap();
+    fs::write(input_file, encoder.finish().unwrap()).expect("Failed to write gzipped input");
+
+    let output = run_umlink(&[
+        input_file,
+        "-c",
+        "test_data/class/com/example/Computer.class",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let compressed = fs::read(output_file).expect("Failed to read output file");
+    assert!(
+        compressed.starts_with(&[0x1f, 0x8b]),
+        "Output ending in .gz should be gzipped"
+    );
+    let mut content = String::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .read_to_string(&mut content)
+        .expect("Output should decompress");
+
+    assert!(
+        content.contains("class Computer"),
+        "Decompressed output should hold the linked diagram:\n{}",
+        content
+    );
+    assert!(
+        content.contains("click Computer href"),
+        "Frontmatter of the gzipped input should still apply:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_label_from_field_name() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_label_from_field_name.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "test_data/input/test_label_from_field_name.mmd",
+        "-c",
+        "test_data/class/com/example/network",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Switch -- Port : uplink\n"),
+        "Unlabelled annotated fields should be labelled by name:\n{}",
+        content
+    );
+    assert!(
+        content.contains("Switch -- Port : downlink\n"),
+        "Each field should get its own edge:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_member_order() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_member_order.mmd";
+    let generate = || {
+        let _ = fs::remove_file(output_file);
+        let output = run_umlink(&[
+            "test_data/input/test_member_order.mmd",
+            "-c",
+            "test_data/class/com/example/bank",
+            "-o",
+            output_file,
+            "--link",
+            "com.example.UmlAssociate",
+        ])
+        .expect("Failed to execute umlink");
+        assert!(
+            output.status.success(),
+            "umlink exited with non-zero status: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        fs::read_to_string(output_file).expect("Failed to read output file")
+    };
+
+    let content = generate();
+    assert!(
+        content.contains(
+            "class Account {\n  \
+             #balance: int\n  \
+             ~branch: String\n  \
+             +getBalance() int\n  \
+             #audit() void\n  \
+             ~reconcile() void\n  \
+             -log(arg0: String) void\n}"
+        ),
+        "Members should be sorted by visibility, then name:\n{}",
+        content
+    );
+    assert_eq!(
+        content,
+        generate(),
+        "Member order should be stable across runs"
+    );
+}
+
+#[test]
+fn test_classes_ordered_by_full_name() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_ordered_by_full_name.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "test_data/input/test_disambiguate.mmd",
+        "-c",
+        "test_data/duplicates/class",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // `Config_ui` is com.example.dup.ui.Config, so it comes after every
+    // class of com.example.dup.core even though its name sorts before them
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    let position = |needle: &str| {
+        content
+            .find(needle)
+            .unwrap_or_else(|| panic!("`{}` missing from:\n{}", needle, content))
+    };
+    assert!(
+        position("class Config {") < position("class Engine {")
+            && position("class Engine {") < position("class Config_ui {")
+            && position("class Config_ui {") < position("class Screen {"),
+        "Classes should be ordered by fully qualified name:\n{}",
+        content
+    );
+    assert!(
+        position("Engine -- Config\n") < position("Screen -- Config_ui\n"),
+        "Relations should be ordered by the full name of their ends:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_watch_regenerates_on_classfile_changes() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let class_dir = Path::new("test_output/watch_classes");
+    let output_file = "test_output/test_watch.mmd";
+    let _ = fs::remove_dir_all(class_dir);
+    let _ = fs::remove_file(output_file);
+    fs::create_dir_all(class_dir).expect("Failed to create classfile directory");
+    fs::copy(
+        "test_data/class/com/example/shapes/Shape.class",
+        class_dir.join("Shape.class"),
+    )
+    .expect("Failed to copy classfile");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_umlink"))
+        .args([
+            "-c",
+            "test_output/watch_classes",
+            "-o",
+            output_file,
+            "--watch",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to start umlink");
+
+    // Read stdout on its own thread, so waiting for a line can time out
+    let (sender, receiver) = mpsc::channel();
+    let stdout = child.stdout.take().unwrap();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let wait_for = |prefix: &str| loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match receiver.recv_timeout(timeout) {
+            Ok(line) if line.contains(prefix) => break Some(line),
+            Ok(_) => continue,
+            Err(_) => break None,
+        }
+    };
+
+    // Wait until the first diagram is written and the watcher is running
+    let watching = wait_for("Watching");
+    if watching.is_none() {
+        let _ = child.kill();
+    }
+    assert!(
+        watching.is_some(),
+        "umlink should start watching after writing the diagram"
+    );
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(content.contains("class Shape"), "{}", content);
+
+    fs::copy(
+        "test_data/class/com/example/shapes/Circle.class",
+        class_dir.join("Circle.class"),
+    )
+    .expect("Failed to copy classfile");
+
+    // A regeneration may catch the copy half written, in which case the next
+    // one picks up the whole classfile
+    let mut regenerated = None;
+    let mut content = String::new();
+    while let Some(line) = wait_for("Regenerated") {
+        content = fs::read_to_string(output_file).unwrap_or_default();
+        regenerated = Some(line);
+        if content.contains("class Circle") {
+            break;
+        }
+    }
+    child.kill().expect("Failed to stop umlink");
+    let _ = child.wait();
+
+    assert!(
+        content.contains("class Circle") && content.contains("Circle --|> Shape"),
+        "The diagram should be regenerated with the new class:\n{}",
+        content
+    );
+    assert!(
+        regenerated.is_some_and(|line| line.starts_with('[')),
+        "Each regeneration should print a timestamped line"
+    );
+}
+
+#[test]
+fn test_interface_extends_interface() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_interface_hierarchy.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/channels",
+        "-o",
+        output_file,
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    for extension in [
+        "ReadableChannel --|> Channel\n",
+        "WritableChannel --|> Channel\n",
+        "ByteChannel --|> ReadableChannel\n",
+        "ByteChannel --|> WritableChannel\n",
+    ] {
+        assert!(
+            content.contains(extension),
+            "Interfaces should inherit from the interfaces they extend (`{}`):\n{}",
+            extension.trim_end(),
+            content
+        );
+    }
+    assert!(
+        content.contains("FileChannel ..|> ByteChannel\n"),
+        "Classes should still realize the interfaces they implement:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_max_classfile_bytes() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // Shape.class is 224 bytes and Circle.class 338
+    let output_file = "test_output/test_max_classfile_bytes.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/shapes",
+        "-o",
+        output_file,
+        "--max-classfile-bytes",
+        "300",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "Oversized classfiles should be skipped rather than fail: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Circle.class") && stderr.contains("over the limit of 300 bytes"),
+        "Skipping a classfile should be warned about:\n{}",
+        stderr
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(content.contains("class Shape"), "{}", content);
+    assert!(
+        !content.contains("class Circle"),
+        "Classfiles over the limit shouldn't be loaded:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_max_classfile_bytes_unlimited() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    // The largest limit still reads archive entries
+    let output_file = "test_output/test_max_classfile_bytes_unlimited.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/jars/jarred.jar",
+        "-o",
+        output_file,
+        "--max-classfile-bytes",
+        &u64::MAX.to_string(),
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(content.contains("class Ticker {"), "{}", content);
+}
+
+#[test]
+fn test_array_annotation_label() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_array_annotation_label.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/access",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.access.Uses",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Repository -- Database : reads, writes\n"),
+        "An array valued label should be joined with commas:\n{}",
+        content
+    );
+}
+
+#[test]
+fn test_classpath() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_classpath.mmd";
+    let _ = fs::remove_file(output_file);
+    let separator = if cfg!(windows) { ";" } else { ":" };
+    // The doubled separator leaves an empty entry, which is ignored
+    let classpath = [
+        "test_data/class/com/example/shapes",
+        "",
+        "test_data/jars/jarred.jar",
+    ]
+    .join(separator);
+    let output = run_umlink(&["--classpath", &classpath, "-o", output_file])
+        .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    for class in ["class Circle", "class Shape", "class Ticker"] {
+        assert!(
+            content.contains(class),
+            "Every classpath entry should be loaded (`{}` missing):\n{}",
+            class,
+            content
+        );
+    }
+}
+
+#[test]
+fn test_link_and_navigate_to_same_class() {
+    setup_test_output_dir().expect("Failed to create test output directory");
+
+    let output_file = "test_output/test_link_and_navigate.mmd";
+    let _ = fs::remove_file(output_file);
+    let output = run_umlink(&[
+        "-c",
+        "test_data/class/com/example/fleet",
+        "-o",
+        output_file,
+        "--link",
+        "com.example.UmlAssociate",
+        "--navigate",
+        "com.example.UmlNavigate",
+    ])
+    .expect("Failed to execute umlink");
+
+    assert!(
+        output.status.success(),
+        "umlink exited with non-zero status: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // Both fields point at Truck without a label, and are told apart only by
+    // their direction
+    let content = fs::read_to_string(output_file).expect("Failed to read output file");
+    assert!(
+        content.contains("Dispatcher -- Truck\n") && content.contains("Dispatcher --> Truck\n"),
+        "A link and a navigate association to the same class should both be drawn:\n{}",
+        content
+    );
 }